@@ -0,0 +1,128 @@
+// A sample plugin: counts the words of the buffer in a status bar module and adds the
+// `:WordCount` and `:SortLines` commands.
+//
+//     cargo build --example word_count
+//     plugin target/debug/examples/word_count      (in edit.conf, or as `:plugin ...`)
+//
+// Plugins read JSON-RPC 2.0 messages from standard input and write theirs to standard
+// output, one message per line.
+
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+
+use serde_json::{json, Value};
+
+// what the text asked for with `getText` is for
+enum Want {
+    Count,
+    Report,
+    Sort(Option<(usize, usize)>),
+}
+
+struct Plugin {
+    next_id: u64,
+    pending: HashMap<u64, Want>,
+}
+
+impl Plugin {
+    fn send(&self, message: Value) {
+        let mut stdout = std::io::stdout().lock();
+        let _ = writeln!(stdout, "{}", message);
+        let _ = stdout.flush();
+    }
+
+    fn request(&mut self, method: &str, params: Value) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.send(json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }));
+        id
+    }
+
+    fn notify(&self, method: &str, params: Value) {
+        self.send(json!({ "jsonrpc": "2.0", "method": method, "params": params }));
+    }
+
+    fn get_text(&mut self, want: Want) {
+        let id = self.request("getText", json!({}));
+        self.pending.insert(id, want);
+    }
+
+    fn handle(&mut self, message: Value) -> bool {
+        match message["method"].as_str() {
+            Some("initialize") => {
+                self.send(json!({ "jsonrpc": "2.0", "id": message["id"], "result": {} }));
+                self.request("registerCommand", json!({ "name": "WordCount", "help": "Count lines, words and characters" }));
+                self.request("registerCommand", json!({ "name": "SortLines", "help": "Sort the lines of the range" }));
+                self.request("registerModule", json!({ "name": "words", "text": "" }));
+                self.notify("subscribe", json!({ "events": ["BufRead", "TextChanged", "BufWritePost"] }));
+                self.get_text(Want::Count);
+            }
+            Some("event") => self.get_text(Want::Count),
+            Some("command") => {
+                let params = &message["params"];
+                match params["name"].as_str() {
+                    Some("WordCount") => self.get_text(Want::Report),
+                    Some("SortLines") => {
+                        let range = params["range"].as_array().and_then(|r| {
+                            Some((r.first()?.as_u64()? as usize, r.get(1)?.as_u64()? as usize))
+                        });
+                        self.get_text(Want::Sort(range));
+                    }
+                    _ => {}
+                }
+            }
+            Some("shutdown") => return false,
+            Some(_) => {}
+            // the answer to one of our requests
+            None => {
+                let want = message["id"].as_u64().and_then(|id| self.pending.remove(&id));
+                let lines: Vec<String> = match message["result"]["lines"].as_array() {
+                    Some(lines) => lines.iter().filter_map(|l| l.as_str().map(String::from)).collect(),
+                    None => return true,
+                };
+                match want {
+                    Some(Want::Count) => {
+                        let words: usize = lines.iter().map(|l| l.split_whitespace().count()).sum();
+                        self.request("setModuleText", json!({ "name": "words", "text": format!("{} words", words) }));
+                    }
+                    Some(Want::Report) => {
+                        let words: usize = lines.iter().map(|l| l.split_whitespace().count()).sum();
+                        let chars: usize = lines.iter().map(|l| l.chars().count() + 1).sum();
+                        let text = format!("{} lines, {} words, {} characters", lines.len(), words, chars);
+                        self.notify("showMessage", json!({ "text": text }));
+                    }
+                    Some(Want::Sort(range)) => {
+                        // the range is 1-based, without one the whole buffer is sorted
+                        let (start, end) = match range {
+                            Some((first, last)) => (first.saturating_sub(1), last.min(lines.len())),
+                            None => (0, lines.len()),
+                        };
+                        let mut sorted = lines[start.min(end)..end].to_vec();
+                        sorted.sort();
+                        self.request("setText", json!({ "start": start, "end": end, "lines": sorted }));
+                    }
+                    None => {}
+                }
+            }
+        }
+        true
+    }
+}
+
+fn main() {
+    let mut plugin = Plugin { next_id: 1, pending: HashMap::new() };
+
+    for line in std::io::stdin().lock().lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => break,
+        };
+        let message = match serde_json::from_str(&line) {
+            Ok(message) => message,
+            Err(_) => continue,
+        };
+        if !plugin.handle(message) {
+            break;
+        }
+    }
+}
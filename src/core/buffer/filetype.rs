@@ -0,0 +1,34 @@
+use std::path::Path;
+
+// file type of `path` from its name, "" when it is not known
+pub fn detect(path: &str) -> &'static str {
+    let path = Path::new(path.trim());
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+    match name {
+        "Makefile" | "makefile" | "GNUmakefile" => return "make",
+        "Dockerfile" => return "dockerfile",
+        _ => {}
+    }
+
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or_default();
+    match extension {
+        "rs" => "rust",
+        "py" => "python",
+        "go" => "go",
+        "c" | "h" => "c",
+        "cpp" | "cc" | "cxx" | "hpp" | "hh" => "cpp",
+        "js" | "mjs" | "cjs" => "javascript",
+        "ts" => "typescript",
+        "java" => "java",
+        "sh" | "bash" | "zsh" => "sh",
+        "toml" => "toml",
+        "json" => "json",
+        "yml" | "yaml" => "yaml",
+        "md" | "markdown" => "markdown",
+        "html" | "htm" => "html",
+        "css" => "css",
+        "lua" => "lua",
+        "mk" => "make",
+        _ => "",
+    }
+}
@@ -0,0 +1,97 @@
+// state of the buffer before a change, restored by undo
+#[derive(Clone)]
+pub struct Snapshot {
+    pub lines: Vec<String>,
+    pub cursor: (usize, usize), // (line, column)
+}
+
+// undo/redo stacks, changes made between `begin` and the matching `end` form one undo step
+#[derive(Default)]
+pub struct History {
+    undo: Vec<Snapshot>,
+    redo: Vec<Snapshot>,
+
+    pending: Option<Snapshot>,
+    depth: usize,
+    // bumped every time a new step is recorded
+    version: usize,
+    // bumped by new steps, undo and redo: every time the lines became different
+    changes: usize,
+}
+
+impl History {
+    pub fn new() -> Self {
+        Self {
+            undo: Vec::new(),
+            redo: Vec::new(),
+            pending: None,
+            depth: 0,
+            version: 0,
+            changes: 0,
+        }
+    }
+
+    pub fn begin(&mut self, lines: &[String], cursor: (usize, usize)) {
+        if self.depth == 0 {
+            self.pending = Some(Snapshot {
+                lines: lines.to_vec(),
+                cursor,
+            });
+        }
+        self.depth += 1;
+    }
+
+    // closes a step, it is only recorded when the lines actually changed
+    pub fn end(&mut self, lines: &[String]) {
+        self.depth = self.depth.saturating_sub(1);
+        if self.depth > 0 {
+            return;
+        }
+
+        if let Some(snapshot) = self.pending.take() {
+            if snapshot.lines != lines {
+                self.undo.push(snapshot);
+                self.redo.clear();
+                self.version += 1;
+                self.changes += 1;
+            }
+        }
+    }
+
+    pub fn undo(&mut self, lines: &[String], cursor: (usize, usize)) -> Option<Snapshot> {
+        let snapshot = self.undo.pop()?;
+        self.redo.push(Snapshot {
+            lines: lines.to_vec(),
+            cursor,
+        });
+        self.changes += 1;
+
+        Some(snapshot)
+    }
+
+    pub fn redo(&mut self, lines: &[String], cursor: (usize, usize)) -> Option<Snapshot> {
+        let snapshot = self.redo.pop()?;
+        self.undo.push(Snapshot {
+            lines: lines.to_vec(),
+            cursor,
+        });
+        self.changes += 1;
+
+        Some(snapshot)
+    }
+
+    pub fn version(&self) -> usize {
+        self.version
+    }
+
+    pub fn changes(&self) -> usize {
+        self.changes
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.pending = None;
+        self.depth = 0;
+    }
+}
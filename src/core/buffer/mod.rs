@@ -1,13 +1,29 @@
+use std::collections::HashMap;
+
+use history::History;
+
+pub mod filetype;
+pub mod history;
+
 pub struct Buffer {
     pub file: Option<String>,
-    pub lines: Vec<String>
+    pub lines: Vec<String>,
+    // language of the file, picked from its name, "" when unknown
+    pub filetype: String,
+
+    // line marks set with `m{a-z}` or `:mark`, stored as 0-based line indexes
+    pub marks: HashMap<char, usize>,
+    // lines picked by `:g`, they follow their lines through edits like marks do
+    flagged: Vec<usize>,
+
+    pub history: History,
 }
 
 impl Buffer {
     pub fn from_file(file: Option<String>) -> Self {
         let file_name = file;
         let lines = match &file_name {
-            Some(file) => 
+            Some(file) =>
                 {
                     let strings = std::fs::read_to_string(file);
 
@@ -20,16 +36,24 @@ impl Buffer {
                 },
             None => vec![String::new()]
         };
+        // an empty file still has the line the cursor is on
+        let lines = if lines.is_empty() { vec![String::new()] } else { lines };
+
+        let filetype = file_name.as_deref().map(filetype::detect).unwrap_or_default().to_string();
 
         Self {
-            file:file_name, 
-            lines
+            file:file_name,
+            lines,
+            filetype,
+            marks: HashMap::new(),
+            flagged: Vec::new(),
+            history: History::new(),
         }
     }
 
     pub fn load_file(&mut self, filename: Option<&str>) -> anyhow::Result<()> {
         let lines = match filename {
-            Some(file) => 
+            Some(file) =>
             {
                 let strings = std::fs::read_to_string(file);
 
@@ -43,38 +67,195 @@ impl Buffer {
             None => vec![String::new()]
         };
 
-        self.file = Some(filename.unwrap().trim().to_string());
+        self.file = filename.map(|f| f.trim().to_string());
+        self.filetype = filename.map(filetype::detect).unwrap_or_default().to_string();
         self.lines = lines;
+        if self.lines.is_empty() {
+            self.lines.push(String::new());
+        }
+        self.marks.clear();
+        self.flagged.clear();
+        self.history.clear();
 
         Ok(())
     }
 
-    pub fn get(&self, line: usize) -> Option<String> {
-        if self.lines.len() >= line {
-            return Some(self.lines[line].clone());
+    pub fn get_file_lenght(&self) -> usize {
+        self.lines.len()
+    }
+
+    // inserts lines before `at`, marks below the insertion point move down
+    pub fn insert_lines(&mut self, at: usize, new_lines: Vec<String>) {
+        let count = new_lines.len();
+        let at = at.min(self.lines.len());
+        self.lines.splice(at..at, new_lines);
+
+        self.adjust_marks(|line| Some(if line >= at { line + count } else { line }));
+    }
+
+    // removes lines `first..=last` and returns them, marks inside the range are dropped
+    pub fn remove_lines(&mut self, first: usize, last: usize) -> Vec<String> {
+        let last = last.min(self.lines.len() - 1);
+        let removed: Vec<String> = self.lines.drain(first..=last).collect();
+        let count = removed.len();
+
+        self.adjust_marks(|line| match line {
+            l if l < first => Some(l),
+            l if l > last => Some(l - count),
+            _ => None,
+        });
+
+        if self.lines.is_empty() {
+            self.lines.push(String::new());
         }
-        None
+
+        removed
     }
 
-    pub fn get_file_lenght(&self) -> usize {
-        self.lines.len()
+    // moves lines `first..=last` below line `dest` (`None` means above the first line),
+    // marks travel together with their lines
+    pub fn move_lines(&mut self, first: usize, last: usize, dest: Option<usize>) {
+        let count = last - first + 1;
+        let moved: Vec<String> = self.lines.drain(first..=last).collect();
+
+        let insert_at = match dest {
+            None => 0,
+            Some(d) if d < first => d + 1,
+            Some(d) => d + 1 - count,
+        };
+        self.lines.splice(insert_at..insert_at, moved);
+
+        self.adjust_marks(|line| {
+            Some(if line >= first && line <= last {
+                line - first + insert_at
+            } else if insert_at < first && line >= insert_at && line < first {
+                line + count
+            } else if insert_at > first && line > last && line < insert_at + count {
+                line - count
+            } else {
+                line
+            })
+        });
     }
 
-    pub fn save(&self) -> anyhow::Result<()> {
-        let mut whole_content = String::new();
-        for line in self.lines.iter() {
-            whole_content.push_str(line);
-            whole_content.push('\n');
+    // text from `(line, column)` position `from` up to `to` (not included), one string
+    // per line; `to` may be the start of the line after the last one taken
+    pub fn text_range(&self, from: (usize, usize), to: (usize, usize)) -> Vec<String> {
+        if from.0 == to.0 {
+            return vec![self.lines[from.0][from.1..to.1].to_string()];
         }
 
-        std::fs::write(self.file.clone().unwrap(), whole_content)?;
+        let mut text = vec![self.lines[from.0][from.1..].to_string()];
+        text.extend(self.lines[from.0 + 1..to.0].iter().cloned());
+        text.push(self.lines[to.0][..to.1].to_string());
+        text
+    }
 
-        Ok(())
+    // removes the text between `from` and `to` (see `text_range`) and returns it,
+    // marks on the lines joined into the first one are dropped
+    pub fn remove_text(&mut self, from: (usize, usize), to: (usize, usize)) -> Vec<String> {
+        let removed = self.text_range(from, to);
+
+        let rest = self.lines[to.0][to.1..].to_string();
+        self.lines[from.0].truncate(from.1);
+        self.lines[from.0].push_str(&rest);
+        if to.0 > from.0 {
+            self.lines.drain(from.0 + 1..=to.0);
+            let count = to.0 - from.0;
+            self.adjust_marks(|line| match line {
+                l if l <= from.0 => Some(l),
+                l if l > to.0 => Some(l - count),
+                _ => None,
+            });
+        }
+
+        removed
+    }
+
+    // inserts `text` at `at`, every string after the first one starts a new line;
+    // returns the position right after the inserted text
+    pub fn insert_text(&mut self, at: (usize, usize), text: &[String]) -> (usize, usize) {
+        let (line, col) = at;
+        let rest = self.lines[line].split_off(col);
+
+        let last = line + text.len() - 1;
+        self.lines[line].push_str(&text[0]);
+        if text.len() > 1 {
+            self.insert_lines(line + 1, text[1..].to_vec());
+        }
+        let end = (last, self.lines[last].len());
+        self.lines[last].push_str(&rest);
+
+        end
+    }
+
+    // moves marks and flagged lines to their new place, `None` drops them
+    fn adjust_marks(&mut self, new_place: impl Fn(usize) -> Option<usize>) {
+        self.marks = self
+            .marks
+            .iter()
+            .filter_map(|(mark, line)| new_place(*line).map(|l| (*mark, l)))
+            .collect();
+        self.flagged = self.flagged.iter().filter_map(|line| new_place(*line)).collect();
+    }
+
+    pub fn set_flagged(&mut self, lines: Vec<usize>) {
+        self.flagged = lines;
+    }
+
+    // takes the topmost flagged line
+    pub fn take_flagged(&mut self) -> Option<usize> {
+        let (index, _) = self.flagged.iter().enumerate().min_by_key(|(_, line)| **line)?;
+        Some(self.flagged.swap_remove(index))
+    }
+
+    pub fn set_mark(&mut self, mark: char, line: usize) {
+        self.marks.insert(mark, line);
+    }
+
+    pub fn get_mark(&self, mark: char) -> Option<usize> {
+        self.marks.get(&mark).copied()
+    }
+
+    // starts an undo step, everything until the matching `end_change` is undone at once
+    pub fn begin_change(&mut self, cursor: (usize, usize)) {
+        self.history.begin(&self.lines, cursor);
+    }
+
+    pub fn end_change(&mut self) {
+        self.history.end(&self.lines);
+    }
+
+    // restores the previous state and returns the cursor position saved with it
+    pub fn undo(&mut self, cursor: (usize, usize)) -> Option<(usize, usize)> {
+        let snapshot = self.history.undo(&self.lines, cursor)?;
+        self.lines = snapshot.lines;
+        Some(snapshot.cursor)
+    }
+
+    pub fn redo(&mut self, cursor: (usize, usize)) -> Option<(usize, usize)> {
+        let snapshot = self.history.redo(&self.lines, cursor)?;
+        self.lines = snapshot.lines;
+        Some(snapshot.cursor)
+    }
+
+    pub fn save(&self) -> anyhow::Result<()> {
+        let file = match &self.file {
+            Some(f) => f.clone(),
+            None => anyhow::bail!("E32: No file name"),
+        };
+
+        self.save_by_name(&file)
     }
 
     pub fn save_by_name(&self, filename: &str) -> anyhow::Result<()>{
+        self.save_lines(filename, 0, self.lines.len() - 1)
+    }
+
+    // writes lines `first..=last` into `filename`
+    pub fn save_lines(&self, filename: &str, first: usize, last: usize) -> anyhow::Result<()> {
         let mut whole_content = String::new();
-        for line in self.lines.iter() {
+        for line in self.lines[first..=last].iter() {
             whole_content.push_str(line);
             whole_content.push('\n');
         }
@@ -0,0 +1,176 @@
+use serde_json::{json, Value};
+
+use super::Editor;
+
+// the events autocommands can wait for, plugins can wait for `TextChanged` too
+pub const EVENTS: &[&str] = &[
+    "BufRead",
+    "BufNewFile",
+    "BufWritePre",
+    "BufWritePost",
+    "ModeChanged",
+    "InsertLeave",
+    "CursorHold",
+    "VimLeave",
+];
+
+// the event called `name`, whatever its case
+fn find_event(name: &str) -> anyhow::Result<&'static str> {
+    match EVENTS.iter().find(|e| e.eq_ignore_ascii_case(name)) {
+        Some(event) => Ok(event),
+        None => anyhow::bail!("E216: No such event: {}", name),
+    }
+}
+
+// `*` stands for any text and `?` for one character
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // where the last `*` was and the text it stands for so far ends
+    let mut star = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                // the `*` takes one more character
+                Some((star_p, star_t)) => {
+                    star = Some((star_p, star_t + 1));
+                    p = star_p + 1;
+                    t = star_t + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+// a pattern without a `/` is matched against the file name alone, like `*.rs`
+fn pattern_matches(pattern: &str, name: &str) -> bool {
+    let name = match pattern.contains('/') {
+        true => name,
+        false => name.rsplit('/').next().unwrap_or(name),
+    };
+    glob_match(pattern, name)
+}
+
+// an Ex command run on an event, from `autocmd BufWritePre *.rs {cmd}`
+pub struct Autocmd {
+    pub event: &'static str,
+    pub pattern: String,
+    pub command: String,
+    // autocommands from the configuration files are replaced when they are read again
+    pub from_config: bool,
+}
+
+pub struct Autocmds {
+    pub list: Vec<Autocmd>,
+    // the commands of autocommands do not start more autocommands
+    running: bool,
+}
+
+impl Autocmds {
+    pub fn new() -> Self {
+        Self { list: Vec::new(), running: false }
+    }
+
+    pub fn clear_config(&mut self) {
+        self.list.retain(|a| !a.from_config);
+    }
+}
+
+impl Editor {
+    // `:autocmd {event}[,{event}] {pattern}[,{pattern}] {cmd}` adds an autocommand, with
+    // less words it lists them; `:autocmd!` removes the ones that fit before adding any,
+    // `*` as event stands for all of them
+    pub(super) fn ex_autocmd(&mut self, args: &str, bang: bool, from_config: bool) -> anyhow::Result<()> {
+        let mut words = args.splitn(3, char::is_whitespace).filter(|w| !w.is_empty());
+        let events = match words.next() {
+            Some("*") | None => EVENTS.to_vec(),
+            Some(events) => events.split(',').map(find_event).collect::<anyhow::Result<_>>()?,
+        };
+        let patterns: Vec<String> = words.next().map(|p| p.split(',').map(String::from).collect()).unwrap_or_default();
+        let command = words.next().unwrap_or("").trim();
+
+        let fits = |a: &Autocmd| events.contains(&a.event) && (patterns.is_empty() || patterns.contains(&a.pattern));
+        let list = &mut self.autocmds.list;
+        if bang {
+            list.retain(|a| !fits(a));
+        }
+
+        if command.is_empty() {
+            if !bang {
+                let lines: Vec<String> = list
+                    .iter()
+                    .filter(|a| fits(a))
+                    .map(|a| format!("{:<14} {:<10} {}", a.event, a.pattern, a.command))
+                    .collect();
+                match lines.len() {
+                    0 => self.command_bar.message = Some("No autocommands found".to_string()),
+                    1 => self.command_bar.message = lines.into_iter().next(),
+                    _ => self.command_bar.show_output(lines),
+                }
+            }
+            return Ok(());
+        }
+
+        if patterns.is_empty() {
+            anyhow::bail!("E471: Argument required");
+        }
+        for &event in &events {
+            for pattern in &patterns {
+                list.push(Autocmd { event, pattern: pattern.clone(), command: command.to_string(), from_config });
+            }
+        }
+
+        Ok(())
+    }
+
+    // tells the plugins that subscribed to `event` about it, with the current file, and
+    // runs the autocommands of the event
+    pub(super) fn emit_event(&mut self, event: &str, mut params: Value) {
+        params["file"] = json!(self.buffer.file);
+        self.plugins.emit(event, params.clone());
+        self.run_autocmds(event, &params);
+    }
+
+    // the autocommands of `event` whose pattern fits, `ModeChanged` patterns are matched
+    // against `old:new`, like `*:insert`, the others against the file written or edited
+    fn run_autocmds(&mut self, event: &str, params: &Value) {
+        if self.autocmds.running {
+            return;
+        }
+        let name = match event {
+            "ModeChanged" => format!("{}:{}", params["old"].as_str().unwrap_or(""), params["new"].as_str().unwrap_or("")),
+            _ => params["written"].as_str().or(params["file"].as_str()).unwrap_or("").to_string(),
+        };
+        let commands: Vec<(String, String)> = self
+            .autocmds
+            .list
+            .iter()
+            .filter(|a| a.event == event && pattern_matches(&a.pattern, &name))
+            .map(|a| (a.pattern.clone(), a.command.clone()))
+            .collect();
+
+        self.autocmds.running = true;
+        for (pattern, command) in commands {
+            if let Err(e) = self.execute_command(command) {
+                self.command_bar.message = Some(format!("{} Autocommands for \"{}\": {}", event, pattern, e));
+            }
+            if !self.running && event != "VimLeave" {
+                break;
+            }
+        }
+        self.autocmds.running = false;
+    }
+}
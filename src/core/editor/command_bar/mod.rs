@@ -1,80 +1,121 @@
-use std::{borrow::BorrowMut, io::{Stdout, Write}};
+use std::io::{Stdout, Write};
 
-use crossterm::{cursor::MoveTo, style::{self, Color, Stylize}, QueueableCommand};
+use crossterm::{cursor::MoveTo, style::{self, Stylize}, QueueableCommand};
 
-// adding all list of modules
-use super::{modules::{get_modules, BarModule}, Editor, Mode};
+use super::theme::Theme;
 
-use super::config::{CommandBarSettings,CommandsBindings};
+// names Tab goes through in the command line, typing anything else starts over
+pub struct Completion {
+    // the text in front of the name
+    pub prefix: String,
+    pub matches: Vec<String>,
+    pub index: usize,
+}
+
+impl Completion {
+    pub fn shown(&self) -> String {
+        format!("{}{}", self.prefix, self.matches[self.index])
+    }
+}
 
 pub struct CommandBar {
-    background_color: (u8, u8, u8),
-    font_color: (u8, u8, u8),
     pub command: String,
+    pub completion: Option<Completion>,
+    // result or error of the last command, shown until the next one starts
+    pub message: Option<String>,
+    // output of a command too long for one line, shown over the text a page at a time
+    output: Vec<String>,
+    output_top: usize,
 }
 
 impl CommandBar {
     pub fn new() -> Self {
-        let settings = CommandBarSettings::init();
-        
         Self {
             command: ":".to_string(),
-            background_color: settings.get_info_backcolor().unwrap(),
-            font_color: settings.get_info_color().unwrap(),
+            completion: None,
+            message: None,
+            output: Vec::new(),
+            output_top: 0,
         }
     }
 
     // draw status bar and all modules in the terminal
-    pub fn draw(&mut self, _stdout: &mut Stdout, size: (u16, u16)) -> anyhow::Result<()> {
-        _stdout.queue(MoveTo(0, size.1))?;
+    pub fn draw(&mut self, _stdout: &mut Stdout, size: (u16, u16), theme: &Theme) -> anyhow::Result<()> {
+        _stdout.queue(MoveTo(0, size.1.saturating_sub(1)))?;
         _stdout.queue(style::PrintStyledContent(
-            (" ").repeat((size.0) as usize).on(
-                Color::Rgb {
-                    r: self.background_color.0, 
-                    g: self.background_color.1, 
-                    b: self.background_color.2 
-                })
+            theme.bar(" ".repeat(size.0 as usize), "command_bar_background", "command_bar_text")
         ))?;
         _stdout.flush()?;
-        _stdout.queue(MoveTo(0, size.1))?;
+        _stdout.queue(MoveTo(0, size.1.saturating_sub(1)))?;
 
         _stdout.queue(style::PrintStyledContent(
-            self.command.as_str().on(
-                Color::Rgb { 
-                    r: self.background_color.0,
-                    g: self.background_color.1, 
-                    b: self.background_color.2 
-                })
-                .with(Color::Rgb { 
-                    r: self.font_color.0, 
-                    g: self.font_color.1, 
-                    b: self.font_color.2 
-                })
+            theme.bar(self.command.as_str(), "command_bar_background", "command_bar_text")
             ))?;
         _stdout.flush()?;
         Ok(())
     }
 
-    pub fn clean(&mut self, _stdout: &mut Stdout, size: (u16, u16)) -> anyhow::Result<()> {
-        _stdout.queue(MoveTo(0, size.1))?;
-        _stdout.queue(style::Print(
-            (" ").repeat((size.0) as usize)
-        ))?;
-        _stdout.flush()?;
+    // clears the bar, leaving only the last message if there is one
+    pub fn clean(&mut self, _stdout: &mut Stdout, size: (u16, u16), theme: &Theme) -> anyhow::Result<()> {
+        let message = self.message.clone().unwrap_or_default();
+        let message: String = message.chars().take(size.0 as usize).collect();
 
-        _stdout.queue(MoveTo(0, size.1))?;
+        _stdout.queue(MoveTo(0, size.1.saturating_sub(1)))?;
+        let message = format!("{message:<width$}", width = size.0 as usize).with(theme.color("message"));
+        _stdout.queue(style::PrintStyledContent(match theme.background() {
+            Some(background) => message.on(background),
+            None => message,
+        }))?;
         _stdout.flush()?;
-        _stdout.queue(MoveTo(0, size.1))?;
+
+        _stdout.queue(MoveTo(0, size.1.saturating_sub(1)))?;
         Ok(())
     }
-    // changes background color of status bar
-    pub fn change_background_color(&mut self, new_color: &(u8, u8, u8)) {
-        self.background_color = new_color.clone();
+
+    pub fn show_output(&mut self, lines: Vec<String>) {
+        self.output = lines;
+        self.output_top = 0;
+    }
+
+    pub fn has_output(&self) -> bool {
+        !self.output.is_empty()
     }
 
-    // moves cursor to the begining of status bar
-    fn move_to_bar(&self, _stdout: &mut Stdout, size: (u16, u16)) -> anyhow::Result<()> {
-        _stdout.queue(MoveTo(0, size.1-1))?;
+    // rows for output lines, the last row of the screen holds the prompt
+    fn output_rows(size: (u16, u16)) -> usize {
+        (size.1 as usize).saturating_sub(1).max(1)
+    }
+
+    // a key shows the next page of the output and closes it after the last one
+    pub fn page_output(&mut self, size: (u16, u16)) {
+        let page = Self::output_rows(size);
+        if self.output_top + page < self.output.len() {
+            self.output_top += page;
+        } else {
+            self.output.clear();
+        }
+    }
+
+    // draws the current page of the output above a prompt on the last row
+    pub fn draw_output(&mut self, _stdout: &mut Stdout, size: (u16, u16), theme: &Theme) -> anyhow::Result<()> {
+        let width = size.0 as usize;
+        let page = Self::output_rows(size);
+        let end = (self.output_top + page).min(self.output.len());
+        let lines = &self.output[self.output_top..end];
+        let prompt = if end < self.output.len() { "-- More --" } else { "Press any key to continue" };
+
+        let first_row = (size.1 as usize).saturating_sub(1 + lines.len());
+        for (i, line) in lines.iter().chain(std::iter::once(&prompt.to_string())).enumerate() {
+            let line: String = line.chars().take(width).collect();
+            let line = format!("{line:<width$}").with(theme.color("message"));
+            _stdout.queue(MoveTo(0, (first_row + i) as u16))?;
+            _stdout.queue(style::PrintStyledContent(match theme.background() {
+                Some(background) => line.on(background),
+                None => line,
+            }))?;
+        }
+        _stdout.flush()?;
+
         Ok(())
     }
 }
@@ -1,15 +1,59 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use super::keymap::map_command;
+use super::options::{self, Level, Options, OPTIONS};
+use super::theme::parse_rgb;
+
+// name of the configuration file inside the configuration directory
+const CONFIG_FILE: &str = "edit.conf";
+// name of a project's configuration file, looked for next to the edited file and above it
+const PROJECT_FILE: &str = ".edit.conf";
+
+// settings other than options that `:config` lists after the options, in the order it
+// lists them
+const SETTINGS: &[&str] = &[
+    "theme", "editor_font_color",
+    "indent_after_default", "auto_pairs_default",
+    "status_bar_background_color", "status_bar_separator",
+    "command_bar_background_color", "command_bar_font_color",
+    "cmd_quit", "cmd_write", "cmd_edit",
+    "exrc",
+];
+
+// every option and setting, in the order `:config` lists them
+pub fn setting_names() -> impl Iterator<Item = &'static str> {
+    OPTIONS.iter().map(|o| o.name).chain(SETTINGS.iter().copied())
+}
+
+// colours set here override the ones of the theme
 pub struct EditorSettings {
-    pub font_color: (u8, u8, u8),
+    pub font_color: Option<(u8, u8, u8)>,
+    pub theme: String,
+}
+
+pub struct AutoPairsSettings {
+    // pairs for every file type, like "() [] {}"
+    pub default: Option<String>,
+    // pairs for one file type, from `auto_pairs_<filetype>` lines
+    pub by_filetype: Vec<(String, String)>,
+}
+
+pub struct IndentSettings {
+    // characters a deeper level starts after, like "{ ( ["
+    pub default: Option<String>,
+    // the same for one file type, from `indent_after_<filetype>` lines
+    pub by_filetype: Vec<(String, String)>,
 }
 
 pub struct StatusBarSettings {
-    pub background_color: (u8, u8, u8),
+    pub background_color: Option<(u8, u8, u8)>,
     pub separator_symbol: String,
 }
 
 pub struct CommandBarSettings {
-    pub background_color: (u8, u8, u8),
-    pub font_color: (u8, u8, u8),
+    pub background_color: Option<(u8, u8, u8)>,
+    pub font_color: Option<(u8, u8, u8)>,
 }
 
 pub struct CommandsBindings {
@@ -18,171 +62,379 @@ pub struct CommandsBindings {
     pub edit: String,
 }
 
-impl EditorSettings {
-    pub fn init() -> Self{
-        let strings = 
-        match std::fs::read_to_string("~/.config/edit/edit.conf") {
-            Ok(v) => {v},
-            Err(_) => return Self{
-                font_color: (255,255,255),
-            }
+// configuration given on the command line: `-c/--config <path>` and `--set key=value`;
+// `dir` stands in for the user's configuration directory when it is set
+#[derive(Clone, Default)]
+pub struct ConfigOptions {
+    pub file: Option<PathBuf>,
+    pub sets: Vec<String>,
+    pub dir: Option<PathBuf>,
+}
+
+impl ConfigOptions {
+    // where the user's file, themes and syntax definitions are
+    pub fn config_dir(&self) -> Option<PathBuf> {
+        self.dir.clone().or_else(config_dir)
+    }
+}
+
+// every setting, read once when the editor starts from layers that each override the one
+// before: the defaults, the user's file, the project's file and the command line; the
+// options set here are the global values `:set` changes later
+pub struct Config {
+    pub options: Options,
+    pub editor: EditorSettings,
+    pub status_bar: StatusBarSettings,
+    pub command_bar: CommandBarSettings,
+    pub commands: CommandsBindings,
+    pub auto_pairs: AutoPairsSettings,
+    pub indent: IndentSettings,
+    // `nmap <C-s> :w<CR>`, `command Fmt !rustfmt %`, `alias W w` and `plugin {executable}`
+    // style lines: where they are, the command and its arguments
+    pub ex_lines: Vec<(String, String, String)>,
+    // whether the project's file may map keys, define commands and autocommands and start
+    // plugins, only the user's file and the command line can turn it on
+    pub exrc: bool,
+    // the files of the layers, watched for changes while editing
+    pub files: Vec<PathBuf>,
+    // where each setting that is not a default was set
+    origins: HashMap<String, String>,
+}
+
+// `$XDG_CONFIG_HOME/edit`, or `~/.config/edit` when it is not set
+pub fn config_dir() -> Option<PathBuf> {
+    let non_empty = |name: &str| std::env::var_os(name).filter(|v| !v.is_empty());
+    match non_empty("XDG_CONFIG_HOME") {
+        Some(dir) => Some(PathBuf::from(dir).join("edit")),
+        None => non_empty("HOME").map(|home| PathBuf::from(home).join(".config").join("edit")),
+    }
+}
+
+fn parse_color(value: &str) -> Result<Option<(u8, u8, u8)>, String> {
+    match parse_rgb(value) {
+        Some(rgb) => Ok(Some(rgb)),
+        None => Err(format!("expected a colour like (255, 204, 229) or #ffccee, got `{}`", value)),
+    }
+}
+
+// values may be put in double quotes to keep white space at their ends
+fn unquote(value: &str) -> &str {
+    match value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+        Some(inner) => inner,
+        None => value,
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            options: Options::new(),
+            editor: EditorSettings {
+                font_color: None,
+                theme: String::from("default"),
+            },
+            status_bar: StatusBarSettings {
+                background_color: None,
+                separator_symbol: String::from("◢◤"),
+            },
+            command_bar: CommandBarSettings {
+                background_color: None,
+                font_color: None,
+            },
+            commands: CommandsBindings {
+                quit: String::from(":q"),
+                save: String::from(":w"),
+                edit: String::from(":e"),
+            },
+            auto_pairs: AutoPairsSettings {
+                default: None,
+                by_filetype: Vec::new(),
+            },
+            indent: IndentSettings {
+                default: None,
+                by_filetype: Vec::new(),
+            },
+            ex_lines: Vec::new(),
+            exrc: false,
+            files: Vec::new(),
+            origins: HashMap::new(),
+        }
+    }
+}
+
+// `.edit.conf` in directory `dir` or the closest directory above it that has one
+fn find_project_file(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors().map(|d| d.join(PROJECT_FILE)).find(|path| path.is_file())
+}
+
+fn show_color(color: Option<(u8, u8, u8)>) -> String {
+    match color {
+        Some((r, g, b)) => format!("({}, {}, {})", r, g, b),
+        None => "from theme".to_string(),
+    }
+}
+
+impl Config {
+    // all layers of configuration for editing `file`, and the problems found in them
+    pub fn load(file: Option<&str>, options: &ConfigOptions) -> (Self, Vec<String>) {
+        let mut config = Config::default();
+        let mut errors = Vec::new();
+
+        // the user's file is watched even before it exists
+        if let Some(dir) = options.config_dir() {
+            errors.extend(config.apply_file(&dir.join(CONFIG_FILE), false));
+            config.files.push(dir.join(CONFIG_FILE));
+        }
+
+        // the project is found from the edited file, or from the working directory
+        let start = match file.map(|f| Path::new(f.trim()).to_path_buf()) {
+            Some(path) => path.canonicalize().unwrap_or(path).parent().map(Path::to_path_buf),
+            None => std::env::current_dir().ok(),
         };
-        let lines:Vec<String> = strings.lines().map(|x| x.to_string()).collect();
-        let mut custom_font_color:(u8,u8,u8) = (255,204,229);
-        for i in lines {
-            if i.starts_with("editor_font_color"){
-                let temp:Vec<String>= i.split('=').map(|x| x.trim().to_string()).collect();
-                let temp = temp.last().unwrap();
-                let temp = &temp[1..temp.len()-1];
-                let temp:Vec<u8> = temp.split(',').map(|x| x.trim().parse::<u8>().unwrap()).collect();
-                custom_font_color.0 = temp[0];
-                custom_font_color.1 = temp[1];
-                custom_font_color.2 = temp[2];
+        let mut project_lines = 0..0;
+        if let Some(project) = start.as_deref().and_then(find_project_file) {
+            let first = config.ex_lines.len();
+            errors.extend(config.read_file(&project, false, true));
+            project_lines = first..config.ex_lines.len();
+            config.files.push(project);
+        }
+
+        if let Some(path) = &options.file {
+            errors.extend(config.apply_file(path, true));
+            config.files.push(path.clone());
+        }
+        for set in &options.sets {
+            let result = match set.split_once('=') {
+                Some((key, value)) => config.apply_from(key.trim(), value.trim(), "--set"),
+                None => Err(format!("expected `key=value`, got `{}`", set)),
+            };
+            if let Err(e) = result {
+                errors.push(format!("--set {}: {}", set, e));
             }
         }
-        
-        Self{
-            font_color: custom_font_color,
+
+        // a project's file comes with the code in it, what it runs waits for `exrc`
+        if !config.exrc {
+            for (origin, command, _) in config.ex_lines.drain(project_lines) {
+                errors.push(format!("{}: `{}` lines of a project are read only with `exrc = true`", origin, command));
+            }
         }
+
+        (config, errors)
     }
-    pub fn get_info_color(&self) -> Option<(u8,u8,u8)>{
-        Some(self.font_color)
+
+    // applies every `key = value` line of a file, a missing file is only an error when
+    // it was `required`
+    pub fn apply_file(&mut self, path: &Path, required: bool) -> Vec<String> {
+        self.read_file(path, required, false)
     }
-    
-}
 
-impl StatusBarSettings {
-    pub fn init() -> Self{
-        
-        let strings = 
-        match std::fs::read_to_string("~/.config/edit/edit.conf") {
-            Ok(v) => {v},
-            Err(_) => return Self{
-                background_color: (255,204,229),
-                separator_symbol: String::from("◢◤"),
-            }
+    // the same for the file of a `project`, which cannot set `exrc` itself
+    fn read_file(&mut self, path: &Path, required: bool, project: bool) -> Vec<String> {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if required => return vec![format!("{}: {}", path.display(), e)],
+            Err(_) => return Vec::new(),
         };
-        let lines:Vec<String> = strings.lines().map(|x| x.to_string()).collect();
-        let mut custom_color:(u8,u8,u8) = (255,204,229);
-        let mut custom_separator = String::from("◢◤");
-        for i in lines {
-            if i.starts_with("status_bar_background_color"){
-                let temp:Vec<String>= i.split('=').map(|x| x.trim().to_string()).collect();
-                let temp = temp.last().unwrap();
-                let temp = &temp[1..temp.len()-1];
-                let temp:Vec<u8> = temp.split(',').map(|x| x.trim().parse::<u8>().unwrap()).collect();
-                custom_color.0 = temp[0];
-                custom_color.1 = temp[1];
-                custom_color.2 = temp[2];
+
+        let mut errors = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
             }
-            if i.starts_with("status_bar_separator"){
-                let temp:Vec<String>= i.split('=').map(|x| x.trim().to_string()).collect();
-                let temp = temp.last().unwrap();
-                custom_separator = (&temp[1..temp.len()-1]).to_string();
+            let origin = format!("{}:{}", path.display(), i + 1);
+
+            // mappings, user commands, aliases and autocommands are written like the commands,
+            // they may have `=` in them
+            let (command, args) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            let ex_line = matches!(command, "command" | "command!" | "alias" | "autocmd" | "autocmd!" | "au" | "au!" | "plugin");
+            if map_command(command).is_some() || ex_line {
+                self.ex_lines.push((origin, command.to_string(), args.trim().to_string()));
+                continue;
+            }
+
+            let result = match line.split_once('=') {
+                Some((key, _)) if project && key.trim() == "exrc" => {
+                    Err("`exrc` is set in the user's file or on the command line".to_string())
+                }
+                Some((key, value)) => self.apply_from(key.trim(), value.trim(), &origin),
+                None => Err(format!("expected `key = value`, got `{}`", line)),
+            };
+            if let Err(e) = result {
+                errors.push(format!("{}:{}: {}", path.display(), i + 1, e));
             }
         }
-        
-        Self{
-            background_color: custom_color,
-            separator_symbol: custom_separator,
+        errors
+    }
+
+    // sets one setting and remembers where it was set
+    fn apply_from(&mut self, key: &str, value: &str, origin: &str) -> Result<(), String> {
+        self.apply(key, value)?;
+        self.set_origin(key, origin);
+        Ok(())
+    }
+
+    // options changed with `:set` keep their values when the configuration is read again,
+    // unless one of its files sets them now
+    pub fn keep_set_options(&mut self, old: &Config) {
+        for (index, def) in OPTIONS.iter().enumerate() {
+            let set_while_editing = old.origins.get(def.name).is_some_and(|o| o == ":set");
+            if set_while_editing && !self.origins.contains_key(def.name) {
+                self.options.set(index, old.options.global_value(index).clone(), Level::Global);
+                self.set_origin(def.name, ":set");
+            }
         }
+        self.options.keep_local(&old.options);
     }
-    pub fn get_info_backcolor(&self) -> Option<(u8,u8,u8)>{
-        Some(self.background_color)
+
+    // remembers where a setting was set last, under the name `:config` lists it by
+    pub fn set_origin(&mut self, key: &str, origin: &str) {
+        let key = match key {
+            "colorscheme" => "theme",
+            "cmd_save" => "cmd_write",
+            "auto_pairs" => "autopairs",
+            key => options::find(key).map_or(key, |i| OPTIONS[i].name),
+        };
+        self.origins.insert(key.to_string(), origin.to_string());
     }
-    pub fn get_info_separator(&self) -> Option<String>{
-        Some(self.separator_symbol.clone())
+
+    // sets one setting from its text value
+    pub fn apply(&mut self, key: &str, value: &str) -> Result<(), String> {
+        let text = unquote(value).to_string();
+        // `auto_pairs` is what the option was called before `:set` knew it
+        let option = if key == "auto_pairs" { options::find("autopairs") } else { options::find(key) };
+        if let Some(index) = option {
+            let value = options::parse(&OPTIONS[index], &text)?;
+            self.options.set(index, value, Level::Global);
+            return Ok(());
+        }
+
+        match key {
+            "editor_font_color" => self.editor.font_color = parse_color(value)?,
+            "theme" | "colorscheme" => self.editor.theme = text,
+
+            "status_bar_background_color" => self.status_bar.background_color = parse_color(value)?,
+            "status_bar_separator" => self.status_bar.separator_symbol = text,
+            "command_bar_background_color" => self.command_bar.background_color = parse_color(value)?,
+            "command_bar_font_color" => self.command_bar.font_color = parse_color(value)?,
+
+            "cmd_quit" => self.commands.quit = text,
+            // `cmd_save` is the name older configuration files use
+            "cmd_write" | "cmd_save" => self.commands.save = text,
+            "cmd_edit" => self.commands.edit = text,
+
+            "exrc" => {
+                self.exrc = match text.as_str() {
+                    "true" => true,
+                    "false" => false,
+                    _ => return Err(format!("expected true or false, got `{}`", text)),
+                }
+            }
+
+            "auto_pairs_default" => self.auto_pairs.default = Some(value.to_string()),
+            "indent_after_default" => self.indent.default = Some(value.to_string()),
+
+            _ => {
+                if let Some(filetype) = key.strip_prefix("auto_pairs_") {
+                    self.auto_pairs.by_filetype.push((filetype.to_string(), value.to_string()));
+                } else if let Some(filetype) = key.strip_prefix("indent_after_") {
+                    self.indent.by_filetype.push((filetype.to_string(), value.to_string()));
+                } else {
+                    return Err(format!("unknown setting `{}`", key));
+                }
+            }
+        }
+        Ok(())
     }
 }
 
-impl CommandBarSettings {
-    pub fn init() -> Self{
-        let strings = 
-        match std::fs::read_to_string("~/.config/edit/edit.conf") {
-            Ok(v) => {v},
-            Err(_) => return Self{
-                background_color: (255,255,255),
-                font_color: (0,0,0),
+impl Config {
+    // the value of a setting as the configuration file would write it
+    pub fn value(&self, key: &str) -> Option<String> {
+        // the global value, what the configuration set or `:set` changed it to
+        if let Some(index) = options::find(key) {
+            return Some(self.options.global_value(index).show());
+        }
+
+        let value = match key {
+            "theme" => self.editor.theme.clone(),
+            "editor_font_color" => show_color(self.editor.font_color),
+            "indent_after_default" => self.indent.default.clone().unwrap_or_else(|| "built in".to_string()),
+            "auto_pairs_default" => self.auto_pairs.default.clone().unwrap_or_else(|| "built in".to_string()),
+            "status_bar_background_color" => show_color(self.status_bar.background_color),
+            "status_bar_separator" => format!("\"{}\"", self.status_bar.separator_symbol),
+            "command_bar_background_color" => show_color(self.command_bar.background_color),
+            "command_bar_font_color" => show_color(self.command_bar.font_color),
+            "cmd_quit" => format!("\"{}\"", self.commands.quit),
+            "cmd_write" => format!("\"{}\"", self.commands.save),
+            "cmd_edit" => format!("\"{}\"", self.commands.edit),
+            "exrc" => self.exrc.to_string(),
+            _ => {
+                let by_filetype = if key.starts_with("auto_pairs_") {
+                    &self.auto_pairs.by_filetype
+                } else {
+                    &self.indent.by_filetype
+                };
+                let filetype = key.strip_prefix("auto_pairs_").or(key.strip_prefix("indent_after_"))?;
+                by_filetype.iter().rev().find(|(f, _)| f == filetype)?.1.clone()
             }
         };
-        let lines:Vec<String> = strings.lines().map(|x| x.to_string()).collect();
-        let mut custom_backgroundcolor:(u8,u8,u8) = (255,204,229);
-        let mut custom_font_color:(u8,u8,u8) = (0,0,0);
-        for i in lines {
-            if i.starts_with("command_bar_background_color"){
-                let temp:Vec<String>= i.split('=').map(|x| x.trim().to_string()).collect();
-                let temp = temp.last().unwrap();
-                let temp = &temp[1..temp.len()-1];
-                let temp:Vec<u8> = temp.split(',').map(|x| x.trim().parse::<u8>().unwrap()).collect();
-                custom_backgroundcolor.0 = temp[0];
-                custom_backgroundcolor.1 = temp[1];
-                custom_backgroundcolor.2 = temp[2];
-            }
-            if i.starts_with("command_bar_font_color"){
-                let temp:Vec<String>= i.split('=').map(|x| x.trim().to_string()).collect();
-                let temp = temp.last().unwrap();
-                let temp = &temp[1..temp.len()-1];
-                let temp:Vec<u8> = temp.split(',').map(|x| x.trim().parse::<u8>().unwrap()).collect();
-                custom_font_color.0 = temp[0];
-                custom_font_color.1 = temp[1];
-                custom_font_color.2 = temp[2];
+        Some(value)
+    }
+
+    // `key = value  (where it was set)` for one setting
+    pub fn describe(&self, key: &str) -> Option<String> {
+        let value = self.value(key)?;
+        let key = options::find(key).map_or(key, |i| OPTIONS[i].name);
+        let origin = self.origins.get(key).map(String::as_str).unwrap_or("default");
+        Some(format!("{} = {}  ({})", key, value, origin))
+    }
+
+    // every setting with its value and where it was set, file type settings last
+    pub fn describe_all(&self) -> Vec<String> {
+        let by_filetype = self
+            .auto_pairs
+            .by_filetype
+            .iter()
+            .map(|(f, _)| format!("auto_pairs_{}", f))
+            .chain(self.indent.by_filetype.iter().map(|(f, _)| format!("indent_after_{}", f)));
+        let mut keys: Vec<String> = setting_names().map(String::from).collect();
+        for key in by_filetype {
+            if !keys.contains(&key) {
+                keys.push(key);
             }
         }
-        
-        Self{
-            background_color: custom_backgroundcolor,
-            font_color: custom_font_color,
-        }
+
+        keys.iter().filter_map(|key| self.describe(key)).collect()
+    }
+}
+
+impl EditorSettings {
+    pub fn get_info_color(&self) -> Option<(u8,u8,u8)>{
+        self.font_color
     }
+}
+
+impl StatusBarSettings {
     pub fn get_info_backcolor(&self) -> Option<(u8,u8,u8)>{
-        Some(self.background_color)
+        self.background_color
     }
-    pub fn get_info_color(&self) -> Option<(u8,u8,u8)>{
-        Some(self.font_color)
+    pub fn get_info_separator(&self) -> Option<String>{
+        Some(self.separator_symbol.clone())
     }
 }
 
-impl CommandsBindings {
-    pub fn init() -> Self{
-        
-        let strings = 
-        match std::fs::read_to_string("~/.config/edit/edit.conf") {
-            Ok(v) => {v},
-            Err(_) => return Self{
-                quit: String::from(":q"),
-                save: String::from(":w"),
-                edit: String::from(":e")
-            }
-        };
-        let lines:Vec<String> = strings.lines().map(|x| x.to_string()).collect();
-        let mut custom_quit = String::from(":q");
-        let mut custom_save = String::from(":w");
-        let mut custom_edit = String::from(":e");
-        for i in lines {
-            if i.starts_with("cmd_quit"){
-                let temp:Vec<String>= i.split('=').map(|x| x.trim().to_string()).collect();
-                let temp = temp.last().unwrap();
-                custom_quit = (&temp[1..temp.len()-1]).to_string();
-            }
-            if i.starts_with("cmd_save"){
-                let temp:Vec<String>= i.split('=').map(|x| x.trim().to_string()).collect();
-                let temp = temp.last().unwrap();
-                custom_save = (&temp[1..temp.len()-1]).to_string();
-            }
-            if i.starts_with("cmd_edit"){
-                let temp:Vec<String>= i.split('=').map(|x| x.trim().to_string()).collect();
-                let temp = temp.last().unwrap();
-                custom_edit = (&temp[1..temp.len()-1]).to_string();
-            }
-        }
-        
-        Self{
-            quit: custom_quit,
-            save: custom_save,
-            edit: custom_edit
-        }
+impl CommandBarSettings {
+    pub fn get_info_backcolor(&self) -> Option<(u8,u8,u8)>{
+        self.background_color
     }
+    pub fn get_info_color(&self) -> Option<(u8,u8,u8)>{
+        self.font_color
+    }
+}
 
+impl CommandsBindings {
     pub fn get_info_quit(&self) -> Option<String>{
         Some(self.quit.clone())
     }
@@ -0,0 +1,206 @@
+use regex::Regex;
+
+use crate::core::buffer::Buffer;
+
+// resolves Ex addresses (`12`, `.`, `$`, `'a`, `/pat/`, `.+3`, `%`, `a,b`, `a;b`)
+// into 1-based line numbers, 0 is allowed and means "above the first line"
+pub struct AddressParser<'a> {
+    buffer: &'a Buffer,
+    current: usize,
+    pub last_pattern: Option<String>,
+}
+
+impl<'a> AddressParser<'a> {
+    // `current` is the 0-based line of the cursor
+    pub fn new(buffer: &'a Buffer, current: usize, last_pattern: Option<String>) -> Self {
+        Self {
+            buffer,
+            current: current + 1,
+            last_pattern,
+        }
+    }
+
+    // parses the range in front of a command, returns it and the rest of the input
+    pub fn parse_range<'b>(&mut self, input: &'b str) -> anyhow::Result<(Option<(usize, usize)>, &'b str)> {
+        let input = input.trim_start();
+        if let Some(rest) = input.strip_prefix('%') {
+            return Ok((Some((1, self.buffer.get_file_lenght())), rest));
+        }
+
+        let mut addresses: Vec<usize> = Vec::new();
+        let mut rest = input;
+        loop {
+            let (address, after) = self.parse_address(rest)?;
+            rest = after.trim_start();
+
+            let separator = rest.chars().next();
+            match separator {
+                Some(',') | Some(';') => {
+                    let address = address.unwrap_or(self.current);
+                    if separator == Some(';') {
+                        self.current = address;
+                    }
+                    addresses.push(address);
+                    rest = &rest[1..];
+
+                    // `5,` means `5,.`
+                    let next = rest.trim_start().chars().next();
+                    if !next.is_some_and(Self::starts_address) {
+                        addresses.push(self.current);
+                        break;
+                    }
+                }
+                _ => {
+                    if let Some(address) = address {
+                        addresses.push(address);
+                    }
+                    break;
+                }
+            }
+        }
+
+        let range = match addresses.len() {
+            0 => None,
+            1 => Some((addresses[0], addresses[0])),
+            n => {
+                let (first, last) = (addresses[n - 2], addresses[n - 1]);
+                Some((first.min(last), first.max(last)))
+            }
+        };
+
+        Ok((range, rest))
+    }
+
+    // parses one address with its offsets, `None` when the input does not start with one
+    pub fn parse_address<'b>(&mut self, input: &'b str) -> anyhow::Result<(Option<usize>, &'b str)> {
+        let mut rest = input.trim_start();
+        let mut base: Option<isize> = None;
+
+        match rest.chars().next() {
+            Some(c) if c.is_ascii_digit() => {
+                let end = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+                base = Some(rest[..end].parse()?);
+                rest = &rest[end..];
+            }
+            Some('.') => {
+                base = Some(self.current as isize);
+                rest = &rest[1..];
+            }
+            Some('$') => {
+                base = Some(self.buffer.get_file_lenght() as isize);
+                rest = &rest[1..];
+            }
+            Some('\'') => {
+                let mark = match rest[1..].chars().next() {
+                    Some(m) => m,
+                    None => anyhow::bail!("E20: Mark not set"),
+                };
+                let line = match self.buffer.get_mark(mark) {
+                    Some(l) => l,
+                    None => anyhow::bail!("E20: Mark not set"),
+                };
+                base = Some(line as isize + 1);
+                rest = &rest[1 + mark.len_utf8()..];
+            }
+            Some(delimiter @ ('/' | '?')) => {
+                let (pattern, after) = split_pattern(&rest[1..], delimiter);
+                let pattern = if pattern.is_empty() {
+                    match &self.last_pattern {
+                        Some(p) => p.clone(),
+                        None => anyhow::bail!("E35: No previous regular expression"),
+                    }
+                } else {
+                    pattern
+                };
+
+                base = Some(self.search(&pattern, delimiter == '/')? as isize);
+                self.last_pattern = Some(pattern);
+                rest = after;
+            }
+            _ => {}
+        }
+
+        // offsets: `+3`, `-`, `++`
+        let mut offset: isize = 0;
+        let mut has_offset = false;
+        while let Some(sign @ ('+' | '-')) = rest.chars().next() {
+            has_offset = true;
+            rest = &rest[1..];
+
+            let end = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+            let amount: isize = if end == 0 { 1 } else { rest[..end].parse()? };
+            rest = &rest[end..];
+
+            if sign == '+' {
+                offset += amount;
+            } else {
+                offset -= amount;
+            }
+        }
+
+        if base.is_none() && !has_offset {
+            return Ok((None, rest));
+        }
+
+        let line = base.unwrap_or(self.current as isize) + offset;
+        if line < 0 || line > self.buffer.get_file_lenght() as isize {
+            anyhow::bail!("E16: Invalid range");
+        }
+
+        Ok((Some(line as usize), rest))
+    }
+
+    // finds the next (or previous) line matching `pattern`, wrapping around the buffer
+    fn search(&self, pattern: &str, forward: bool) -> anyhow::Result<usize> {
+        let regex = match Regex::new(pattern) {
+            Ok(r) => r,
+            Err(_) => anyhow::bail!("E383: Invalid search string: {}", pattern),
+        };
+
+        let len = self.buffer.get_file_lenght();
+        let current = self.current.clamp(1, len) - 1;
+        for step in 1..=len {
+            let index = if forward {
+                (current + step) % len
+            } else {
+                (current + len * 2 - step) % len
+            };
+
+            if regex.is_match(&self.buffer.lines[index]) {
+                return Ok(index + 1);
+            }
+        }
+
+        anyhow::bail!("E486: Pattern not found: {}", pattern)
+    }
+
+    fn starts_address(c: char) -> bool {
+        c.is_ascii_digit() || matches!(c, '.' | '$' | '\'' | '/' | '?' | '+' | '-')
+    }
+}
+
+// splits `pat/rest` at the first unescaped delimiter, `\/` stands for a literal delimiter
+pub fn split_pattern(input: &str, delimiter: char) -> (String, &str) {
+    let mut pattern = String::new();
+    let mut chars = input.char_indices();
+
+    while let Some((i, c)) = chars.next() {
+        if c == delimiter {
+            return (pattern, &input[i + c.len_utf8()..]);
+        }
+        if c == '\\' {
+            match chars.next() {
+                Some((_, next)) if next == delimiter => pattern.push(next),
+                Some((_, next)) => {
+                    pattern.push(c);
+                    pattern.push(next);
+                }
+                None => pattern.push(c),
+            }
+            continue;
+        }
+        pattern.push(c);
+    }
+
+    (pattern, "")
+}
@@ -0,0 +1,390 @@
+use super::super::autocmd::EVENTS;
+use super::super::Editor;
+use super::CommandLine;
+
+// what a command takes after its name
+#[derive(Clone, Copy, PartialEq)]
+pub enum Args {
+    None,
+    // one word, quotes and `\ ` keep spaces in it
+    Word,
+    // one file name, like `Word` with `%` for the current file
+    File,
+    // the rest of the line as it was typed
+    Raw,
+}
+
+// what Tab completes in the arguments of a command
+#[derive(Clone, Copy)]
+pub enum Complete {
+    None,
+    Command,
+    File,
+    Option,
+    Setting,
+    Theme,
+    UserCommand,
+    Alias,
+    Values(&'static [&'static str]),
+}
+
+// a built-in command: its full name and the shortest abbreviation it can be typed as,
+// every abbreviation in between works too, like `:del` for `:delete`
+pub struct Command {
+    pub name: &'static str,
+    pub short: &'static str,
+    pub range: bool,
+    pub bang: bool,
+    pub args: Args,
+    pub complete: Complete,
+    // the arguments as `:help` shows them and what the command does
+    pub usage: &'static str,
+    pub help: &'static str,
+}
+
+const fn command(name: &'static str, short: &'static str, args: Args, usage: &'static str, help: &'static str) -> Command {
+    let complete = match args {
+        Args::File => Complete::File,
+        _ => Complete::None,
+    };
+    Command { name, short, range: false, bang: false, args, complete, usage, help }
+}
+
+// every built-in command, in the order `:help` lists them
+pub const COMMANDS: &[Command] = &[
+    Command { bang: true, ..command("quit", "q", Args::None, "", "Quit the editor") },
+    Command { range: true, bang: true, ..command("write", "w", Args::File, "[file]", "Write the buffer or the lines of the range") },
+    Command { bang: true, ..command("wq", "wq", Args::File, "[file]", "Write the buffer and quit") },
+    Command { bang: true, ..command("xit", "x", Args::File, "[file]", "Write the buffer and quit") },
+    Command { bang: true, ..command("edit", "e", Args::File, "{file}", "Open a file") },
+    Command { range: true, ..command("delete", "d", Args::Raw, "[x] [count]", "Delete lines into register x") },
+    Command { range: true, ..command("yank", "y", Args::Raw, "[x] [count]", "Yank lines into register x") },
+    Command { range: true, ..command("put", "pu", Args::Raw, "[x]", "Put the lines of register x below a line") },
+    Command { range: true, ..command("move", "m", Args::Raw, "{address}", "Move lines below an address") },
+    Command { range: true, ..command("copy", "co", Args::Raw, "{address}", "Copy lines below an address") },
+    Command { range: true, ..command("t", "t", Args::Raw, "{address}", "Same as :copy") },
+    Command { range: true, ..command(">", ">", Args::Raw, "[count]", "Shift lines right, once for every >") },
+    Command { range: true, ..command("<", "<", Args::Raw, "[count]", "Shift lines left, once for every <") },
+    Command { range: true, ..command("mark", "ma", Args::Raw, "{a-z}", "Set a mark on a line") },
+    Command { range: true, ..command("k", "k", Args::Raw, "{a-z}", "Same as :mark") },
+    Command { range: true, bang: true, ..command("normal", "norm", Args::Raw, "{keys}", "Run Normal mode keys on every line, `!` skips the mappings") },
+    Command {
+        range: true,
+        bang: true,
+        ..command("global", "g", Args::Raw, "/{pattern}/[cmd]", "Run a command on the lines matching a pattern")
+    },
+    Command { range: true, ..command("vglobal", "v", Args::Raw, "/{pattern}/[cmd]", "Run a command on the other lines") },
+    Command { range: true, bang: true, ..command("retab", "ret", Args::Raw, "[tabstop]", "Write tabs and spaces again") },
+    command("undo", "u", Args::None, "", "Undo the last change"),
+    command("redo", "red", Args::None, "", "Redo the last undone change"),
+    Command { complete: Complete::Option, ..command("set", "se", Args::Raw, "[option...]", "Show or change options") },
+    Command { complete: Complete::Option, ..command("setlocal", "setl", Args::Raw, "[option...]", "Same as :set for this buffer") },
+    Command { complete: Complete::Option, ..command("setglobal", "setg", Args::Raw, "[option...]", "Same as :set for the global values") },
+    Command { complete: Complete::Setting, ..command("config", "config", Args::Word, "[setting]", "Show settings and where they were set") },
+    command("source", "so", Args::File, "[file]", "Read a file over the configuration, or all of it again"),
+    Command { complete: Complete::Theme, ..command("colorscheme", "colo", Args::Word, "[name]", "Switch to another theme") },
+    Command {
+        complete: Complete::Values(&["on", "off", "enable", "clear"]),
+        ..command("syntax", "sy", Args::Word, "[on|off]", "Turn syntax highlighting on or off")
+    },
+    Command { bang: true, complete: Complete::UserCommand, ..command("command", "com", Args::Raw, "[Name [cmd]]", "Define or list user commands") },
+    Command { complete: Complete::UserCommand, ..command("delcommand", "delc", Args::Word, "{Name}", "Remove a user command") },
+    Command { complete: Complete::Alias, ..command("alias", "alias", Args::Raw, "[name [cmd]]", "Define or list aliases") },
+    Command { complete: Complete::Alias, ..command("unalias", "unalias", Args::Word, "{name}", "Remove an alias") },
+    Command { range: true, complete: Complete::File, ..command("!", "!", Args::Raw, "{cmd}", "Run a shell command or filter lines through it") },
+    Command { complete: Complete::Command, ..command("help", "h", Args::Word, "[command]", "List the commands or show what one does") },
+    Command { complete: Complete::File, ..command("plugin", "plug", Args::Raw, "[{executable} [args]]", "Start a plugin or list them") },
+    Command {
+        bang: true,
+        complete: Complete::Values(EVENTS),
+        ..command("autocmd", "au", Args::Raw, "[event [pattern [cmd]]]", "Run a command on an event, or list autocommands")
+    },
+    command("map", "map", Args::Raw, "[{keys} [{keys}]]", "Map keys in Normal and Visual mode"),
+    command("nmap", "nm", Args::Raw, "[{keys} [{keys}]]", "Map keys in Normal mode"),
+    command("vmap", "vm", Args::Raw, "[{keys} [{keys}]]", "Map keys in Visual mode"),
+    command("xmap", "xm", Args::Raw, "[{keys} [{keys}]]", "Same as :vmap"),
+    command("imap", "im", Args::Raw, "[{keys} [{keys}]]", "Map keys in Insert mode"),
+    command("cmap", "cm", Args::Raw, "[{keys} [{keys}]]", "Map keys in Command mode"),
+    command("noremap", "no", Args::Raw, "[{keys} [{keys}]]", "Like :map, the keys mapped to are not mapped again"),
+    command("nnoremap", "nn", Args::Raw, "[{keys} [{keys}]]", "Like :nmap, not mapped again"),
+    command("vnoremap", "vn", Args::Raw, "[{keys} [{keys}]]", "Like :vmap, not mapped again"),
+    command("xnoremap", "xn", Args::Raw, "[{keys} [{keys}]]", "Same as :vnoremap"),
+    command("inoremap", "ino", Args::Raw, "[{keys} [{keys}]]", "Like :imap, not mapped again"),
+    command("cnoremap", "cno", Args::Raw, "[{keys} [{keys}]]", "Like :cmap, not mapped again"),
+    command("unmap", "unm", Args::Raw, "{keys}", "Remove a Normal and Visual mode mapping"),
+    command("nunmap", "nun", Args::Raw, "{keys}", "Remove a Normal mode mapping"),
+    command("vunmap", "vu", Args::Raw, "{keys}", "Remove a Visual mode mapping"),
+    command("xunmap", "xu", Args::Raw, "{keys}", "Same as :vunmap"),
+    command("iunmap", "iu", Args::Raw, "{keys}", "Remove an Insert mode mapping"),
+    command("cunmap", "cu", Args::Raw, "{keys}", "Remove a Command mode mapping"),
+];
+
+// the built-in command `name` is short for, `>>` is `>` twice
+pub fn resolve(name: &str) -> Option<&'static Command> {
+    let name = match name.chars().next() {
+        Some(c @ ('>' | '<')) if name.chars().all(|x| x == c) => &name[..1],
+        _ => name,
+    };
+    COMMANDS.iter().find(|c| c.name.starts_with(name) && name.starts_with(c.short))
+}
+
+impl Command {
+    // `:[range]d[elete] [x] [count]`
+    pub fn usage(&self) -> String {
+        let mut usage = String::from(":");
+        if self.range {
+            usage.push_str("[range]");
+        }
+        usage.push_str(self.short);
+        if self.name.len() > self.short.len() {
+            usage.push_str(&format!("[{}]", &self.name[self.short.len()..]));
+        }
+        if self.bang {
+            usage.push_str("[!]");
+        }
+        if !self.usage.is_empty() {
+            usage.push(' ');
+            usage.push_str(self.usage);
+        }
+        usage
+    }
+}
+
+// the words of an argument: white space separates them, `"..."` and `'...'` keep them
+// together, in double quotes and outside of quotes `\` takes away the meaning of the next
+// white space, quote or `\`; other backslashes stay, like the one in `\%`
+pub fn split_words(args: &str) -> anyhow::Result<Vec<String>> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut quote = None;
+    let mut chars = args.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some('\''), c) => word.get_or_insert_with(String::new).push(c),
+            (_, '\\') if matches!(chars.peek(), Some(n) if n.is_whitespace() || matches!(n, '"' | '\'' | '\\')) => {
+                word.get_or_insert_with(String::new).push(chars.next().unwrap());
+            }
+            (Some(_), c) => word.get_or_insert_with(String::new).push(c),
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                word.get_or_insert_with(String::new);
+            }
+            (None, c) if c.is_whitespace() => words.extend(word.take()),
+            (None, c) => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    if quote.is_some() {
+        anyhow::bail!("E114: Missing quote: {}", args);
+    }
+    words.extend(word);
+
+    Ok(words)
+}
+
+// a command defined with `:command Name {replacement}`, `<args>`, `<q-args>` and `<bang>`
+// in the replacement stand for what the command was given
+pub struct UserCommand {
+    pub name: String,
+    pub replacement: String,
+    // commands from the configuration files are replaced when they are read again
+    pub from_config: bool,
+}
+
+// another name for a command, from `:alias W w`
+pub struct Alias {
+    pub name: String,
+    pub command: String,
+    pub from_config: bool,
+}
+
+// commands that run one more command can not go on for ever
+const MAX_DEPTH: usize = 100;
+
+pub struct UserCommands {
+    pub commands: Vec<UserCommand>,
+    pub aliases: Vec<Alias>,
+    depth: usize,
+}
+
+impl UserCommands {
+    pub fn new() -> Self {
+        Self { commands: Vec::new(), aliases: Vec::new(), depth: 0 }
+    }
+
+    pub fn clear_config(&mut self) {
+        self.commands.retain(|c| !c.from_config);
+        self.aliases.retain(|a| !a.from_config);
+    }
+
+    // every name a command can be typed as, for completion
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.commands.iter().map(|c| c.name.as_str()).chain(self.aliases.iter().map(|a| a.name.as_str()))
+    }
+}
+
+// `"text"` with `\` in front of quotes and backslashes
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn check_name(name: &str) -> anyhow::Result<()> {
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphabetic()) {
+        anyhow::bail!("E182: Invalid command name: {}", name);
+    }
+    Ok(())
+}
+
+impl Editor {
+    // `:command[!] Name {replacement}`, `:command` lists the commands
+    pub(super) fn ex_command(&mut self, cmd: &CommandLine, from_config: bool) -> anyhow::Result<()> {
+        let (name, replacement) = match cmd.args.split_once(char::is_whitespace) {
+            Some((name, replacement)) => (name, replacement.trim_start()),
+            None => (cmd.args.as_str(), ""),
+        };
+
+        if replacement.is_empty() {
+            let lines: Vec<String> = self
+                .user_commands
+                .commands
+                .iter()
+                .filter(|c| c.name.starts_with(name))
+                .map(|c| format!("{:<12} {}", c.name, c.replacement))
+                .collect();
+            match lines.len() {
+                0 => self.command_bar.message = Some("No user-defined commands found".to_string()),
+                1 => self.command_bar.message = lines.into_iter().next(),
+                _ => self.command_bar.show_output(lines),
+            }
+            return Ok(());
+        }
+
+        check_name(name)?;
+        if !name.starts_with(|c: char| c.is_ascii_uppercase()) {
+            anyhow::bail!("E183: User defined commands must start with an uppercase letter");
+        }
+        let commands = &mut self.user_commands.commands;
+        if let Some(index) = commands.iter().position(|c| c.name == name) {
+            // the configuration files replace their own commands when they are read again
+            let replaces = cmd.bang || (from_config && commands[index].from_config);
+            if !replaces {
+                anyhow::bail!("E174: Command already exists: add ! to replace it");
+            }
+            commands.remove(index);
+        }
+        commands.push(UserCommand { name: name.to_string(), replacement: replacement.to_string(), from_config });
+
+        Ok(())
+    }
+
+    // `:delcommand Name`
+    pub(super) fn ex_delcommand(&mut self, name: &str) -> anyhow::Result<()> {
+        let commands = &mut self.user_commands.commands;
+        match commands.iter().position(|c| c.name == name) {
+            Some(index) => {
+                commands.remove(index);
+                Ok(())
+            }
+            None => anyhow::bail!("E184: No such user-defined command: {}", name),
+        }
+    }
+
+    // `:alias W w`, `:alias` lists the aliases and `:unalias W` removes one
+    pub(super) fn ex_alias(&mut self, args: &str, remove: bool, from_config: bool) -> anyhow::Result<()> {
+        let (name, command) = match args.split_once(char::is_whitespace) {
+            Some((name, command)) => (name, command.trim_start()),
+            None => (args, ""),
+        };
+        let aliases = &mut self.user_commands.aliases;
+
+        if remove {
+            let before = aliases.len();
+            aliases.retain(|a| a.name != name);
+            if aliases.len() == before {
+                anyhow::bail!("No such alias: {}", name);
+            }
+            return Ok(());
+        }
+
+        if command.is_empty() {
+            let lines: Vec<String> = aliases
+                .iter()
+                .filter(|a| a.name.starts_with(name))
+                .map(|a| format!("{:<12} {}", a.name, a.command))
+                .collect();
+            match lines.len() {
+                0 => self.command_bar.message = Some("No aliases found".to_string()),
+                1 => self.command_bar.message = lines.into_iter().next(),
+                _ => self.command_bar.show_output(lines),
+            }
+            return Ok(());
+        }
+
+        check_name(name)?;
+        aliases.retain(|a| a.name != name);
+        aliases.push(Alias { name: name.to_string(), command: command.to_string(), from_config });
+
+        Ok(())
+    }
+
+    // runs the user command or alias `cmd` names, false when it is neither
+    pub(super) fn run_user_command(&mut self, cmd: &CommandLine) -> anyhow::Result<bool> {
+        let bang = if cmd.bang { "!" } else { "" };
+        let line = if let Some(alias) = self.user_commands.aliases.iter().find(|a| a.name == cmd.name) {
+            format!("{}{} {}", alias.command, bang, cmd.args)
+        } else if let Some(command) = self.user_commands.commands.iter().find(|c| c.name == cmd.name) {
+            command
+                .replacement
+                .replace("<args>", &cmd.args)
+                .replace("<q-args>", &quote(&cmd.args))
+                .replace("<bang>", bang)
+        } else {
+            return Ok(false);
+        };
+
+        if self.user_commands.depth >= MAX_DEPTH {
+            anyhow::bail!("E169: Command too recursive");
+        }
+        self.user_commands.depth += 1;
+        let result = self.parse_command_line(&line).and_then(|mut expanded| {
+            // the range typed in front of an alias goes to the command
+            if expanded.range.is_none() {
+                expanded.range = cmd.range;
+            }
+            // `alias q quit` style aliases name the built-in command under them
+            match expanded.name == cmd.name {
+                true => self.run_builtin(expanded),
+                false => self.run_command(expanded),
+            }
+        });
+        self.user_commands.depth -= 1;
+
+        result.map(|_| true)
+    }
+
+    // `:help` lists every command, `:help {command}` shows what one does
+    pub(super) fn ex_help(&mut self, name: &str) -> anyhow::Result<()> {
+        if name.is_empty() {
+            let lines = COMMANDS.iter().map(|c| format!("{:<36} {}", c.usage(), c.help)).collect();
+            self.command_bar.show_output(lines);
+            return Ok(());
+        }
+
+        let name = name.trim_start_matches(':');
+        let user = &self.user_commands;
+        self.command_bar.message = if let Some(alias) = user.aliases.iter().find(|a| a.name == name) {
+            Some(format!(":{}  alias for :{}", alias.name, alias.command))
+        } else if let Some(command) = user.commands.iter().find(|c| c.name == name) {
+            Some(format!(":{}  runs :{}", command.name, command.replacement))
+        } else if let Some(command) = self.plugins.commands.iter().find(|c| c.name == name) {
+            Some(format!(":{}  {}", command.name, command.help))
+        } else if let Some(command) = resolve(name) {
+            Some(format!("{}  {}", command.usage(), command.help))
+        } else {
+            anyhow::bail!("E149: Sorry, no help for {}", name);
+        };
+
+        Ok(())
+    }
+}
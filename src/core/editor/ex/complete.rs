@@ -0,0 +1,181 @@
+use std::path::Path;
+
+use super::super::command_bar::Completion;
+use super::super::config::setting_names;
+use super::super::options::{Kind, OPTIONS};
+use super::super::theme::Theme;
+use super::super::Editor;
+use super::commands::{resolve, Complete, COMMANDS};
+
+// byte offset where the command name starts in a command line, after the `:` and the range
+fn name_start(line: &str) -> usize {
+    let mut chars = line.char_indices().peekable();
+    if line.starts_with(':') {
+        chars.next();
+    }
+
+    while let Some(&(i, c)) = chars.peek() {
+        match c {
+            '0'..='9' | '.' | '$' | '%' | ',' | ';' | '+' | '-' | ' ' => {
+                chars.next();
+            }
+            // a mark name
+            '\'' => {
+                chars.next();
+                chars.next();
+            }
+            // a pattern, up to the next unescaped delimiter
+            '/' | '?' => {
+                chars.next();
+                while let Some((_, x)) = chars.next() {
+                    match x {
+                        '\\' => {
+                            chars.next();
+                        }
+                        x if x == c => break,
+                        _ => {}
+                    }
+                }
+            }
+            _ => return i,
+        }
+    }
+
+    line.len()
+}
+
+// the command name at the start of `text`: letters, `!` or a run of `>` or `<`
+fn name_len(text: &str) -> usize {
+    match text.chars().next() {
+        Some(c) if c.is_ascii_alphabetic() => text.find(|c: char| !c.is_ascii_alphabetic()).unwrap_or(text.len()),
+        Some(c @ ('>' | '<')) => text.find(|x: char| x != c).unwrap_or(text.len()),
+        Some('!') => 1,
+        _ => 0,
+    }
+}
+
+// files and directories starting with `word`, directories end in `/`
+fn complete_file(word: &str) -> Vec<String> {
+    let (dir, part) = match word.rfind('/') {
+        Some(i) => (&word[..=i], &word[i + 1..]),
+        None => ("", word),
+    };
+    let entries = match std::fs::read_dir(if dir.is_empty() { Path::new(".") } else { Path::new(dir) }) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            // hidden files only when their dot was typed
+            if !name.starts_with(part) || (name.starts_with('.') && !part.starts_with('.')) {
+                return None;
+            }
+            let slash = if entry.path().is_dir() { "/" } else { "" };
+            Some(format!("{}{}{}", dir, name, slash).replace(' ', "\\ "))
+        })
+        .collect()
+}
+
+// option names for `:set`, with `no` and `inv` for the flags and the values of an option
+// after `=`
+fn complete_option(word: &str) -> Vec<String> {
+    if let Some((name, value)) = word.split_once('=') {
+        let def = match OPTIONS.iter().find(|o| o.name == name || (!o.short.is_empty() && o.short == name)) {
+            Some(def) => def,
+            None => return Vec::new(),
+        };
+        return def.values.iter().filter(|v| v.starts_with(value)).map(|v| format!("{}={}", name, v)).collect();
+    }
+
+    let (negation, name) = match (word.strip_prefix("no"), word.strip_prefix("inv")) {
+        (_, Some(name)) => ("inv", name),
+        (Some(name), _) => ("no", name),
+        _ => ("", word),
+    };
+    let mut names: Vec<String> = OPTIONS
+        .iter()
+        .filter(|o| negation.is_empty() || o.kind == Kind::Bool)
+        .filter(|o| o.name.starts_with(name))
+        .map(|o| format!("{}{}", negation, o.name))
+        .collect();
+    if "all".starts_with(word) {
+        names.push("all".to_string());
+    }
+    names
+}
+
+impl Editor {
+    // what `complete` gives for the word `word`
+    fn completions(&self, complete: Complete, word: &str) -> Vec<String> {
+        let names: Vec<String> = match complete {
+            Complete::None => Vec::new(),
+            Complete::Command => COMMANDS
+                .iter()
+                .map(|c| c.name)
+                .filter(|name| name.chars().all(|c| c.is_ascii_alphabetic()))
+                .chain(self.user_commands.names())
+                .chain(self.plugins.commands.iter().map(|c| c.name.as_str()))
+                .map(String::from)
+                .collect(),
+            Complete::File => return complete_file(word),
+            Complete::Option => return complete_option(word),
+            Complete::Setting => setting_names().map(String::from).collect(),
+            Complete::Theme => Theme::names(self.config_options.config_dir().map(|d| d.join("themes")).as_deref()),
+            Complete::UserCommand => self.user_commands.commands.iter().map(|c| c.name.clone()).collect(),
+            Complete::Alias => self.user_commands.aliases.iter().map(|a| a.name.clone()).collect(),
+            Complete::Values(values) => values.iter().map(|v| v.to_string()).collect(),
+        };
+
+        names.into_iter().filter(|name| name.starts_with(word)).collect()
+    }
+
+    // the names that fit the command name or the last argument at the end of `line`
+    pub(super) fn completion_for(&self, line: &str) -> Option<Completion> {
+        let start = name_start(line);
+        let rest = &line[start..];
+        let len = name_len(rest);
+
+        // still typing the name
+        let (complete, word_start) = if len == rest.len() && !rest.starts_with(['!', '>', '<']) {
+            (Complete::Command, start)
+        } else {
+            let name = &rest[..len];
+            let mut args = &rest[len..];
+            if name != "!" {
+                args = args.strip_prefix('!').unwrap_or(args);
+                // the arguments start after white space
+                if !args.starts_with(char::is_whitespace) {
+                    return None;
+                }
+            }
+            // user commands and aliases take anything
+            let user = self.user_commands.names().chain(self.plugins.commands.iter().map(|c| c.name.as_str())).any(|n| n == name);
+            let complete = match resolve(name) {
+                Some(command) if !user => command.complete,
+                _ => Complete::None,
+            };
+
+            // the word is what follows the last white space that is not escaped with `\`
+            let bytes = line.as_bytes();
+            let word_start = (0..line.len())
+                .rev()
+                .find(|&i| bytes[i] == b' ' && (i == 0 || bytes[i - 1] != b'\\'))
+                .map_or(0, |i| i + 1)
+                .max(line.len() - args.len());
+            (complete, word_start)
+        };
+
+        let word = &line[word_start..];
+        let mut matches = self.completions(complete, &word.replace("\\ ", " "));
+        matches.sort();
+        matches.dedup();
+        if matches.is_empty() {
+            return None;
+        }
+
+        Some(Completion { prefix: line[..word_start].to_string(), matches, index: 0 })
+    }
+}
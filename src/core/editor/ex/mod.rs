@@ -0,0 +1,559 @@
+use address::{split_pattern, AddressParser};
+use commands::{resolve, split_words, Args};
+use regex::Regex;
+
+use super::options::Level;
+use super::keys::parse_keys;
+use super::{Editor, Mode, Register};
+
+pub mod address;
+pub mod commands;
+mod complete;
+mod set;
+mod shell;
+
+// one parsed command line: `[range]name[!] [args]`
+pub struct CommandLine {
+    pub range: Option<(usize, usize)>,
+    pub name: String,
+    pub bang: bool,
+    pub args: String,
+}
+
+impl CommandLine {
+    // the command as it was typed, without the range
+    fn typed(&self) -> String {
+        let bang = if self.bang { "!" } else { "" };
+        format!("{}{} {}", self.name, bang, self.args).trim_end().to_string()
+    }
+}
+
+impl Editor {
+    pub(super) fn execute_command(&mut self, command: String) -> anyhow::Result<()> {
+        let command = command.trim();
+        let command = command.strip_prefix(':').unwrap_or(command);
+        if command.is_empty() {
+            return Ok(());
+        }
+
+        // everything a command line changes is undone at once
+        self.begin_change();
+        let result = self
+            .parse_command_line(command)
+            .and_then(|cmd| self.run_command(cmd));
+        self.buffer.end_change();
+
+        result
+    }
+
+    // a `nmap`, `command`, `alias`, `autocmd` or `plugin` line of a configuration file
+    pub(super) fn run_config_line(&mut self, command: &str, args: &str) -> anyhow::Result<()> {
+        match command {
+            "command" | "command!" => {
+                let cmd = CommandLine {
+                    range: None,
+                    name: "command".to_string(),
+                    bang: command.ends_with('!'),
+                    args: args.to_string(),
+                };
+                self.ex_command(&cmd, true)
+            }
+            "alias" => self.ex_alias(args, false, true),
+            "autocmd" | "autocmd!" | "au" | "au!" => self.ex_autocmd(args, command.ends_with('!'), true),
+            "plugin" => self.ex_plugin(args),
+            _ => self.ex_map(command, args, true),
+        }
+    }
+
+    // Tab in the command bar completes the name of the command being typed or its last
+    // argument, more Tabs go through the other names that fit
+    pub(super) fn complete_command(&mut self) {
+        let bar = &mut self.command_bar;
+        if let Some(completion) = &mut bar.completion {
+            if bar.command == completion.shown() {
+                completion.index = (completion.index + 1) % completion.matches.len();
+                bar.command = completion.shown();
+                return;
+            }
+        }
+
+        let completion = self.completion_for(&self.command_bar.command);
+        if let Some(completion) = &completion {
+            self.command_bar.command = completion.shown();
+        }
+        self.command_bar.completion = completion;
+    }
+
+    pub(super) fn parse_command_line(&mut self, input: &str) -> anyhow::Result<CommandLine> {
+        let mut parser = AddressParser::new(&self.buffer, self.current_line(), self.last_pattern.clone());
+        let (range, rest) = parser.parse_range(input)?;
+        self.last_pattern = parser.last_pattern;
+
+        let rest = rest.trim_start();
+        let name_end = match rest.chars().next() {
+            Some(c) if c.is_ascii_alphabetic() => rest
+                .find(|c: char| !c.is_ascii_alphabetic())
+                .unwrap_or(rest.len()),
+            // `>` and `<` can be repeated to shift several times
+            Some(c @ ('>' | '<')) => rest.find(|x: char| x != c).unwrap_or(rest.len()),
+            Some(c) => c.len_utf8(),
+            None => 0,
+        };
+
+        let name = rest[..name_end].to_string();
+        let mut args = &rest[name_end..];
+        // `:!` takes everything after it as the shell command
+        let bang = name != "!" && args.starts_with('!');
+        if bang {
+            args = &args[1..];
+        }
+
+        Ok(CommandLine {
+            range,
+            name,
+            bang,
+            args: args.trim().to_string(),
+        })
+    }
+
+    fn run_command(&mut self, cmd: CommandLine) -> anyhow::Result<()> {
+        // a bare range jumps to its last line
+        if cmd.name.is_empty() {
+            if let Some((_, last)) = cmd.range {
+                self.goto_line(last.saturating_sub(1));
+            }
+            return Ok(());
+        }
+
+        if self.run_user_command(&cmd)? {
+            return Ok(());
+        }
+        if self.plugins.run_command(&cmd.name, &cmd.args, cmd.bang, cmd.range) {
+            return Ok(());
+        }
+        self.run_builtin(cmd)
+    }
+
+    fn run_builtin(&mut self, cmd: CommandLine) -> anyhow::Result<()> {
+        let name = cmd.name.as_str();
+        // the names `cmd_quit`, `cmd_write` and `cmd_edit` set go before the abbreviations
+        let command = if name == self.qiut.trim_start_matches(':') {
+            resolve("quit")
+        } else if name == self.save.trim_start_matches(':') {
+            resolve("write")
+        } else if name == self.edit.trim_start_matches(':') {
+            resolve("edit")
+        } else {
+            resolve(name)
+        };
+        let command = match command {
+            Some(command) => command,
+            None => anyhow::bail!("E492: Not an editor command: {}", cmd.typed()),
+        };
+
+        if cmd.range.is_some() && !command.range {
+            anyhow::bail!("E481: No range allowed");
+        }
+        if cmd.bang && !command.bang {
+            anyhow::bail!("E477: No ! allowed");
+        }
+        // the one word `Word` and `File` commands take, without its quotes
+        let arg = match command.args {
+            Args::None if !cmd.args.is_empty() => anyhow::bail!("E488: Trailing characters: {}", cmd.args),
+            Args::Word | Args::File => {
+                let mut words = split_words(&cmd.args)?;
+                if words.len() > 1 {
+                    match command.args {
+                        Args::File => anyhow::bail!("E172: Only one file name allowed"),
+                        _ => anyhow::bail!("E488: Trailing characters: {}", words[1..].join(" ")),
+                    }
+                }
+                let word = words.pop().unwrap_or_default();
+                match command.args {
+                    Args::File => self.expand_file_name(&word)?,
+                    _ => word,
+                }
+            }
+            _ => String::new(),
+        };
+
+        match command.name {
+            "quit" => {
+                self.running = false;
+            }
+            "write" => {
+                self.ex_write(&cmd, &arg)?;
+            }
+            "wq" | "xit" => {
+                self.ex_write(&cmd, &arg)?;
+                self.running = false;
+            }
+            "edit" => {
+                self.buffer.load_file(Some(&arg))?;
+                self.config.options.reset_buffer();
+                self.apply_options();
+                self.cursor_x = 0;
+                self.cursor_y = 0;
+                self.viewport_top = 0;
+                self.emit_file_read();
+            }
+            "delete" => {
+                let (first, last, register) = self.range_with_count(&cmd)?;
+                let removed = self.buffer.remove_lines(first, last);
+                self.registers.insert(register, Register::linewise(removed));
+                self.goto_line(first);
+            }
+            "yank" => {
+                let (first, last, register) = self.range_with_count(&cmd)?;
+                self.registers.insert(register, Register::linewise(self.buffer.lines[first..=last].to_vec()));
+            }
+            "put" => {
+                let line = match cmd.range {
+                    Some((_, last)) => last,
+                    None => self.current_line() + 1,
+                };
+                let register = cmd.args.chars().next().unwrap_or('"');
+                self.put_lines(register, line)?;
+            }
+            "move" => {
+                let (first, last) = self.line_range(&cmd)?;
+                let dest = self.parse_destination(&cmd.args)?;
+                if dest > first && dest <= last {
+                    anyhow::bail!("E134: Cannot move a range of lines into itself");
+                }
+
+                self.buffer.move_lines(first, last, dest.checked_sub(1));
+                let new_last = if dest > last { dest - 1 } else { dest + last - first };
+                self.goto_line(new_last);
+            }
+            "t" | "copy" => {
+                let (first, last) = self.line_range(&cmd)?;
+                let dest = self.parse_destination(&cmd.args)?;
+
+                let copied = self.buffer.lines[first..=last].to_vec();
+                let count = copied.len();
+                self.buffer.insert_lines(dest, copied);
+                self.goto_line(dest + count - 1);
+            }
+            "k" | "mark" => {
+                let (_, last) = self.line_range(&cmd)?;
+                match cmd.args.chars().next() {
+                    Some(m) if m.is_ascii_lowercase() && cmd.args.len() == 1 => {
+                        self.buffer.set_mark(m, last);
+                    }
+                    Some(_) => anyhow::bail!("E191: Argument must be a letter"),
+                    None => anyhow::bail!("E471: Argument required"),
+                }
+            }
+            "normal" => {
+                let (first, last) = self.line_range(&cmd)?;
+                self.ex_normal(first, last, &cmd.args, !cmd.bang)?;
+            }
+            "global" | "vglobal" => {
+                let invert = cmd.bang || command.name == "vglobal";
+                self.ex_global(&cmd, invert)?;
+            }
+            "retab" => {
+                self.ex_retab(&cmd)?;
+            }
+            "config" => match arg.as_str() {
+                "" => self.command_bar.show_output(self.config.describe_all()),
+                key => match self.config.describe(key) {
+                    Some(line) => self.command_bar.message = Some(line),
+                    None => anyhow::bail!("Unknown setting: {}", key),
+                },
+            },
+            "source" => match arg.as_str() {
+                "" => self.reload_config()?,
+                path => self.source(path)?,
+            },
+            "colorscheme" => match arg.as_str() {
+                "" => self.command_bar.message = Some(self.theme.name.clone()),
+                name => self.set_theme(name)?,
+            },
+            "syntax" => match arg.as_str() {
+                "on" | "enable" => self.ex_set("syntax", Level::Both)?,
+                "off" | "clear" => self.ex_set("nosyntax", Level::Both)?,
+                "" => self.command_bar.message = Some(format!("syntax {}", if self.syntax.enabled { "on" } else { "off" })),
+                _ => anyhow::bail!("E475: Invalid argument: {}", arg),
+            },
+            "set" => {
+                self.ex_set(&cmd.args, Level::Both)?;
+            }
+            "setlocal" => {
+                self.ex_set(&cmd.args, Level::Local)?;
+            }
+            "setglobal" => {
+                self.ex_set(&cmd.args, Level::Global)?;
+            }
+            "undo" => {
+                let cursor = self.cursor_position();
+                match self.buffer.undo(cursor) {
+                    Some(position) => self.restore_cursor(position),
+                    None => anyhow::bail!("Already at oldest change"),
+                }
+            }
+            "redo" => {
+                let cursor = self.cursor_position();
+                match self.buffer.redo(cursor) {
+                    Some(position) => self.restore_cursor(position),
+                    None => anyhow::bail!("Already at newest change"),
+                }
+            }
+            "command" => {
+                self.ex_command(&cmd, false)?;
+            }
+            "delcommand" => {
+                self.ex_delcommand(&arg)?;
+            }
+            "alias" => {
+                self.ex_alias(&cmd.args, false, false)?;
+            }
+            "unalias" => {
+                self.ex_alias(&arg, true, false)?;
+            }
+            "!" => {
+                self.ex_shell(&cmd)?;
+            }
+            "help" => {
+                self.ex_help(&arg)?;
+            }
+            "plugin" => {
+                self.ex_plugin(&cmd.args)?;
+            }
+            "autocmd" => {
+                self.ex_autocmd(&cmd.args, cmd.bang, false)?;
+            }
+            ">" | "<" => {
+                let (first, last, _) = self.range_with_count(&cmd)?;
+                for line in first..=last {
+                    for _ in 0..name.len() {
+                        self.shift_line(line, command.name == ">");
+                    }
+                }
+                self.goto_line(last);
+            }
+            // the mapping commands
+            name => {
+                self.ex_map(name, &cmd.args, false)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // 0-based range of the command, the current line when no range was given
+    fn line_range(&self, cmd: &CommandLine) -> anyhow::Result<(usize, usize)> {
+        let (first, last) = match cmd.range {
+            Some(r) => r,
+            None => return Ok((self.current_line(), self.current_line())),
+        };
+
+        if first == 0 || last > self.buffer.get_file_lenght() {
+            anyhow::bail!("E16: Invalid range");
+        }
+
+        Ok((first - 1, last - 1))
+    }
+
+    // range for `:d [x] [count]` style commands, a count starts at the last line of the range
+    fn range_with_count(&self, cmd: &CommandLine) -> anyhow::Result<(usize, usize, char)> {
+        let (mut first, mut last) = self.line_range(cmd)?;
+        let mut args = cmd.args.as_str();
+
+        let mut register = '"';
+        if let Some(c) = args.chars().next() {
+            if c.is_ascii_alphabetic() {
+                register = c;
+                args = args[1..].trim_start();
+            }
+        }
+
+        if !args.is_empty() {
+            let count: usize = match args.parse() {
+                Ok(c) if c > 0 => c,
+                _ => anyhow::bail!("E488: Trailing characters: {}", args),
+            };
+            first = last;
+            last = (last + count - 1).min(self.buffer.get_file_lenght() - 1);
+        }
+
+        Ok((first, last, register))
+    }
+
+    // destination of `:m` and `:t`, 1-based where 0 means above the first line
+    fn parse_destination(&mut self, args: &str) -> anyhow::Result<usize> {
+        let mut parser = AddressParser::new(&self.buffer, self.current_line(), self.last_pattern.clone());
+        let (dest, rest) = parser.parse_address(args)?;
+        self.last_pattern = parser.last_pattern;
+
+        match dest {
+            Some(d) if rest.trim().is_empty() => Ok(d),
+            Some(_) => anyhow::bail!("E488: Trailing characters: {}", rest.trim()),
+            None => anyhow::bail!("E14: Invalid address"),
+        }
+    }
+
+    // `:w [file]`, `file` is the argument with `%` expanded
+    fn ex_write(&mut self, cmd: &CommandLine, file: &str) -> anyhow::Result<()> {
+        let range = match cmd.range {
+            Some(_) if file.is_empty() && !cmd.bang => anyhow::bail!("E140: Use ! to write partial buffer"),
+            Some(_) => Some(self.line_range(cmd)?),
+            None => None,
+        };
+        let file = match (file, &self.buffer.file) {
+            ("", Some(name)) => name.clone(),
+            ("", None) => anyhow::bail!("E32: No file name"),
+            (file, _) => file.to_string(),
+        };
+
+        self.write_file(&file, range)
+    }
+
+    // runs normal mode keys on every line of the range, through the mappings unless it is
+    // `:normal!`
+    fn ex_normal(&mut self, first: usize, last: usize, keys: &str, remap: bool) -> anyhow::Result<()> {
+        let keys = parse_keys(keys);
+
+        for line in first..=last {
+            if line >= self.buffer.get_file_lenght() {
+                break;
+            }
+            self.goto_line(line);
+            self.cursor_x = 0;
+            self.set_mode(Mode::Normal);
+
+            match remap {
+                true => self.feed_mapped_keys(&keys)?,
+                false => self.feed_keys(&keys)?,
+            }
+        }
+        self.set_mode(Mode::Normal);
+        self.pending.clear();
+
+        Ok(())
+    }
+
+    // `:g/pattern/cmd` runs `cmd` on every matching line (`invert` picks the others),
+    // lines are flagged first so deleting or moving lines on the way is safe
+    fn ex_global(&mut self, cmd: &CommandLine, invert: bool) -> anyhow::Result<()> {
+        if self.in_global {
+            anyhow::bail!("E147: Cannot do :global recursive");
+        }
+
+        let (first, last) = match cmd.range {
+            Some(_) => self.line_range(cmd)?,
+            None => (0, self.buffer.get_file_lenght() - 1),
+        };
+
+        let delimiter = match cmd.args.chars().next() {
+            Some(c) if !c.is_alphanumeric() && c != '"' && c != '|' && c != '\\' => c,
+            _ => anyhow::bail!("E476: Invalid command"),
+        };
+        let (pattern, command) = split_pattern(&cmd.args[delimiter.len_utf8()..], delimiter);
+        let pattern = if pattern.is_empty() {
+            match &self.last_pattern {
+                Some(p) => p.clone(),
+                None => anyhow::bail!("E35: No previous regular expression"),
+            }
+        } else {
+            pattern
+        };
+        let regex = match Regex::new(&pattern) {
+            Ok(r) => r,
+            Err(_) => anyhow::bail!("E383: Invalid search string: {}", pattern),
+        };
+        self.last_pattern = Some(pattern.clone());
+
+        let matching: Vec<usize> = (first..=last)
+            .filter(|line| regex.is_match(&self.buffer.lines[*line]) != invert)
+            .collect();
+        if matching.is_empty() {
+            anyhow::bail!("E486: Pattern not found: {}", pattern);
+        }
+        self.buffer.set_flagged(matching);
+
+        let command = command.trim().to_string();
+        self.in_global = true;
+        let mut result = Ok(());
+        while let Some(line) = self.buffer.take_flagged() {
+            self.goto_line(line);
+            self.cursor_x = 0;
+            if command.is_empty() {
+                continue;
+            }
+
+            result = self
+                .parse_command_line(&command)
+                .and_then(|cmd| self.run_command(cmd));
+            if result.is_err() || !self.running {
+                break;
+            }
+        }
+        self.in_global = false;
+        self.buffer.set_flagged(Vec::new());
+
+        result
+    }
+
+    // moves a line `shiftwidth` columns right or left, the indentation is written again
+    // with tabs or spaces as `expandtab` says
+    fn shift_line(&mut self, line: usize, right: bool) {
+        let text = &self.buffer.lines[line];
+        if text.is_empty() {
+            return;
+        }
+        let indent = text.len() - text.trim_start().len();
+        let width = self.tabs.display_col(text, indent);
+        let width = if right {
+            width + self.tabs.shiftwidth()
+        } else {
+            width.saturating_sub(self.tabs.shiftwidth())
+        };
+
+        let filled = self.tabs.fill(0, width);
+        self.buffer.lines[line].replace_range(..indent, &filled);
+    }
+
+    // `:retab [n]` writes the tabs of the range again for the current settings, `n` sets a new
+    // `tabstop`; with `!` runs of spaces are turned into tabs too
+    fn ex_retab(&mut self, cmd: &CommandLine) -> anyhow::Result<()> {
+        let old_tabstop = self.tabs.tabstop;
+        if !cmd.args.is_empty() {
+            match cmd.args.parse::<usize>() {
+                Ok(0) => anyhow::bail!("E487: Argument must be positive: {}", cmd.args),
+                // through the option, so `:set ts?` and later `:set`s see it
+                Ok(n) => self.ex_set(&format!("tabstop={}", n), Level::Both)?,
+                Err(_) => anyhow::bail!("E475: Invalid argument: {}", cmd.args),
+            }
+        }
+
+        let (first, last) = match cmd.range {
+            Some(_) => self.line_range(cmd)?,
+            None => (0, self.buffer.get_file_lenght() - 1),
+        };
+        for line in first..=last {
+            let text = &self.buffer.lines[line];
+            let retabbed = self.tabs.retab(text, old_tabstop, cmd.bang);
+            if retabbed != *text {
+                self.buffer.lines[line] = retabbed;
+            }
+        }
+
+        Ok(())
+    }
+
+    // inserts the lines of `register` below 1-based line `after` (0 puts them above the first line)
+    pub(super) fn put_lines(&mut self, register: char, after: usize) -> anyhow::Result<()> {
+        let lines = match self.registers.get(&register) {
+            Some(r) => r.lines.clone(),
+            None => anyhow::bail!("E353: Nothing in register {}", register),
+        };
+
+        let count = lines.len();
+        self.buffer.insert_lines(after, lines);
+        self.goto_line(after + count - 1);
+
+        Ok(())
+    }
+}
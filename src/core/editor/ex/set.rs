@@ -0,0 +1,136 @@
+use crossterm::{terminal, QueueableCommand};
+
+use super::super::options::{self, Kind, Level, Options, Value, OPTIONS};
+use super::super::Editor;
+
+impl Editor {
+    // `:set wrap`, `:set nowrap`, `:set invwrap` / `:set wrap!`, `:set so=5`, `:set sbr?`,
+    // `:set ts&`; `:setlocal` and `:setglobal` change only one of the values of buffer and
+    // window options
+    pub(super) fn ex_set(&mut self, args: &str, level: Level) -> anyhow::Result<()> {
+        let args = split_set_args(args);
+        if args.is_empty() || args == ["all"] {
+            let all = !args.is_empty();
+            let shown: Vec<String> = (0..OPTIONS.len())
+                .filter(|&i| all || !self.config.options.is_default(i))
+                .map(|i| self.show_option(i, level))
+                .collect();
+            match shown.len() {
+                0 => self.command_bar.message = Some("All options are at their defaults".to_string()),
+                1 => self.command_bar.message = shown.into_iter().next(),
+                _ => self.command_bar.show_output(shown),
+            }
+            return Ok(());
+        }
+
+        let mut shown = Vec::new();
+        let mut changed = false;
+        for arg in args {
+            let (name, value) = match arg.split_once('=') {
+                Some((name, value)) => (name, Some(value)),
+                None => (arg.as_str(), None),
+            };
+
+            // `wrap?`, `wrap!`, `ts&`
+            let suffix = name.chars().last().filter(|c| "?!&".contains(*c)).filter(|_| value.is_none());
+            let name = match suffix {
+                Some(_) => &name[..name.len() - 1],
+                None => name,
+            };
+            let (index, prefix) = match options::find(name) {
+                Some(index) => (index, ""),
+                None => match ["no", "inv"].iter().find_map(|p| Some((options::find(name.strip_prefix(p)?)?, *p))) {
+                    Some((index, prefix)) if OPTIONS[index].kind == Kind::Bool => (index, prefix),
+                    _ => anyhow::bail!("E518: Unknown option: {}", name),
+                },
+            };
+            let def = &OPTIONS[index];
+            let current = self.option_value(index, level).clone();
+
+            let new = match (prefix, suffix, value, &current) {
+                ("", Some('?'), None, _) => {
+                    shown.push(self.show_option(index, level));
+                    continue;
+                }
+                ("", Some('&'), None, _) => Options::default_value(index),
+                ("no", None, None, Value::Bool(_)) => Value::Bool(false),
+                ("inv", None, None, Value::Bool(b)) | ("", Some('!'), None, Value::Bool(b)) => Value::Bool(!b),
+                ("", None, None, Value::Bool(_)) => Value::Bool(true),
+                // a number or text without a value is shown, like with `?`
+                ("", None, None, _) => {
+                    shown.push(self.show_option(index, level));
+                    continue;
+                }
+                ("", None, Some(text), Value::Number(_)) if text.parse::<usize>().is_err() => {
+                    anyhow::bail!("E521: Number required after =: {}", arg)
+                }
+                ("", None, Some(text), Value::Number(_) | Value::Text(_)) => match options::parse(def, text) {
+                    Ok(value) => value,
+                    Err(_) if def.kind == Kind::Number => anyhow::bail!("E487: Argument must be positive: {}", arg),
+                    Err(_) => anyhow::bail!("E474: Invalid argument: {}", arg),
+                },
+                _ => anyhow::bail!("E474: Invalid argument: {}", arg),
+            };
+
+            self.config.options.set(index, new, level);
+            self.config.set_origin(def.name, ":set");
+            changed = true;
+        }
+
+        if changed {
+            self.apply_options();
+            // the gutter, the status bar and the colours may have moved or changed
+            if self.terminal {
+                self.stdout.queue(terminal::Clear(terminal::ClearType::All))?;
+            }
+        }
+        if !shown.is_empty() {
+            self.command_bar.message = Some(shown.join("  "));
+        }
+
+        Ok(())
+    }
+
+    // the value `:setglobal` works on, or the one of the current buffer and window
+    fn option_value(&self, index: usize, level: Level) -> &Value {
+        match level {
+            Level::Global => self.config.options.global_value(index),
+            Level::Both | Level::Local => self.config.options.value(index),
+        }
+    }
+
+    // `wrap`, `nowrap` or `so=5`
+    fn show_option(&self, index: usize, level: Level) -> String {
+        let name = OPTIONS[index].name;
+        match self.option_value(index, level) {
+            Value::Bool(true) => name.to_string(),
+            Value::Bool(false) => format!("no{}", name),
+            Value::Number(n) => format!("{}={}", name, n),
+            Value::Text(s) => format!("{}={}", name, s),
+        }
+    }
+}
+
+// splits `:set` arguments at white space, `\ ` puts a space into a value
+fn split_set_args(args: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut part = String::new();
+    let mut chars = args.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => part.extend(chars.next()),
+            c if c.is_whitespace() => {
+                if !part.is_empty() {
+                    parts.push(std::mem::take(&mut part));
+                }
+            }
+            c => part.push(c),
+        }
+    }
+    if !part.is_empty() {
+        parts.push(part);
+    }
+
+    parts
+}
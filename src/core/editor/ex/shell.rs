@@ -0,0 +1,127 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use super::super::Editor;
+use super::CommandLine;
+
+// the text `save` would write for `lines`
+fn file_text(lines: &[String]) -> String {
+    lines.iter().map(|line| format!("{}\n", line)).collect()
+}
+
+impl Editor {
+    // `%` in a command argument stands for the current file, `\%` for a `%`
+    pub(super) fn expand_file_name(&self, args: &str) -> anyhow::Result<String> {
+        let mut expanded = String::new();
+        let mut chars = args.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' if chars.peek() == Some(&'%') => expanded.push(chars.next().unwrap()),
+                '%' => match &self.buffer.file {
+                    Some(file) => expanded.push_str(file),
+                    None => anyhow::bail!("E499: Empty file name for '%'"),
+                },
+                c => expanded.push(c),
+            }
+        }
+        Ok(expanded)
+    }
+
+    // `:!cmd` runs `cmd` in the shell and shows what it printed, `:{range}!cmd` puts the
+    // lines of the range through it instead
+    pub(super) fn ex_shell(&mut self, cmd: &CommandLine) -> anyhow::Result<()> {
+        if cmd.args.is_empty() {
+            anyhow::bail!("E471: Argument required");
+        }
+        let command = self.expand_file_name(&cmd.args)?;
+
+        if cmd.range.is_some() {
+            let (first, last) = self.line_range(cmd)?;
+            let output = run_shell(&command, Some(&file_text(&self.buffer.lines[first..=last])))?;
+            let mut lines: Vec<String> = output.lines().map(|s| s.to_string()).collect();
+            if lines.is_empty() {
+                lines.push(String::new());
+            }
+            let whole = first == 0 && last + 1 == self.buffer.lines.len();
+            self.buffer.remove_lines(first, last);
+            let count = lines.len();
+            self.buffer.insert_lines(first, lines);
+            // removing every line leaves an empty one behind
+            if whole {
+                self.buffer.lines.pop();
+            }
+            self.goto_line(first + count - 1);
+            return Ok(());
+        }
+
+        // a command like `rustfmt %` changes the file, the buffer follows it when it had
+        // nothing that was not written yet
+        let file = self.buffer.file.clone();
+        let written = file.as_ref().and_then(|f| std::fs::read_to_string(f).ok());
+        let unchanged = written.as_deref() == Some(file_text(&self.buffer.lines).as_str());
+
+        let output = run_shell(&command, None)?;
+
+        if let (true, Some(file)) = (unchanged, &file) {
+            if let Ok(text) = std::fs::read_to_string(file) {
+                if Some(&text) != written.as_ref() {
+                    let mut lines: Vec<String> = text.lines().map(|s| s.to_string()).collect();
+                    if lines.is_empty() {
+                        lines.push(String::new());
+                    }
+                    self.buffer.lines = lines;
+                    let cursor = self.cursor_position();
+                    self.restore_cursor(cursor);
+                    self.emit_file_read();
+                }
+            }
+        }
+
+        let lines: Vec<String> = output.lines().map(|s| s.to_string()).collect();
+        match lines.len() {
+            0 => self.command_bar.message = Some(format!(":!{}", command)),
+            1 => self.command_bar.message = lines.into_iter().next(),
+            _ => self.command_bar.show_output(lines),
+        }
+
+        Ok(())
+    }
+}
+
+// runs `command` with `sh -c`, `input` goes to its standard input; returns what it printed
+// on standard output and error
+fn run_shell(command: &str, input: Option<&str>) -> anyhow::Result<String> {
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdin(if input.is_some() { Stdio::piped() } else { Stdio::null() })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    // the input is written while the output is read, a full pipe would stop both sides
+    let writer = match (input, child.stdin.take()) {
+        (Some(input), Some(mut stdin)) => {
+            let input = input.to_string();
+            // a command that does not read all of its input closes the pipe early
+            Some(std::thread::spawn(move || {
+                let _ = stdin.write_all(input.as_bytes());
+            }))
+        }
+        _ => None,
+    };
+    let output = child.wait_with_output()?;
+    if let Some(writer) = writer {
+        let _ = writer.join();
+    }
+
+    let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+    text.push_str(&String::from_utf8_lossy(&output.stderr));
+    if !output.status.success() && input.is_none() {
+        if !text.is_empty() && !text.ends_with('\n') {
+            text.push('\n');
+        }
+        text.push_str(&format!("shell returned {}", output.status.code().unwrap_or(-1)));
+    }
+    Ok(text)
+}
@@ -0,0 +1,95 @@
+use std::collections::HashMap;
+
+use super::config::IndentSettings;
+use super::tabs::Tabs;
+
+// characters that start a deeper level at the end of a line, for file types without a list
+// of their own
+const DEFAULT_INDENT_AFTER: &str = "{ ( [";
+
+// how new lines are indented: `autoindent` copies the indentation of the line above,
+// `smartindent` does too and adds a level after a line ending in one of the file type's
+// characters, both are set from the options of the same names
+pub struct Indent {
+    pub autoindent: bool,
+    pub smartindent: bool,
+    default: Vec<char>,
+    by_filetype: HashMap<String, Vec<char>>,
+}
+
+// "{ ( :" into its characters
+fn parse_chars(list: &str) -> Vec<char> {
+    list.split_whitespace().filter_map(|c| c.chars().next()).collect()
+}
+
+// leading white space of `text`
+pub fn leading(text: &str) -> &str {
+    &text[..text.len() - text.trim_start().len()]
+}
+
+// the closing character of a bracket
+fn closing(open: char) -> Option<char> {
+    match open {
+        '{' => Some('}'),
+        '(' => Some(')'),
+        '[' => Some(']'),
+        _ => None,
+    }
+}
+
+impl Indent {
+    pub fn new(settings: &IndentSettings) -> Self {
+        let mut by_filetype: HashMap<String, Vec<char>> = HashMap::new();
+        // blocks in Python and YAML start after a colon
+        by_filetype.insert("python".to_string(), parse_chars("{ ( [ :"));
+        by_filetype.insert("yaml".to_string(), parse_chars("{ [ :"));
+        for (filetype, list) in &settings.by_filetype {
+            by_filetype.insert(filetype.clone(), parse_chars(list));
+        }
+
+        Self {
+            autoindent: true,
+            smartindent: true,
+            default: parse_chars(settings.default.as_deref().unwrap_or(DEFAULT_INDENT_AFTER)),
+            by_filetype,
+        }
+    }
+
+    // whether new lines start with the indentation of the line above
+    pub fn copies_indent(&self) -> bool {
+        self.autoindent || self.smartindent
+    }
+
+    fn indent_after(&self, filetype: &str) -> &[char] {
+        self.by_filetype.get(filetype).unwrap_or(&self.default)
+    }
+
+    // indentation of a line started below `above`, a new level is `shiftwidth` columns
+    pub fn new_line_indent(&self, filetype: &str, above: &str, tabs: &Tabs) -> String {
+        if !self.copies_indent() {
+            return String::new();
+        }
+        let mut indent = leading(above).to_string();
+
+        let last = above.trim_end().chars().next_back();
+        if self.smartindent && last.is_some_and(|c| self.indent_after(filetype).contains(&c)) {
+            let col = tabs.display_col(&indent, indent.len());
+            indent.push_str(&tabs.fill(col, col + tabs.shiftwidth()));
+        }
+        indent
+    }
+
+    // whether a line split between `left` and `right` splits a bracket pair like `{|}`,
+    // the closing bracket then goes on a line of its own
+    pub fn splits_pair(&self, filetype: &str, left: &str, right: &str) -> bool {
+        if !self.smartindent {
+            return false;
+        }
+        let open = match left.chars().next_back() {
+            Some(c) if self.indent_after(filetype).contains(&c) => c,
+            _ => return false,
+        };
+
+        closing(open).is_some() && right.chars().next() == closing(open)
+    }
+}
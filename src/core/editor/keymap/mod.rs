@@ -0,0 +1,241 @@
+use crossterm::event::{Event, KeyEvent};
+
+use super::keys::{parse_keys, same_key};
+use super::{Editor, Mode};
+
+// keys a mapping may expand to before it is taken for an endless one
+const MAX_EXPANSIONS: usize = 1000;
+// `:normal` commands that may run inside each other, through mappings that use it
+const MAX_NORMAL_DEPTH: usize = 100;
+
+// keys typed in mode `mode` (`n`, `v`, `i` or `c`) that are replaced by other keys, they
+// are not looked up again in the mappings when `noremap` is set
+struct Mapping {
+    mode: char,
+    keys: Vec<KeyEvent>,
+    to: Vec<KeyEvent>,
+    // the keys as they were written, for listing
+    lhs: String,
+    rhs: String,
+    noremap: bool,
+    // mappings from the configuration files are replaced when they are read again
+    from_config: bool,
+}
+
+// what the keys typed so far are in the mappings of a mode
+enum Lookup {
+    // the keys of a mapping, and no longer mapping starts with them
+    Found(usize),
+    // the start of a longer mapping, maybe a mapping of their own too
+    Partial(Option<usize>),
+    None,
+}
+
+pub struct Keymap {
+    maps: Vec<Mapping>,
+    // typed keys that may still become a mapping
+    pending: Vec<KeyEvent>,
+    // how many `:normal` commands run mapped keys right now
+    depth: usize,
+}
+
+// the modes of a `:map` style command and whether it is a `noremap` or an `unmap` one;
+// `map`, `noremap` and `unmap` are for Normal and Visual mode, `n`, `v` (or `x`), `i` and
+// `c` in front of them for one mode
+pub fn map_command(name: &str) -> Option<(&'static str, bool, bool)> {
+    let (modes, base) = match (name.get(..1)?, &name[1..]) {
+        ("n", base) if base != "oremap" => ("n", base),
+        ("v" | "x", base) => ("v", base),
+        ("i", base) => ("i", base),
+        ("c", base) => ("c", base),
+        _ => ("nv", name),
+    };
+    match base {
+        "map" => Some((modes, false, false)),
+        "noremap" => Some((modes, true, false)),
+        "unmap" => Some((modes, false, true)),
+        _ => None,
+    }
+}
+
+// the mode keys are mapped in
+fn map_mode(mode: Mode) -> char {
+    match mode {
+        Mode::Normal => 'n',
+        Mode::Visual => 'v',
+        Mode::Insert | Mode::Replace => 'i',
+        Mode::Command => 'c',
+    }
+}
+
+impl Keymap {
+    pub fn new() -> Self {
+        Self { maps: Vec::new(), pending: Vec::new(), depth: 0 }
+    }
+
+    pub fn is_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    // mappings of the configuration files go before they are read again
+    pub fn clear_config(&mut self) {
+        self.maps.retain(|m| !m.from_config);
+    }
+
+    fn lookup(&self, mode: char, keys: &[KeyEvent]) -> Lookup {
+        let starts_with = |m: &Mapping| m.keys.len() >= keys.len() && m.keys.iter().zip(keys).all(|(a, b)| same_key(a, b));
+        let found = self.maps.iter().position(|m| m.mode == mode && m.keys.len() == keys.len() && starts_with(m));
+        let longer = self.maps.iter().any(|m| m.mode == mode && m.keys.len() > keys.len() && starts_with(m));
+        match (found, longer) {
+            (found, true) => Lookup::Partial(found),
+            (Some(index), false) => Lookup::Found(index),
+            (None, false) => Lookup::None,
+        }
+    }
+}
+
+impl Editor {
+    // `:nmap {lhs} {rhs}`, `:inoremap {lhs} {rhs}`, `:nunmap {lhs}` and `:nmap` to list
+    pub(super) fn ex_map(&mut self, name: &str, args: &str, from_config: bool) -> anyhow::Result<()> {
+        let (modes, noremap, unmap) = match map_command(name) {
+            Some(command) => command,
+            None => anyhow::bail!("E492: Not an editor command: {}", name),
+        };
+        let (lhs, rhs) = match args.split_once(char::is_whitespace) {
+            Some((lhs, rhs)) => (lhs, rhs.trim_start()),
+            None => (args, ""),
+        };
+        let keys = parse_keys(&self.with_leader(lhs));
+
+        if unmap {
+            if lhs.is_empty() {
+                anyhow::bail!("E474: Invalid argument");
+            }
+            let before = self.keymap.maps.len();
+            self.keymap.maps.retain(|m| !(modes.contains(m.mode) && m.keys == keys));
+            if self.keymap.maps.len() == before {
+                anyhow::bail!("E31: No such mapping");
+            }
+            return Ok(());
+        }
+
+        // a configuration file can not list mappings
+        if rhs.is_empty() && from_config {
+            anyhow::bail!("expected `{} {{keys}} {{keys or command}}`", name);
+        }
+        if rhs.is_empty() {
+            let lines: Vec<String> = self
+                .keymap
+                .maps
+                .iter()
+                .filter(|m| modes.contains(m.mode) && m.keys.starts_with(&keys))
+                .map(|m| format!("{}  {:<12} {}{}", m.mode, m.lhs, if m.noremap { "* " } else { "" }, m.rhs))
+                .collect();
+            match lines.len() {
+                0 => self.command_bar.message = Some("No mapping found".to_string()),
+                1 => self.command_bar.message = lines.into_iter().next(),
+                _ => self.command_bar.show_output(lines),
+            }
+            return Ok(());
+        }
+
+        for mode in modes.chars() {
+            self.keymap.maps.retain(|m| !(m.mode == mode && m.keys == keys));
+            self.keymap.maps.push(Mapping {
+                mode,
+                keys: keys.clone(),
+                to: parse_keys(&self.with_leader(rhs)),
+                lhs: lhs.to_string(),
+                rhs: rhs.to_string(),
+                noremap,
+                from_config,
+            });
+        }
+
+        Ok(())
+    }
+
+    // `<leader>` stands for the `leader` option when a mapping is made
+    fn with_leader(&self, keys: &str) -> String {
+        let leader = self.config.options.text("leader");
+        keys.replace("<leader>", leader).replace("<Leader>", leader)
+    }
+
+    // runs a key typed on the terminal through the mappings of the current mode
+    pub(super) fn handle_key(&mut self, key: KeyEvent) -> anyhow::Result<()> {
+        self.keymap.pending.push(key);
+        self.resolve_keys(false)
+    }
+
+    // runs `keys` through the mappings of the modes they get to, like `:normal` does; the
+    // keys typed after the ones that ran it wait meanwhile
+    pub(super) fn feed_mapped_keys(&mut self, keys: &[KeyEvent]) -> anyhow::Result<()> {
+        if self.keymap.depth >= MAX_NORMAL_DEPTH {
+            anyhow::bail!("E192: Recursive use of :normal too deep");
+        }
+        let typed = std::mem::replace(&mut self.keymap.pending, keys.to_vec());
+        self.keymap.depth += 1;
+        let result = self.resolve_keys(true);
+        self.keymap.depth -= 1;
+        self.keymap.pending = typed;
+        result
+    }
+
+    // no key came within `timeoutlen`, the keys typed so far are taken for what they are
+    pub(super) fn keys_timed_out(&mut self) -> anyhow::Result<()> {
+        self.resolve_keys(true)
+    }
+
+    // replaces the pending keys that are mappings and runs the rest; keys that start a
+    // longer mapping wait for the next key unless the time is up
+    fn resolve_keys(&mut self, timed_out: bool) -> anyhow::Result<()> {
+        let mut expansions = 0;
+
+        while !self.keymap.pending.is_empty() && self.running {
+            // keys waited for by a command, like the mark name after `m`, are never mapped
+            let lookup = match self.pending.is_empty() {
+                true => self.keymap.lookup(map_mode(self.mode), &self.keymap.pending),
+                false => Lookup::None,
+            };
+            let found = match lookup {
+                Lookup::Partial(_) if !timed_out => return Ok(()),
+                Lookup::Found(index) | Lookup::Partial(Some(index)) => index,
+                Lookup::Partial(None) | Lookup::None => {
+                    let key = self.keymap.pending.remove(0);
+                    self.process_event(Event::Key(key))?;
+                    continue;
+                }
+            };
+
+            expansions += 1;
+            if expansions > MAX_EXPANSIONS {
+                self.keymap.pending.clear();
+                self.command_bar.message = Some("E223: recursive mapping".to_string());
+                return Ok(());
+            }
+
+            let mapping = &self.keymap.maps[found];
+            let (lhs_len, rhs, noremap) = (mapping.keys.len(), mapping.to.clone(), mapping.noremap);
+            // a mapping that starts with its own keys runs them as they are, like `nmap x xx`
+            let own = rhs.len() >= lhs_len && rhs.iter().zip(&mapping.keys).all(|(a, b)| same_key(a, b));
+            let rest = self.keymap.pending.split_off(lhs_len);
+            self.keymap.pending.clear();
+
+            if noremap {
+                self.feed_keys(&rhs)?;
+                self.keymap.pending = rest;
+            } else {
+                let rhs = match own {
+                    true => {
+                        self.feed_keys(&rhs[..lhs_len])?;
+                        rhs[lhs_len..].to_vec()
+                    }
+                    false => rhs,
+                };
+                self.keymap.pending = rhs.into_iter().chain(rest).collect();
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,105 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+// turns a key string like `A;<Esc>` into key events,
+// names inside `<...>` stand for special keys, `<C-s>`, `<A-j>` and `<S-Tab>` add modifiers
+pub fn parse_keys(keys: &str) -> Vec<KeyEvent> {
+    let mut events = Vec::new();
+    let mut rest = keys;
+
+    while let Some(c) = rest.chars().next() {
+        if c == '<' {
+            if let Some(end) = rest.find('>') {
+                if let Some(key) = key_with_modifiers(&rest[1..end]) {
+                    events.push(key);
+                    rest = &rest[end + 1..];
+                    continue;
+                }
+            }
+        }
+
+        events.push(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+        rest = &rest[c.len_utf8()..];
+    }
+
+    events
+}
+
+// `C-A-x` style names, the modifiers before the last `-`
+fn key_with_modifiers(name: &str) -> Option<KeyEvent> {
+    let mut modifiers = KeyModifiers::NONE;
+    let mut rest = name;
+    while let Some((modifier, key)) = rest.split_once('-').filter(|(_, key)| !key.is_empty()) {
+        modifiers |= match modifier.to_lowercase().as_str() {
+            "c" => KeyModifiers::CONTROL,
+            "a" | "m" => KeyModifiers::ALT,
+            "s" => KeyModifiers::SHIFT,
+            _ => return None,
+        };
+        rest = key;
+    }
+
+    let code = match special_key(rest) {
+        Some(code) => code,
+        None => {
+            let mut chars = rest.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) if modifiers != KeyModifiers::NONE => KeyCode::Char(c),
+                _ => return None,
+            }
+        }
+    };
+
+    let key = match code {
+        // terminals send Shift-Tab as a key of its own
+        KeyCode::Tab if modifiers.contains(KeyModifiers::SHIFT) => {
+            KeyEvent::new(KeyCode::BackTab, modifiers - KeyModifiers::SHIFT)
+        }
+        // and control letters in lower case
+        KeyCode::Char(c) if modifiers.contains(KeyModifiers::CONTROL) => {
+            KeyEvent::new(KeyCode::Char(c.to_ascii_lowercase()), modifiers)
+        }
+        code => KeyEvent::new(code, modifiers),
+    };
+    Some(key)
+}
+
+fn special_key(name: &str) -> Option<KeyCode> {
+    let name = name.to_lowercase();
+    let code = match name.as_str() {
+        "esc" => KeyCode::Esc,
+        "cr" | "enter" | "return" => KeyCode::Enter,
+        "bs" => KeyCode::Backspace,
+        "tab" => KeyCode::Tab,
+        "space" => KeyCode::Char(' '),
+        "lt" => KeyCode::Char('<'),
+        "bar" => KeyCode::Char('|'),
+        "bslash" => KeyCode::Char('\\'),
+        "del" => KeyCode::Delete,
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        _ => match name.strip_prefix('f').and_then(|n| n.parse().ok()) {
+            Some(n @ 1..=12) => KeyCode::F(n),
+            _ => return None,
+        },
+    };
+
+    Some(code)
+}
+
+// whether a typed key is the key of a mapping, terminals add Shift to upper case letters
+pub fn same_key(a: &KeyEvent, b: &KeyEvent) -> bool {
+    let significant = |key: &KeyEvent| {
+        let mut modifiers = key.modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT);
+        if let KeyCode::Char(_) = key.code {
+            modifiers -= KeyModifiers::SHIFT;
+        }
+        (key.code, modifiers)
+    };
+    significant(a) == significant(b)
+}
@@ -0,0 +1,285 @@
+use super::motion::Position;
+use super::tabs::Tabs;
+use super::Editor;
+use crate::core::syntax::Highlight;
+
+// characters `linebreak` may break a line after
+const BREAK_AT: &str = " \t!@*-+;:,./?";
+
+// byte ranges of `text` shown on each screen row, `width` is the room on the first row
+// and `rest_width` the room on the rows after it
+pub fn wrap_line(text: &str, width: usize, rest_width: usize, linebreak: bool, tabs: &Tabs) -> Vec<(usize, usize)> {
+    let mut rows = Vec::new();
+    let mut start = 0;
+    let mut room = width.max(1);
+    // screen column of the line and of the start of the row
+    let mut col = 0;
+    let mut row_col = 0;
+
+    for (i, c) in text.char_indices() {
+        let cells = tabs.char_width(c, col);
+        if i > start && col + cells > row_col + room {
+            let mut end = i;
+            if linebreak {
+                if let Some((j, b)) = text[start..i].char_indices().rfind(|(_, b)| BREAK_AT.contains(*b)) {
+                    end = start + j + b.len_utf8();
+                }
+            }
+            rows.push((start, end));
+            row_col = tabs.display_col(text, end);
+            start = end;
+            room = rest_width.max(1);
+        }
+        col += cells;
+    }
+
+    rows.push((start, text.len()));
+    rows
+}
+
+// one row of the viewport: the line number (on the first row of a line only), the text,
+// the columns of the text that are selected and the highlight of every column
+pub struct ScreenRow {
+    pub number: Option<usize>,
+    pub text: String,
+    pub selected: Option<(usize, usize)>,
+    pub highlights: Vec<Highlight>,
+}
+
+// maps buffer lines to screen rows: without `wrap` every line takes one row,
+// with it long lines continue on the rows below behind the `showbreak` prefix
+impl Editor {
+    fn showbreak_width(&self) -> usize {
+        self.showbreak.chars().count().min(self.text_width().saturating_sub(1))
+    }
+
+    // byte ranges of buffer line `line` shown on each of its screen rows
+    pub(super) fn line_rows(&self, line: usize) -> Vec<(usize, usize)> {
+        let text = match self.buffer.lines.get(line) {
+            Some(text) => text,
+            None => return vec![(0, 0)],
+        };
+        if !self.wrap {
+            return vec![(0, text.len())];
+        }
+
+        let width = self.text_width();
+        wrap_line(text, width, width - self.showbreak_width(), self.linebreak, &self.tabs)
+    }
+
+    // screen rows buffer line `line` takes, lines past the end of the file take one
+    pub(super) fn line_height(&self, line: usize) -> usize {
+        if self.wrap {
+            self.line_rows(line).len()
+        } else {
+            1
+        }
+    }
+
+    // last buffer line that fits on screen entirely when the window starts at `top`,
+    // rows past the end of the file count as lines too
+    pub(super) fn last_visible_line(&self, top: usize) -> usize {
+        let height = self.viewport_height();
+        let mut rows = 0;
+        let mut line = top;
+        loop {
+            rows += self.line_height(line);
+            if rows > height {
+                return line.saturating_sub(1).max(top);
+            }
+            if rows == height {
+                return line;
+            }
+            line += 1;
+        }
+    }
+
+    // screen row of the cursor inside its line and the column inside that row
+    fn cursor_row(&self) -> (usize, usize) {
+        let line = self.current_line();
+        let text = &self.buffer.lines[line];
+        let x = (self.cursor_x as usize).min(text.len());
+        let rows = self.line_rows(line);
+
+        let row = rows
+            .iter()
+            .position(|(start, end)| x >= *start && x < *end)
+            .unwrap_or(rows.len() - 1);
+        let start = rows[row].0;
+
+        (row, self.tabs.display_col(text, x) - self.tabs.display_col(text, start))
+    }
+
+    // terminal position of the cursor inside the viewport
+    pub(super) fn cursor_screen_pos(&self) -> (u16, u16) {
+        let top = self.viewport_top as usize;
+        let (row, col) = self.cursor_row();
+        let x = if !self.wrap {
+            self.cursor_col() - self.viewport_left as usize
+        } else if row > 0 {
+            col + self.showbreak_width()
+        } else {
+            col
+        };
+
+        let above: usize = (top..self.current_line()).map(|line| self.line_height(line)).sum();
+        let y = self.viewport_row() + (above + row).min(self.viewport_height() - 1);
+        let x = self.gutter_width() + x.min(self.text_width() - 1);
+
+        (x as u16, y as u16)
+    }
+
+    // what every row of the viewport shows
+    pub(super) fn screen_rows(&self) -> Vec<ScreenRow> {
+        let height = self.viewport_height();
+        let width = self.text_width();
+        let mut screen = Vec::with_capacity(height);
+
+        let mut line = self.viewport_top as usize;
+        while screen.len() < height {
+            let text = match self.buffer.lines.get(line) {
+                Some(text) => text,
+                None => {
+                    screen.push(ScreenRow { number: None, text: " ".repeat(width), selected: None, highlights: Vec::new() });
+                    continue;
+                }
+            };
+
+            if !self.wrap {
+                let left = self.viewport_left as usize;
+                let selected = self.selected_cols(line, 0, text.len()).and_then(|(from, to)| {
+                    let (from, to) = (from.max(left) - left, to.saturating_sub(left).min(width));
+                    (from < to).then_some((from, to))
+                });
+                let highlights = self.cell_highlights(line, 0, text.len()).into_iter().skip(left).take(width).collect();
+                screen.push(ScreenRow { number: Some(line + 1), text: self.visible_text(text), selected, highlights });
+            } else {
+                for (row, (start, end)) in self.line_rows(line).into_iter().enumerate() {
+                    if screen.len() == height {
+                        break;
+                    }
+                    let prefix = if row > 0 { self.showbreak_width() } else { 0 };
+                    let mut shown: String = self.showbreak.chars().take(prefix).collect();
+                    shown.push_str(&self.tabs.expand(text, start, end));
+                    let shown: String = shown.chars().chain(std::iter::repeat(' ')).take(width).collect();
+
+                    let selected = self
+                        .selected_cols(line, start, end)
+                        .map(|(from, to)| (from + prefix, (to + prefix).min(width)));
+                    let mut highlights = vec![Highlight::Normal; prefix];
+                    highlights.extend(self.cell_highlights(line, start, end));
+                    screen.push(ScreenRow { number: (row == 0).then_some(line + 1), text: shown, selected, highlights });
+                }
+            }
+            line += 1;
+        }
+
+        screen
+    }
+
+    // highlight of every screen column the part `start..end` of line `line` takes
+    fn cell_highlights(&self, line: usize, start: usize, end: usize) -> Vec<Highlight> {
+        let text = &self.buffer.lines[line];
+        let bytes = self.syntax.line_highlights(line, text.len());
+        let mut col = self.tabs.display_col(text, start);
+        let mut cells = Vec::new();
+        for (i, c) in text[start..end].char_indices() {
+            let width = self.tabs.char_width(c, col);
+            cells.extend(std::iter::repeat_n(bytes[start + i], width));
+            col += width;
+        }
+        cells
+    }
+
+    // selected columns of the part `start..end` of line `line`, counted from `start`;
+    // a selected line break shows as one extra column
+    fn selected_cols(&self, line: usize, start: usize, end: usize) -> Option<(usize, usize)> {
+        let (from, to) = self.selection()?;
+        if line < from.line || line > to.line || (line == to.line && to.col == 0 && line > from.line) {
+            return None;
+        }
+
+        let text = &self.buffer.lines[line];
+        let first = if line == from.line { from.col } else { 0 };
+        let (last, line_break) = if line == to.line { (to.col, false) } else { (text.len(), true) };
+
+        let first = first.max(start);
+        let last = last.min(end);
+        if first > last || (first == last && !(line_break && last == text.len())) {
+            return None;
+        }
+
+        let row_col = self.tabs.display_col(text, start);
+        let from_col = self.tabs.display_col(text, first) - row_col;
+        let mut to_col = self.tabs.display_col(text, last) - row_col;
+        if line_break && last == text.len() {
+            to_col += 1;
+        }
+
+        Some((from_col, to_col))
+    }
+
+    // buffer position shown at terminal cell (`x`, `y`), clicks past the end of a row land
+    // on its last character and clicks below the text on the last line
+    pub(super) fn position_at(&self, x: u16, y: u16) -> Position {
+        let col = (x as usize).saturating_sub(self.gutter_width());
+        let mut y = (y as usize).saturating_sub(self.viewport_row()).min(self.viewport_height() - 1);
+        let last_line = self.buffer.get_file_lenght() - 1;
+
+        let mut line = self.viewport_top as usize;
+        loop {
+            let rows = self.line_rows(line);
+            if y < rows.len() || line == last_line {
+                let row = y.min(rows.len() - 1);
+                let (start, end) = rows[row];
+                let text = &self.buffer.lines[line];
+
+                let byte = if !self.wrap {
+                    self.tabs.byte_at_col(text, col + self.viewport_left as usize)
+                } else {
+                    let prefix = if row > 0 { self.showbreak_width() } else { 0 };
+                    let col = self.tabs.display_col(text, start) + col.saturating_sub(prefix);
+                    self.row_byte_at_col(line, start, end, col)
+                };
+                return Position::new(line, byte);
+            }
+            y -= rows.len();
+            line += 1;
+        }
+    }
+
+    // `gj` / `gk`: moves the cursor one screen row, which is one line without `wrap`
+    pub(super) fn move_screen_row(&mut self, down: bool) {
+        let line = self.current_line();
+        let (row, col) = self.cursor_row();
+        let rows = self.line_rows(line);
+
+        let (line, row) = if down && row + 1 < rows.len() {
+            (line, row + 1)
+        } else if down && line < self.buffer.get_file_lenght() - 1 {
+            (line + 1, 0)
+        } else if !down && row > 0 {
+            (line, row - 1)
+        } else if !down && line > 0 {
+            (line - 1, self.line_rows(line - 1).len() - 1)
+        } else {
+            return;
+        };
+
+        self.scroll_to_line(line);
+        let (start, end) = self.line_rows(line)[row];
+        let col = self.tabs.display_col(&self.buffer.lines[line], start) + col;
+        self.cursor_x = self.row_byte_at_col(line, start, end, col) as u16;
+    }
+
+    // byte offset of the character at screen column `col` of line `line` (counted from the
+    // start of the line) inside its row `start..end`, past the end of the row the last one
+    fn row_byte_at_col(&self, line: usize, start: usize, end: usize, col: usize) -> usize {
+        let text = &self.buffer.lines[line];
+        let byte = self.tabs.byte_at_col(text, col).max(start);
+        if byte < end {
+            return byte;
+        }
+        text[start..end].char_indices().next_back().map(|(i, _)| start + i).unwrap_or(start)
+    }
+}
@@ -1,11 +1,12 @@
+use std::collections::HashMap;
 use std::io::{stdout, Stdout};
-use crossterm::style;
+use std::path::Path;
+use std::time::Instant;
 
-use anyhow::Ok;
 use crossterm::{
     cursor::{self, MoveTo},
-    event::{self, read},
-    style::{Color, Print, PrintStyledContent, Stylize},
+    event::{self, KeyEvent},
+    style::{Color, PrintStyledContent, StyledContent, Stylize},
     terminal, ExecutableCommand, QueueableCommand,
 };
 use std::io::Write;
@@ -13,22 +14,57 @@ use std::io::Write;
 use command_bar::CommandBar;
 use status_bar::StatusBar;
 
-use super::{buffer::Buffer, timer::Timer};
-
-use config::EditorSettings;
-use config::CommandsBindings;
+use super::{buffer::Buffer, syntax::{Highlight, Syntax}, timer::Timer};
+
+use autocmd::Autocmds;
+use config::Config;
+use ex::commands::UserCommands;
+pub use config::ConfigOptions;
+use indent::Indent;
+use keymap::Keymap;
+use serde_json::json;
+use plugins::host::PluginHost;
+use pairs::{AutoPairs, Typed};
+use reload::ConfigWatch;
+use motion::{FindKind, Motion, Position, Screen};
+use scroll::Align;
+use status_bar::Side;
+use tabs::Tabs;
+use theme::palette::{self, ColorDepth};
+use theme::{Rgb, Theme};
 // mods
+mod autocmd;
 mod command_bar;
 mod modules;
 mod status_bar;
 mod config;
-
+mod ex;
+mod keys;
+mod keymap;
+mod motion;
+mod scroll;
+mod layout;
+mod options;
+mod pairs;
+pub mod plugins;
+mod reload;
+mod indent;
+mod tabs;
+mod theme;
+mod visual;
+mod mouse;
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Clone, Copy)]
 enum Action {
     // Possible movement actions
     MoveUp,
     MoveDown,
     MoveLeft,
     MoveRight,
+    MoveScreenRow(bool), // `gj` / `gk`, true moves down
 
     Typing(char),
 
@@ -37,6 +73,149 @@ enum Action {
     Backspace,
 
     SetMode(Mode),
+
+    SetMark(char),
+    JumpToMark(char),
+    Put(bool), // true puts below the current line
+
+    Append,
+    AppendToEnd,
+    InsertAtStart,
+    OpenLine(bool), // true opens below the current line
+    DeleteChar,
+
+    Undo,
+    Redo,
+
+    ReplaceChar(char),
+    RepeatChange,
+
+    Motion(Motion),
+    RepeatFind(bool), // true for `,`, which searches the other way
+
+    // the bool is true when scrolling towards the end of the file
+    ScrollLines(bool),
+    ScrollHalfPage(bool),
+    ScrollPage(bool),
+    AlignCursor(Align, bool), // true also moves to the first non-blank character
+
+    DeleteSelection,
+    YankSelection,
+}
+
+impl Action {
+    // actions that `3x` style counts simply run several times
+    fn repeats_with_count(&self) -> bool {
+        matches!(
+            self,
+            Action::MoveUp
+                | Action::MoveDown
+                | Action::MoveLeft
+                | Action::MoveRight
+                | Action::MoveScreenRow(_)
+                | Action::DeleteChar
+                | Action::Put(_)
+                | Action::Undo
+                | Action::Redo
+        )
+    }
+
+    // actions that only move the cursor or the window, the ones Visual mode takes over
+    fn moves_cursor(&self) -> bool {
+        matches!(
+            self,
+            Action::MoveUp
+                | Action::MoveDown
+                | Action::MoveLeft
+                | Action::MoveRight
+                | Action::MoveScreenRow(_)
+                | Action::JumpToMark(_)
+                | Action::Motion(_)
+                | Action::RepeatFind(_)
+                | Action::ScrollLines(_)
+                | Action::ScrollHalfPage(_)
+                | Action::ScrollPage(_)
+                | Action::AlignCursor(..)
+        )
+    }
+}
+
+// single key normal mode motions
+fn normal_motion(c: char) -> Option<Motion> {
+    let motion = match c {
+        'w' => Motion::WordForward { big: false },
+        'W' => Motion::WordForward { big: true },
+        'b' => Motion::WordBackward { big: false },
+        'B' => Motion::WordBackward { big: true },
+        'e' => Motion::WordEnd { big: false },
+        'E' => Motion::WordEnd { big: true },
+        '0' => Motion::LineStart,
+        '^' => Motion::FirstNonBlank,
+        '$' => Motion::LineEnd,
+        '+' => Motion::NextLineStart,
+        '-' => Motion::PreviousLineStart,
+        'G' => Motion::LastLine,
+        '}' => Motion::ParagraphForward,
+        '{' => Motion::ParagraphBackward,
+        ')' => Motion::SentenceForward,
+        '(' => Motion::SentenceBackward,
+        'H' => Motion::ScreenTop,
+        'M' => Motion::ScreenMiddle,
+        'L' => Motion::ScreenBottom,
+        '%' => Motion::MatchPair,
+        _ => return None,
+    };
+
+    Some(motion)
+}
+
+// normal mode `Ctrl-{c}` commands
+fn control_action(c: char) -> Option<Action> {
+    match c {
+        'r' => Some(Action::Redo),
+        'e' => Some(Action::ScrollLines(true)),
+        'y' => Some(Action::ScrollLines(false)),
+        'd' => Some(Action::ScrollHalfPage(true)),
+        'u' => Some(Action::ScrollHalfPage(false)),
+        'f' => Some(Action::ScrollPage(true)),
+        'b' => Some(Action::ScrollPage(false)),
+        _ => None,
+    }
+}
+
+// digits typed in front of a command, `0` only continues a count that already started
+fn is_count_key(key: &KeyEvent, started: bool) -> bool {
+    match key.code {
+        event::KeyCode::Char('0') => started,
+        event::KeyCode::Char(c) => c.is_ascii_digit(),
+        _ => false,
+    }
+}
+
+// number of keys the count in front of a command takes
+fn count_prefix_len(keys: &[KeyEvent]) -> usize {
+    let mut len = 0;
+    while len < keys.len() && is_count_key(&keys[len], len > 0) {
+        len += 1;
+    }
+    len
+}
+
+// text of a register, `linewise` text is put on lines of its own
+#[derive(Clone)]
+struct Register {
+    lines: Vec<String>,
+    linewise: bool,
+}
+
+impl Register {
+    fn linewise(lines: Vec<String>) -> Self {
+        Self { lines, linewise: true }
+    }
+
+    fn charwise(lines: Vec<String>) -> Self {
+        Self { lines, linewise: false }
+    }
 }
 
 #[derive(Clone, Copy, PartialEq)]
@@ -45,10 +224,15 @@ enum Mode {
     Normal,
     Insert,
     Command,
+    Replace,
+    Visual,
 }
     
 pub struct Editor {
-    font_color: (u8,u8,u8),
+    // colours of everything drawn, `color_overrides` are the colours set in the config
+    // file, they stay when the theme changes
+    theme: Theme,
+    color_overrides: Vec<(&'static str, Rgb)>,
 
     buffer: Buffer,
     viewport_top: u16,
@@ -58,6 +242,8 @@ pub struct Editor {
     pub cursor_y: u16,
 
     stdout: Stdout,
+    // whether `new` took over the terminal, which is given back when the editor goes away
+    terminal: bool,
     timer: Timer,
     running: bool,
 
@@ -65,66 +251,224 @@ pub struct Editor {
 
     mode: Mode,
 
+    // the values of the options are copied into the fields below whenever they change
     enable_status_bar: bool,
     status_bar: StatusBar,
 
     command_bar: CommandBar,
-    scrolling_padding: u16,
+    // lines kept visible above and below the cursor
+    scrolloff: usize,
+    // lines scrolled by `Ctrl-D` and `Ctrl-U`, 0 means half the window
+    scroll: usize,
+    // columns kept visible left and right of the cursor, and the least number of
+    // columns to scroll sideways (0 puts the cursor in the middle)
+    sidescrolloff: usize,
+    sidescroll: usize,
+    // screen column `j` and `k` try to keep
+    preferred_col: Option<usize>,
+    // the end of the Visual selection that stays put while the cursor moves
+    visual_anchor: Position,
+    // mouse events are captured when `mouse` is on, `mouse_down` is where the left
+    // button was pressed, a drag from there selects text
+    mouse: bool,
+    mouse_captured: bool,
+    mouse_down: Option<Position>,
+    // long lines continue on the next screen rows, broken at word boundaries with
+    // `linebreak`, with `showbreak` in front of the continuation rows
+    wrap: bool,
+    linebreak: bool,
+    showbreak: String,
+    // line numbers in front of the text
+    number: bool,
+    // characters typed in Insert mode together with their closing character
+    auto_pairs: AutoPairs,
+    // indentation of new lines
+    indent: Indent,
+    // tab width and what Tab types
+    tabs: Tabs,
+    syntax: Syntax,
+
+    // yanked and deleted lines, `"` is the unnamed register
+    registers: HashMap<char, Register>,
+    last_pattern: Option<String>,
+    // keys of an unfinished normal mode command, e.g. `m` waiting for a mark name
+    pending: String,
+    // keys replaced by other keys, from `:map` and the configuration
+    keymap: Keymap,
+    // commands defined with `:command` and `:alias`
+    user_commands: UserCommands,
+    // plugin processes, and the changes of the buffer they were told about
+    plugins: PluginHost,
+    seen_changes: usize,
+    // commands run on events, from `:autocmd`; when the last key came and whether
+    // `CursorHold` fired since
+    autocmds: Autocmds,
+    idle_since: Instant,
+    held: bool,
+    in_global: bool,
+
+    // count typed in front of a normal mode command
+    count: usize,
+    // count of the action being applied, `typed_count` is 0 when none was typed
+    action_count: usize,
+    typed_count: usize,
+    // last `f`, `t`, `F` or `T`, repeated by `;` and `,`
+    last_find: Option<Motion>,
+    // keys of the command being typed and of the last one that changed the buffer, for `.`
+    recording: Vec<KeyEvent>,
+    last_change: Vec<KeyEvent>,
+    history_version: usize,
+    replaying: bool,
+    // keys typed in the current Insert/Replace session, repeated on Esc when a count was given
+    insert_keys: Vec<KeyEvent>,
+    insert_count: usize,
+    insert_opened_line: bool,
+    // original characters overwritten in the current Replace session, restored by Backspace
+    replaced: Vec<Option<char>>,
 
     qiut: String,
     save: String,
-    edit: String
+    edit: String,
+
+    // the settings the editor started with, listed by `:config`, and the options `:set` changes;
+    // the command line options to read them again with and the files watched for changes
+    config: Config,
+    config_options: ConfigOptions,
+    config_watch: ConfigWatch,
 }
 
 impl Editor {
-    pub fn new(buf: Buffer) -> anyhow::Result<Self> {
+    pub fn new(buf: Buffer, options: &ConfigOptions) -> anyhow::Result<Self> {
         let mut _stdout = stdout();
 
-        let mut timer = Timer::new();
-        timer.start();
-
         terminal::enable_raw_mode()?;
         _stdout
             .execute(terminal::EnterAlternateScreen)? // Enter to the upper terminal layer
             .execute(terminal::Clear(terminal::ClearType::All))? // Clear new terminal layer
             .execute(cursor::SetCursorStyle::BlinkingBar)?
-            .execute(cursor::DisableBlinking)?;
+            .execute(cursor::DisableBlinking)?
+            .execute(event::EnableBracketedPaste)?;
 
         let _size = terminal::size().expect("Could not get size of terminal");
 
-        let settings = EditorSettings::init();
-        let settings_bind = CommandsBindings::init();
+        // what comes from the configuration is set up by `apply_config` below
+        let (config, config_errors) = Config::load(buf.file.as_deref(), options);
+
+        let mut editor = Editor::with_config(buf, config, options, _size)?;
+        editor.terminal = true;
+        editor.timer.start();
+
+        // problems with the configuration are shown until the first command
+        let errors: Vec<String> = config_errors.into_iter().chain(editor.apply_config()?).collect();
+        editor.command_bar.message = error_message(errors);
+
+        Ok(editor)
+    }
+
+    // an editor on `buf` of `size` columns and rows that has not touched the terminal,
+    // the values of `config` take effect with `apply_config`
+    fn with_config(buf: Buffer, config: Config, options: &ConfigOptions, size: (u16, u16)) -> anyhow::Result<Self> {
         Ok(Editor {
-            font_color: settings.get_info_color().unwrap(),
+            theme: Theme::load("default", None)?,
+            color_overrides: Vec::new(),
 
             buffer: buf,
             viewport_left: 0,
             viewport_top: 0,
-            scrolling_padding: 1,
+            scrolloff: 0,
+            scroll: 0,
+            sidescrolloff: 0,
+            sidescroll: 0,
+            preferred_col: None,
+            visual_anchor: Position::new(0, 0),
+            mouse: false,
+            mouse_captured: false,
+            mouse_down: None,
+            wrap: false,
+            linebreak: false,
+            showbreak: String::new(),
+            number: true,
+            auto_pairs: AutoPairs::new(&config.auto_pairs),
+            indent: Indent::new(&config.indent),
+            tabs: Tabs::new(&config.options),
+            syntax: Syntax::load(false, None).0,
 
             cursor_x: 0,
             cursor_y: 0,
 
             running: true,
 
-            size: _size,
+            size,
 
             mode: Mode::Normal,
 
             enable_status_bar: true,
-            status_bar: StatusBar::new(),
+            status_bar: StatusBar::new(&config.status_bar),
             command_bar: CommandBar::new(),
 
-            timer,
-
-            stdout: _stdout,
-
-            qiut: settings_bind.get_info_quit().unwrap(),
-            save: settings_bind.get_info_save().unwrap(),
-            edit: settings_bind.get_info_edit().unwrap(),
+            timer: Timer::new(),
+
+            stdout: stdout(),
+            terminal: false,
+
+            registers: HashMap::new(),
+            last_pattern: None,
+            pending: String::new(),
+            keymap: Keymap::new(),
+            user_commands: UserCommands::new(),
+            plugins: PluginHost::new(),
+            seen_changes: 0,
+            autocmds: Autocmds::new(),
+            idle_since: Instant::now(),
+            held: false,
+            in_global: false,
+
+            count: 0,
+            action_count: 1,
+            typed_count: 0,
+            last_find: None,
+            recording: Vec::new(),
+            last_change: Vec::new(),
+            history_version: 0,
+            replaying: false,
+            insert_keys: Vec::new(),
+            insert_count: 1,
+            insert_opened_line: false,
+            replaced: Vec::new(),
+
+            qiut: String::new(),
+            save: String::new(),
+            edit: String::new(),
+
+            config,
+            config_options: options.clone(),
+            config_watch: ConfigWatch::new(Vec::new()),
         })
     }
 
+    // makes the current values of the options take effect
+    fn apply_options(&mut self) {
+        let options = &self.config.options;
+
+        self.scrolloff = options.number("scrolloff");
+        self.sidescroll = options.number("sidescroll");
+        self.sidescrolloff = options.number("sidescrolloff");
+        self.wrap = options.flag("wrap");
+        self.linebreak = options.flag("linebreak");
+        self.showbreak = options.text("showbreak").to_string();
+        self.number = options.flag("number");
+        self.mouse = options.flag("mouse");
+        self.syntax.enabled = options.flag("syntax");
+        self.enable_status_bar = options.flag("statusbar");
+        self.status_bar.set_side(if options.text("statusbarside") == "top" { Side::Top } else { Side::Bottom });
+        self.tabs = Tabs::new(options);
+        self.indent.autoindent = options.flag("autoindent");
+        self.indent.smartindent = options.flag("smartindent");
+        self.auto_pairs.enabled = options.flag("autopairs");
+        // `auto` looks at the terminal again
+        self.theme.depth = ColorDepth::parse(options.text("colors")).unwrap_or_else(palette::detect);
+    }
+
     pub fn start(&mut self) -> anyhow::Result<()> {
         self.mainloop()?;
 
@@ -133,90 +477,366 @@ impl Editor {
 
     // main loop of logic
     fn mainloop(&mut self) -> anyhow::Result<()> {
+        // the file the editor started with
+        if self.buffer.file.is_some() {
+            self.emit_file_read();
+        }
+
         while self.running {
 
 
             self.status_bar.get_editor_info((
-                self.mode.clone(),
+                self.mode,
                 Box::new(self.buffer.file.clone().unwrap_or("[No Name]".to_string())),
             ));
+            self.sync_mouse_capture()?;
             // drawings
+            self.fit_cursor_in_view();
             self.draw(self.size)?;
             self.stdout.flush()?;
             if self.mode != Mode::Command {
+                let (x, y) = self.cursor_screen_pos();
                 self.stdout
-                    .queue(MoveTo(self.cursor_x as u16 + self.buffer.get_file_lenght().to_string().len() as u16+1, self.cursor_y.into()))?; // start cursor
+                    .queue(MoveTo(x, y))?; // start cursor
             } else {
                 self.stdout
-                    .queue(MoveTo(self.command_bar.command.len() as u16 , self.size.1-1))?; 
+                    .queue(MoveTo(self.command_bar.command.len() as u16 , self.size.1.saturating_sub(1)))?; 
             }
             self.stdout.flush()?; // output sync with Stdout
 
-            if let Some(action) = self.handel_event(read()?)? {
+            let ev = match self.next_event()? {
+                Some(ev) => ev,
+                None => continue,
+            };
+            // while a command's output is shown keys only page through it
+            if self.command_bar.has_output() && matches!(ev, event::Event::Key(_)) {
+                self.command_bar.page_output(self.size);
+                continue;
+            }
+            match ev {
+                event::Event::Key(key) => self.handle_key(key)?,
+                ev => {
+                    // keys waiting for the rest of a mapping go first
+                    self.keys_timed_out()?;
+                    self.process_event(ev)?;
+                }
+            }
+
+            if self.buffer.history.changes() != self.seen_changes {
+                self.seen_changes = self.buffer.history.changes();
+                self.emit_event("TextChanged", json!({}));
+            }
+        }
+
+        self.emit_event("VimLeave", json!({}));
+        Ok(())
+    }
+
+    // runs one terminal event through the current mode, applying counts and
+    // recording the keys of commands that change the buffer for `.`
+    fn process_event(&mut self, ev: event::Event) -> anyhow::Result<()> {
+        if let event::Event::Resize(width, height) = ev {
+            return self.resize(width, height);
+        }
+        if let event::Event::Mouse(mouse) = ev {
+            return self.handle_mouse(mouse);
+        }
+        if let event::Event::Paste(text) = ev {
+            self.handle_paste(&text);
+            return Ok(());
+        }
+
+        let key = match ev {
+            event::Event::Key(key) => Some(key),
+            _ => None,
+        };
+
+        if let Some(key) = key {
+            if !self.replaying {
+                if self.at_command_start() {
+                    self.recording.clear();
+                    self.history_version = self.buffer.history.version();
+                }
+                self.recording.push(key);
+            }
+            if self.is_editing() && key.code != event::KeyCode::Esc {
+                self.insert_keys.push(key);
+            }
+        }
+
+        let from_normal = matches!(self.mode, Mode::Normal | Mode::Visual);
+        if let Some(action) = self.handel_event(ev)? {
+            if !matches!(action, Action::MoveUp | Action::MoveDown) {
+                self.preferred_col = None;
+            }
+            let typed = if from_normal { std::mem::take(&mut self.count) } else { 0 };
+            let count = typed.max(1);
+            self.typed_count = typed;
+            self.action_count = count;
+
+            if action.repeats_with_count() {
+                // `3x` is one undo step, undo itself must stay outside of any step
+                let grouped = !matches!(action, Action::Undo | Action::Redo);
+                if grouped {
+                    self.begin_change();
+                }
+                for _ in 0..count {
+                    self.apply_action(action)?;
+                }
+                if grouped {
+                    self.buffer.end_change();
+                }
+            } else {
+                self.apply_action(action)?;
+            }
+        }
+
+        if key.is_some() && !self.replaying && self.at_command_start() {
+            let changed = self.buffer.history.version() != self.history_version;
+            let command = self.recording.get(count_prefix_len(&self.recording));
+            let repeatable = !matches!(
+                command.map(|k| k.code),
+                Some(event::KeyCode::Char(':')) | Some(event::KeyCode::Char('.'))
+            );
+            if changed && repeatable {
+                self.last_change = std::mem::take(&mut self.recording);
+            }
+        }
+
+        Ok(())
+    }
+
+    // true when no command is half typed in normal mode
+    fn at_command_start(&self) -> bool {
+        self.mode == Mode::Normal && self.pending.is_empty() && self.count == 0
+    }
+
+    fn is_editing(&self) -> bool {
+        matches!(self.mode, Mode::Insert | Mode::Replace)
+    }
+
+    // `.` repeats the last change, a new count replaces the one it was typed with
+    fn repeat_last_change(&mut self) -> anyhow::Result<()> {
+        let mut keys = self.last_change.clone();
+        if self.typed_count > 0 {
+            keys.drain(..count_prefix_len(&keys));
+            let count: Vec<KeyEvent> = self.action_count.to_string().chars()
+                .map(|c| KeyEvent::new(event::KeyCode::Char(c), event::KeyModifiers::NONE))
+                .collect();
+            keys.splice(0..0, count);
+        }
+
+        self.replaying = true;
+        let result = self.feed_keys(&keys);
+        self.replaying = false;
+
+        result
+    }
+
+
+    fn apply_action(&mut self, action: Action) -> anyhow::Result<()> {
+        match action {
+            Action::SetMode(new_mode) => {
+                if self.is_editing() && self.insert_count > 1 {
+                    self.repeat_insert()?;
+                }
+                self.set_mode(new_mode);
+            }
+
+            Action::MoveUp | Action::MoveDown => {
+                // the column is remembered while moving through shorter lines
+                let col = match self.preferred_col {
+                    Some(col) => col,
+                    None => self.cursor_col(),
+                };
+                let line = self.current_line();
                 match action {
-                    Action::SetMode(new_mode) => {
-                        if new_mode == Mode::Command {
-                            self.cursor_x = 0;
-                            self.cursor_y = self.size.1;
-                        }
-                        self.mode = new_mode;
+                    Action::MoveUp if line > 0 => self.scroll_to_line(line - 1),
+                    Action::MoveDown => self.scroll_to_line(line + 1),
+                    _ => {}
+                }
+                let line = self.current_line();
+                let x = self.tabs.byte_at_col(&self.buffer.lines[line], col);
+                // only Insert and Replace can put the cursor after the last character
+                self.cursor_x = match self.is_editing() {
+                    true => x,
+                    false => x.min(motion::last_char(&self.buffer, line)),
+                } as u16;
+                self.preferred_col = Some(col);
+            }
+            Action::MoveRight => {
+                let pos = self.cursor_pos();
+                let text = &self.buffer.lines[pos.line];
+                if let Some(c) = text[pos.col..].chars().next() {
+                    let next = pos.col + c.len_utf8();
+                    // only Insert and Replace can put the cursor after the last character
+                    if next < text.len() || self.is_editing() {
+                        self.cursor_x = next as u16;
                     }
+                }
+            }
+            Action::MoveScreenRow(down) => {
+                self.move_screen_row(down);
+            }
+            Action::MoveLeft => {
+                let pos = self.cursor_pos();
+                let text = &self.buffer.lines[pos.line];
+                if let Some(c) = text[..pos.col].chars().next_back() {
+                    self.cursor_x = (pos.col - c.len_utf8()) as u16;
+                }
+            }
 
-                    Action::MoveUp => {
-                        if self.cursor_y < self.scrolling_padding {
-                            self.viewport_top = self.viewport_top.saturating_sub(1);
-                        } else {
-                            self.cursor_y = self.cursor_y.saturating_sub(1);
-                        }
+            Action::Typing(v) => {
+                self.handle_changing(v)?;
+            }
+            Action::EnterKey => {
+                self.handle_enter()?;
+            }
+            Action::TabKey => {
+                self.handle_tab()?;
+            }
+            Action::Backspace => {
+                self.handle_backspace()?;
+            }
 
-                        if self.cursor_y < self.scrolling_padding && self.viewport_top == 0 {
-                            self.cursor_y = self.cursor_y.saturating_sub(1);
-                        }
-                    }
-                    Action::MoveDown => {
-                        let cannot_move_down = self.viewport_height()
-                            >= (self.buffer.get_file_lenght() - self.viewport_top as usize);
-
-                        if self.cursor_y + self.viewport_top
-                            < self.buffer.get_file_lenght() as u16 - 1
-                        {
-                            if self.cursor_y
-                                < self.viewport_height() as u16 - self.scrolling_padding
-                                || cannot_move_down
-                            {
-                                self.cursor_y = self.cursor_y.saturating_add(1);
-                            }
-                            if self.cursor_y
-                                == self.viewport_height() as u16 - self.scrolling_padding
-                            {
-                                if !cannot_move_down {
-                                    self.viewport_top = self.viewport_top.saturating_add(1);
-                                }
-                            }
-                        }
-                    }
-                    Action::MoveRight => {
-                        self.cursor_x = self.cursor_x.saturating_add(1);
-                    }
-                    Action::MoveLeft => {
-                        self.cursor_x = self.cursor_x.saturating_sub(1);
-                        if self.cursor_x < self.viewport_left {
-                            self.cursor_x = self.viewport_left;
-                        }
+            Action::SetMark(mark) => {
+                self.buffer.set_mark(mark, self.current_line());
+            }
+            Action::JumpToMark(mark) => {
+                match self.buffer.get_mark(mark) {
+                    Some(line) => {
+                        self.goto_line(line);
+                        self.cursor_x = 0;
                     }
+                    None => self.command_bar.message = Some("E20: Mark not set".to_string()),
+                }
+            }
+            Action::Put(below) => {
+                let line = self.current_line();
+                self.begin_change();
+                let linewise = self.registers.get(&'"').is_none_or(|r| r.linewise);
+                let result = if linewise {
+                    self.put_lines('"', if below { line + 1 } else { line })
+                } else {
+                    self.put_text('"', below)
+                };
+                if let Err(e) = result {
+                    self.command_bar.message = Some(e.to_string());
+                }
+                self.buffer.end_change();
+            }
 
-                    Action::Typing(v) => {
-                        self.handle_changing(v)?;
-                    }
-                    Action::EnterKey => {
-                        self.handle_enter()?;
-                    }
-                    Action::TabKey => {
-                        self.handle_tab()?;
-                    }
-                    Action::Backspace => {
-                        self.handle_backspace()?;
-                    }
+            Action::Append => {
+                // after the character under the cursor, which may take several bytes
+                let pos = self.cursor_pos();
+                let next = self.buffer.lines[pos.line][pos.col..].chars().next();
+                self.cursor_x = (pos.col + next.map_or(0, char::len_utf8)) as u16;
+                self.set_mode(Mode::Insert);
+            }
+            Action::AppendToEnd => {
+                self.cursor_x = self.buffer.lines[self.current_line()].len() as u16;
+                self.set_mode(Mode::Insert);
+            }
+            Action::InsertAtStart => {
+                let line = &self.buffer.lines[self.current_line()];
+                self.cursor_x = (line.len() - line.trim_start().len()) as u16;
+                self.set_mode(Mode::Insert);
+            }
+            Action::OpenLine(below) => {
+                let line = self.current_line();
+                let at = if below { line + 1 } else { line };
+                // the new line belongs to the same undo step as the text typed into it
+                self.set_mode(Mode::Insert);
+                self.insert_opened_line = true;
+                // `o` indents like Enter at the end of the line, `O` copies the line's indentation
+                let text = &self.buffer.lines[line];
+                let indent = if below {
+                    self.indent.new_line_indent(&self.buffer.filetype, text, &self.tabs)
+                } else if self.indent.copies_indent() {
+                    indent::leading(text).to_string()
+                } else {
+                    String::new()
+                };
+                self.cursor_x = indent.len() as u16;
+                self.buffer.insert_lines(at, vec![indent]);
+                self.goto_line(at);
+            }
+            Action::DeleteChar => {
+                self.begin_change();
+                let pos = self.cursor_pos();
+                if pos.col < self.buffer.lines[pos.line].len() {
+                    self.buffer.lines[pos.line].remove(pos.col);
+                }
+                // the cursor stays on the start of a character
+                self.cursor_x = pos.col.min(motion::last_char(&self.buffer, pos.line)) as u16;
+                self.buffer.end_change();
+            }
+
+            Action::Undo => {
+                let cursor = self.cursor_position();
+                match self.buffer.undo(cursor) {
+                    Some(position) => self.restore_cursor(position),
+                    None => self.command_bar.message = Some("Already at oldest change".to_string()),
+                }
+            }
+            Action::Redo => {
+                let cursor = self.cursor_position();
+                match self.buffer.redo(cursor) {
+                    Some(position) => self.restore_cursor(position),
+                    None => self.command_bar.message = Some("Already at newest change".to_string()),
+                }
+            }
+
+            Action::ReplaceChar(c) => {
+                let pos = self.cursor_pos();
+                let count = self.action_count;
+                // the byte where the `count` characters from the cursor on end, `3rx` fails
+                // without touching anything when the line is too short
+                let end = self.buffer.lines[pos.line][pos.col..]
+                    .char_indices()
+                    .nth(count - 1)
+                    .map(|(i, old)| pos.col + i + old.len_utf8());
+                if let Some(end) = end {
+                    self.begin_change();
+                    self.buffer.lines[pos.line].replace_range(pos.col..end, &c.to_string().repeat(count));
+                    self.cursor_x = (pos.col + c.len_utf8() * (count - 1)) as u16;
+                    self.buffer.end_change();
+                }
+            }
+            Action::RepeatChange => {
+                if !self.replaying {
+                    self.repeat_last_change()?;
+                }
+            }
+
+            Action::Motion(motion) => {
+                self.apply_motion(motion);
+            }
+            Action::RepeatFind(reverse) => {
+                if let Some(motion) = self.last_find.and_then(|m| m.repeat_find(reverse)) {
+                    self.apply_motion(motion);
+                }
+            }
+
+            Action::ScrollLines(down) => {
+                self.scroll_lines(self.action_count, down);
+            }
+            Action::ScrollHalfPage(down) => {
+                self.scroll_half_page(down);
+            }
+            Action::ScrollPage(down) => {
+                self.scroll_page(self.action_count, down);
+            }
+            Action::DeleteSelection => {
+                self.delete_selection();
+            }
+            Action::YankSelection => {
+                self.yank_selection();
+            }
+            Action::AlignCursor(align, first_non_blank) => {
+                self.align_cursor(align);
+                if first_non_blank {
+                    self.cursor_x = motion::first_non_blank(&self.buffer, self.current_line()).col as u16;
                 }
             }
         }
@@ -224,85 +844,235 @@ impl Editor {
         Ok(())
     }
 
-    fn handle_tab(&mut self) -> anyhow::Result<()> {
-        match self.mode {
-            Mode::Insert => {
-                let mut new_line = String::new();
-                let editable_line_index = (self.cursor_y + self.viewport_top) as usize;
-                let old_line = self.buffer.lines[editable_line_index].clone();
+    fn set_mode(&mut self, new_mode: Mode) {
+        if new_mode == self.mode {
+            return;
+        }
+
+        if new_mode == Mode::Command {
+            self.command_bar.message = None;
+        }
+        if self.mode == Mode::Command {
+            self.command_bar.command = ":".to_string();
+            self.command_bar.completion = None;
+        }
 
-                if self.cursor_x > old_line.len() as u16 {
-                    self.stdout.queue(MoveTo(self.cursor_x as u16 + self.buffer.get_file_lenght().to_string().len() as u16+1, self.cursor_y))?;
-                    self.cursor_x = old_line.len() as u16;
+        // the last selection stays reachable as the `'<` and `'>` marks
+        if self.mode == Mode::Visual {
+            if let Some((from, to)) = self.selection() {
+                let last = if to.col == 0 && to.line > from.line { to.line - 1 } else { to.line };
+                self.buffer.set_mark('<', from.line);
+                self.buffer.set_mark('>', last);
+                if new_mode == Mode::Command {
+                    self.command_bar.command = ":'<,'>".to_string();
                 }
+            }
+        }
+        if new_mode == Mode::Visual {
+            self.visual_anchor = self.cursor_pos();
+        }
 
-                if self.cursor_x > 0 {
-                    let unchanged_left_part = &old_line[0..self.cursor_x as usize];
-                    let unchanged_right_part = &old_line[self.cursor_x as usize..old_line.len()];
+        // a whole insert or replace session is one undo step
+        let was_editing = self.is_editing();
+        let editing = matches!(new_mode, Mode::Insert | Mode::Replace);
+        if editing && !was_editing {
+            self.begin_change();
+            self.insert_keys.clear();
+            self.replaced.clear();
+            self.insert_count = self.action_count;
+            self.insert_opened_line = false;
+        }
+        if was_editing && !editing {
+            self.insert_count = 1;
+            self.buffer.end_change();
+        }
 
-                    new_line.push_str(unchanged_left_part);
-                    new_line.push_str("    ");
-                    new_line.push_str(unchanged_right_part);
-                } else {
-                    new_line.push_str("    ");
-                    new_line.push_str(&old_line);
-                }
+        let old = plugins::mode_name(self.mode);
+        self.mode = new_mode;
+        if was_editing && !editing {
+            self.emit_event("InsertLeave", json!({}));
+        }
+        self.emit_event("ModeChanged", json!({ "old": old, "new": plugins::mode_name(new_mode) }));
+    }
 
-                self.buffer.lines[editable_line_index] = new_line;
-                self.cursor_x = self.cursor_x.saturating_add(4);
-            },
-            Mode::Normal => {
+    // `BufRead` for the file of the buffer after it was read, or `BufNewFile` when there is
+    // no such file yet; every way of reading it goes through here: starting the editor, `:e`
+    // and a shell command that changed it
+    fn emit_file_read(&mut self) {
+        let exists = self.buffer.file.as_deref().is_some_and(|f| Path::new(f).exists());
+        self.emit_event(if exists { "BufRead" } else { "BufNewFile" }, json!({}));
+    }
 
-            },
-            Mode::Command => {
+    // writes the buffer, or the lines `first..=last` of it, to `file` between `BufWritePre`
+    // and `BufWritePost`; every write goes through here once the file is known
+    fn write_file(&mut self, file: &str, range: Option<(usize, usize)>) -> anyhow::Result<()> {
+        self.emit_event("BufWritePre", json!({ "written": file }));
+        match range {
+            // the autocommands may have taken lines away
+            Some((first, last)) => {
+                let end = self.buffer.get_file_lenght() - 1;
+                self.buffer.save_lines(file, first.min(end), last.min(end))?
+            }
+            None => self.buffer.save_by_name(file)?,
+        }
+        self.emit_event("BufWritePost", json!({ "written": file }));
+        Ok(())
+    }
 
+    // types the text of the finished insert session again, `3ihi<Esc>` gives `hihihi`
+    fn repeat_insert(&mut self) -> anyhow::Result<()> {
+        let keys = std::mem::take(&mut self.insert_keys);
+        let count = std::mem::replace(&mut self.insert_count, 1);
+
+        let replaying = std::mem::replace(&mut self.replaying, true);
+        let mut result = Ok(());
+        for _ in 1..count {
+            // `3o` puts every copy on a line of its own
+            if self.insert_opened_line {
+                self.apply_action(Action::OpenLine(true))?;
+            }
+            result = self.feed_keys(&keys);
+            if result.is_err() {
+                break;
+            }
+        }
+        self.replaying = replaying;
+
+        result
+    }
+
+    // opens an undo step at the current cursor position
+    fn begin_change(&mut self) {
+        let cursor = self.cursor_position();
+        self.buffer.begin_change(cursor);
+    }
+
+    fn cursor_position(&self) -> (usize, usize) {
+        (self.current_line(), self.cursor_x as usize)
+    }
+
+    fn restore_cursor(&mut self, (line, column): (usize, usize)) {
+        self.goto_line(line);
+        let len = self.buffer.lines[self.current_line()].len();
+        self.cursor_x = column.min(len) as u16;
+    }
+
+    // moves the cursor with a motion, leaving it in place when the motion fails
+    fn apply_motion(&mut self, motion: Motion) {
+        if let Motion::FindChar(_, FindKind { repeated: false, .. }) = motion {
+            self.last_find = Some(motion);
+        }
+
+        let count = (self.typed_count > 0).then_some(self.typed_count);
+        let top = self.viewport_top as usize;
+        let screen = Screen {
+            top,
+            height: self.last_visible_line(top) - top + 1,
+            scrolloff: self.scrolloff(),
+        };
+
+        if let Some(to) = motion::apply(&self.buffer, self.cursor_pos(), motion, count, screen) {
+            self.goto_line(to.line);
+            self.cursor_x = to.col as u16;
+        }
+    }
+
+    // cursor as a buffer position, kept inside the line and on a character boundary
+    fn cursor_pos(&self) -> Position {
+        let line = self.current_line();
+        let text = &self.buffer.lines[line];
+        let mut col = (self.cursor_x as usize).min(text.len());
+        while !text.is_char_boundary(col) {
+            col -= 1;
+        }
+
+        Position::new(line, col)
+    }
+
+    // runs key events through the current mode as if they were typed
+    fn feed_keys(&mut self, keys: &[KeyEvent]) -> anyhow::Result<()> {
+        for key in keys {
+            self.process_event(event::Event::Key(*key))?;
+            if !self.running {
+                break;
             }
         }
 
         Ok(())
     }
 
-    fn handle_changing(&mut self, v: char) -> anyhow::Result<()> {
+    // 0-based index of the buffer line under the cursor
+    fn current_line(&self) -> usize {
+        (self.cursor_y + self.viewport_top) as usize
+    }
+
+    fn handle_tab(&mut self) -> anyhow::Result<()> {
         match self.mode {
             Mode::Insert => {
-                let mut v1:String = v.to_string();
-                match v {
-                    '(' => v1 = "()".to_string(),
-                    '{' => v1 = "{}".to_string(),
-                    '[' => v1 = "[]".to_string(),
-                    '\'' => v1 = "''".to_string(),
-                    '"' => v1 = "\"\"".to_string(),
-                    _ => {}
-                }
-                let mut new_line = String::new();
-                let editable_line_index = (self.cursor_y + self.viewport_top) as usize;
-                let old_line = self.buffer.lines[editable_line_index].clone();
+                let line = self.current_line();
+                let text = &self.buffer.lines[line];
+                let x = (self.cursor_x as usize).min(text.len());
+                let col = self.tabs.display_col(text, x);
+
+                // without `softtabstop` a tab or the spaces up to the next tab stop, with it the
+                // white space before the cursor is filled up to the next soft tab stop
+                let (start, filled) = match self.tabs.softtabstop {
+                    0 if !self.tabs.expandtab => (x, "\t".to_string()),
+                    0 => (x, self.tabs.fill(col, (col / self.tabs.tabstop + 1) * self.tabs.tabstop)),
+                    step => {
+                        let start = text[..x].trim_end_matches([' ', '\t']).len();
+                        let from = self.tabs.display_col(text, start);
+                        (start, self.tabs.fill(from, (col / step + 1) * step))
+                    }
+                };
 
-                if self.cursor_x > old_line.len() as u16 {
-                    self.stdout.queue(MoveTo(self.cursor_x as u16 + self.buffer.get_file_lenght().to_string().len() as u16+1, self.cursor_y))?;
-                    self.cursor_x = old_line.len() as u16;
-                }
+                self.buffer.lines[line].replace_range(start..x, &filled);
+                self.cursor_x = (start + filled.len()) as u16;
+            }
+            Mode::Replace => {
+                // the tab is put in front of the character under the cursor, Backspace takes it out again
+                let line = self.current_line();
+                let x = (self.cursor_x as usize).min(self.buffer.lines[line].len());
+                let col = self.tabs.display_col(&self.buffer.lines[line], x);
+                let filled = match self.tabs.expandtab {
+                    true => " ".repeat(self.tabs.tabstop - col % self.tabs.tabstop),
+                    false => "\t".to_string(),
+                };
 
-                if self.cursor_x > 0 {
-                    let unchanged_left_part = &old_line[0..self.cursor_x as usize];
-                    let unchanged_right_part = &old_line[self.cursor_x as usize..old_line.len()];
+                self.buffer.lines[line].insert_str(x, &filled);
+                self.replaced.extend(filled.chars().map(|_| None));
+                self.cursor_x = (x + filled.len()) as u16;
+            }
+            Mode::Normal | Mode::Visual => {
 
-                    new_line.push_str(unchanged_left_part);
-                    new_line.push_str(&v1);
-                    new_line.push_str(unchanged_right_part);
-                } else {
-                    new_line.push_str(&v1);
-                    new_line.push_str(&old_line);
-                }
+            },
+            Mode::Command => {
+                self.complete_command();
+            }
+        }
 
-                self.buffer.lines[editable_line_index] = new_line;
-                self.cursor_x = self.cursor_x.saturating_add(1);
+        Ok(())
+    }
+
+    fn handle_changing(&mut self, v: char) -> anyhow::Result<()> {
+        match self.mode {
+            Mode::Insert => {
+                let line = self.current_line();
+                let x = (self.cursor_x as usize).min(self.buffer.lines[line].len());
+                let typed = self.auto_pairs.on_type(&self.buffer.filetype, &self.buffer.lines[line], x, v);
+
+                let text = &mut self.buffer.lines[line];
+                match typed {
+                    Typed::Pair(open, close) => text.insert_str(x, &format!("{}{}", open, close)),
+                    Typed::SkipOver => {}
+                    Typed::Plain => text.insert(x, v),
+                }
+                self.cursor_x = (x + v.len_utf8()) as u16;
+            }
+            Mode::Replace => {
+                self.replace_under_cursor(v);
             }
             Mode::Command => {
-
-                self.cursor_x = self.command_bar.command.len() as u16 + 1;
-                self.stdout
-                        .queue(MoveTo(self.cursor_x, self.cursor_y))?;
                 // add char to command in command mode
                 self.command_bar.command.push(v);
             }
@@ -312,149 +1082,216 @@ impl Editor {
         Ok(())
     }
 
-    fn handle_backspace(&mut self) -> anyhow::Result<()> {
+    // Replace mode: overwrites the character under the cursor, remembering the original
+    fn replace_under_cursor(&mut self, v: char) {
+        let line = self.current_line();
+        let text = &mut self.buffer.lines[line];
+        let x = (self.cursor_x as usize).min(text.len());
+
+        let original = text[x..].chars().next();
+        match original {
+            Some(c) => text.replace_range(x..x + c.len_utf8(), &v.to_string()),
+            None => text.push(v),
+        }
+
+        self.replaced.push(original);
+        self.cursor_x = (x + v.len_utf8()) as u16;
+    }
+
+    // Replace mode Backspace: brings back what this session overwrote,
+    // text from before the session is only moved over
+    fn restore_replaced(&mut self) {
+        let line = self.current_line();
+        let text = &mut self.buffer.lines[line];
+        let x = (self.cursor_x as usize).min(text.len());
+
+        let previous = match text[..x].chars().next_back() {
+            Some(c) => c,
+            None => return,
+        };
+        let start = x - previous.len_utf8();
+
+        match self.replaced.pop() {
+            Some(Some(original)) => text.replace_range(start..x, &original.to_string()),
+            Some(None) => text.replace_range(start..x, ""),
+            None => {}
+        }
+        self.cursor_x = start as u16;
+    }
+
+    // pasted text goes in as it is, without auto-pairs or per-key handling, and can be
+    // undone on its own; Replace mode overwrites with it like with typed text
+    fn handle_paste(&mut self, text: &str) {
+        let text = text.replace("\r\n", "\n").replace('\r', "\n");
+        let lines: Vec<String> = text.split('\n').map(|l| l.to_string()).collect();
+
         match self.mode {
+            Mode::Insert => {
+                self.replaced.clear();
+                // closes the undo step of the text typed so far and starts a new one after the paste
+                self.buffer.end_change();
+                self.begin_change();
+                let at = self.cursor_pos();
+                let (line, col) = self.buffer.insert_text((at.line, at.col), &lines);
+                self.buffer.end_change();
+                self.goto_line(line);
+                self.cursor_x = col as u16;
+                self.begin_change();
+            }
+            Mode::Replace => {
+                self.buffer.end_change();
+                self.begin_change();
+                for (i, part) in lines.iter().enumerate() {
+                    if i > 0 {
+                        // line breaks are put in like Enter puts them in
+                        self.replaced.clear();
+                        let at = self.cursor_pos();
+                        let (line, col) = self.buffer.insert_text((at.line, at.col), &[String::new(), String::new()]);
+                        self.goto_line(line);
+                        self.cursor_x = col as u16;
+                    }
+                    for c in part.chars() {
+                        self.replace_under_cursor(c);
+                    }
+                }
+                self.buffer.end_change();
+                self.begin_change();
+            }
+            Mode::Normal => {
+                // like `P` with the text in a register
+                self.begin_change();
+                let at = self.cursor_pos();
+                let (line, col) = self.buffer.insert_text((at.line, at.col), &lines);
+                self.goto_line(line);
+                let put = &self.buffer.lines[line][..col];
+                self.cursor_x = put.char_indices().next_back().map(|(i, _)| i).unwrap_or(0) as u16;
+                self.buffer.end_change();
+            }
             Mode::Command => {
-                if self.command_bar.command.len() > 1 {
-                    self.stdout
-                        .queue(MoveTo(self.cursor_x.saturating_sub(1), self.cursor_y))?;
-                    self.stdout.queue(PrintStyledContent(" ".on(Color::Rgb {
-                        r: 255,
-                        g: 255,
-                        b: 255,
-                    })))?;
-                    self.cursor_x = self.cursor_x.saturating_sub(1);
-                    self.stdout.flush()?;
+                self.command_bar.command.push_str(&lines.join(" "));
+            }
+            Mode::Visual => {
+                // the text takes the place of the selection
+                if let Some((from, to)) = self.selection() {
+                    self.begin_change();
+                    self.buffer.remove_text((from.line, from.col), (to.line, to.col));
+                    let (line, col) = self.buffer.insert_text((from.line, from.col), &lines);
+                    self.goto_line(line);
+                    let put = &self.buffer.lines[line][..col];
+                    self.cursor_x = put.char_indices().next_back().map(|(i, _)| i).unwrap_or(0) as u16;
+                    self.buffer.end_change();
+                }
+                self.set_mode(Mode::Normal);
+            }
+        }
+    }
 
+    fn handle_backspace(&mut self) -> anyhow::Result<()> {
+        match self.mode {
+            Mode::Command
+                if self.command_bar.command.len() > 1 => {
+                    // the bar is drawn again with the shorter command
                     self.command_bar.command.pop();
                 }
-            }
             Mode::Insert => {
                 let mut new_line = String::new();
                 let editable_line_index = (self.cursor_y + self.viewport_top) as usize;
                 let old_line = self.buffer.lines[editable_line_index].clone();
-
-                if self.cursor_x > old_line.len() as u16 {
-                    self.stdout.queue(MoveTo(self.cursor_x as u16 + self.buffer.get_file_lenght().to_string().len() as u16+1, self.cursor_y))?;
-                    self.cursor_x = old_line.len() as u16;
-                }
-
-                if self.cursor_x > 0 {
-                    let unchanged_left_part = &old_line[0..self.cursor_x as usize - 1];
-                    let unchanged_right_part = &old_line[self.cursor_x as usize..old_line.len()];
-                    new_line.push_str(unchanged_left_part);
-                    new_line.push_str(unchanged_right_part);
-
-                    self.buffer.lines[editable_line_index] = new_line;
-                    self.cursor_x = self.cursor_x.saturating_sub(1);
+                let x = (self.cursor_x as usize).min(old_line.len());
+
+                let soft_tab = self.soft_tab_back(&old_line, x);
+                if let Some((start, filled)) = soft_tab {
+                    self.buffer.lines[editable_line_index].replace_range(start..x, &filled);
+                    self.cursor_x = (start + filled.len()) as u16;
+                } else if let Some(previous) = old_line[..x].chars().next_back() {
+                    let start = x - previous.len_utf8();
+                    // Backspace between `(` and `)` takes both
+                    let end = if self.auto_pairs.on_backspace(&self.buffer.filetype, &old_line, x) {
+                        x + old_line[x..].chars().next().map_or(0, |c| c.len_utf8())
+                    } else {
+                        x
+                    };
+
+                    self.buffer.lines[editable_line_index].replace_range(start..end, "");
+                    self.cursor_x = start as u16;
                 } else {
-                    if self.cursor_y > 0 {
+                    if editable_line_index > 0 {
                         let previous_line = self.buffer.lines[editable_line_index-1].clone();
                         new_line.push_str(&previous_line);
                         new_line.push_str(&old_line);
 
-                        let l = (&previous_line).len();
+                        let l = previous_line.len();
 
                         self.buffer.lines[editable_line_index-1] = new_line;
-                        self.cursor_y = self.cursor_y.saturating_sub(1);
+                        self.buffer.remove_lines(editable_line_index, editable_line_index);
+                        self.scroll_to_line(editable_line_index - 1);
                         self.cursor_x = l as u16;
-                        self.buffer.lines.remove(editable_line_index);
                     }
                     
                 }
             }
+            Mode::Replace => {
+                self.restore_replaced();
+            }
             _ => {}
         }
 
         Ok(())
     }
 
+    // with `softtabstop` Backspace right after white space goes back to the previous soft tab
+    // stop, returns the white space to replace the run before the cursor with
+    fn soft_tab_back(&self, text: &str, x: usize) -> Option<(usize, String)> {
+        let step = self.tabs.softtabstop;
+        let start = text[..x].trim_end_matches([' ', '\t']).len();
+        if step == 0 || start == x {
+            return None;
+        }
+
+        let col = self.tabs.display_col(text, x);
+        let from = self.tabs.display_col(text, start);
+        let target = ((col - 1) / step * step).max(from);
+        Some((start, self.tabs.fill(from, target)))
+    }
+
     fn handle_enter(&mut self) -> anyhow::Result<()>{
         match self.mode {
             Mode::Command => {
-                self.execute_command(self.command_bar.command.clone())?;
-                self.command_bar.command = String::new();
-                self.command_bar.command.push(':');
-            }
-            Mode::Insert => {
-                let editable_line_index = (self.cursor_y + self.viewport_top) as usize;//индекс
-                let old_line = self.buffer.lines[editable_line_index].clone(); //старая строка
-                if self.cursor_x+1 > old_line.len() as u16{
-                    self.buffer.lines.
-                    splice(editable_line_index+1..editable_line_index+1, (&["".to_string()])
-                    .iter()
-                    .cloned());
-                    self.cursor_y = self.cursor_y.saturating_add(1);
-                    self.cursor_x = 0;
-                }
-            
-                else {// not crossed the boundings
-                    let unchanged_left_part = &old_line[0..self.cursor_x as usize];//запоминаем левую часть
-                    let unchanged_right_part = &old_line[self.cursor_x as usize..old_line.len()]; //запоминаем правую часть
-                    let y = &[unchanged_left_part.to_string(), unchanged_right_part.to_string()];//записываем их в список
-                    self.buffer.lines.remove(editable_line_index);//удаляем текущую строку
-                    self.buffer.lines.splice(editable_line_index..editable_line_index, y
-                        .iter()
-                        .cloned());//вставляем те части в середину
-                    self.cursor_y = self.cursor_y.saturating_add(1);
-                    self.cursor_x = 0;
+                let command = std::mem::replace(&mut self.command_bar.command, ":".to_string());
+                self.set_mode(Mode::Normal);
+                if let Err(e) = self.execute_command(command) {
+                    self.command_bar.message = Some(e.to_string());
                 }
-                
-                let cannot_move_down = self.viewport_height()
-                            >= (self.buffer.get_file_lenght() - self.viewport_top as usize);
-
-                        if self.cursor_y + self.viewport_top
-                            < self.buffer.get_file_lenght() as u16 - 1
-                        {
-                            if self.cursor_y
-                                < self.viewport_height() as u16 - self.scrolling_padding
-                                || cannot_move_down
-                            {
-                                self.cursor_y = self.cursor_y.saturating_add(1);
-                            }
-                            if self.cursor_y
-                                == self.viewport_height() as u16 - self.scrolling_padding
-                            {
-                                if !cannot_move_down {
-                                    self.viewport_top = self.viewport_top.saturating_add(1);
-                                }
-                            }
-                        }
             }
-            Mode::Normal => {
-                self.cursor_y = self.cursor_y.saturating_add(1);
-            }
-        }
-        Ok(())
-    }
+            Mode::Insert | Mode::Replace => {
+                // overwritten text can not be restored across lines
+                self.replaced.clear();
 
-    fn execute_command(&mut self, command: String) -> anyhow::Result<()> {
-        let _command = command.trim().to_string();
-        if _command == self.qiut {
-            self.running = false;
-        }
-        else if _command == self.save {
-            self.buffer.save()?;
-        }
-        else if _command.starts_with(&self.save) {
-            let c = _command.clone().trim().to_string();
-            let splitted_command = c.split(' ');
-            let splitted_command_vec: Vec<&str> = splitted_command.collect();
-            let new_file_name = splitted_command_vec.last();
+                let editable_line_index = self.current_line();
+                let old_line = self.buffer.lines[editable_line_index].clone();
+                let split_at = (self.cursor_x as usize).min(old_line.len());
 
-            if let Some(nfn) = new_file_name {
-                self.buffer.save_by_name(nfn)?;
-            }
-        }
-        else if _command.starts_with(&self.edit) {
-            let c = _command.clone().trim().to_string();
-            let splitted_command = c.split(' ');
-            let splitted_command_vec: Vec<&str> = splitted_command.collect();
-            let new_file_name = splitted_command_vec.last();
+                let (left, right) = old_line.split_at(split_at);
+                let filetype = &self.buffer.filetype;
+                let indent = self.indent.new_line_indent(filetype, left, &self.tabs);
 
-            self.buffer.load_file(new_file_name.copied())?;
+                // the part right of the cursor moves to a new line below, Enter inside `{}`
+                // puts the closing bracket on a line of its own
+                let mut new_lines = vec![format!("{}{}", indent, right.trim_start())];
+                if self.indent.splits_pair(filetype, left, right) {
+                    new_lines = vec![indent.clone(), format!("{}{}", indent::leading(left), right.trim_start())];
+                }
+                // a line left with nothing but indentation is emptied
+                let left = if self.indent.copies_indent() && left.trim().is_empty() { "" } else { left };
+
+                self.buffer.lines[editable_line_index] = left.to_string();
+                self.buffer.insert_lines(editable_line_index + 1, new_lines);
+                self.scroll_to_line(editable_line_index + 1);
+                self.cursor_x = indent.len() as u16;
+            }
+            // normal mode Enter is the `+` motion
+            Mode::Normal | Mode::Visual => {}
         }
-        
         Ok(())
     }
 
@@ -462,116 +1299,243 @@ impl Editor {
         
         self.draw_viewport()?;
         self.draw_status_bar()?;
+        if self.command_bar.has_output() {
+            return self.command_bar.draw_output(&mut self.stdout, size, &self.theme);
+        }
         match self.mode {
             Mode::Command => {
                 self.draw_command_bar()?;
-                return Ok(());
+                Ok(())
             }
             _ => {
-                self.command_bar.clean(&mut self.stdout, size)?;
-                return Ok(());
+                self.command_bar.clean(&mut self.stdout, size, &self.theme)?;
+                Ok(())
             }
         }
     }
 
-    pub fn viewport_line(&self, n: u16) -> Option<String> {
-        let buffer_line = self.viewport_top + n;
-
-        self.buffer.get(buffer_line as usize)
-    }
-
-    pub fn number_line(&self, number: u16) -> Option<String>{
-        Some((self.viewport_top + number+1).to_string())
-    }
-
     pub fn draw_viewport(&mut self) -> anyhow::Result<()> {
-        let file_len = self.buffer.get_file_lenght();
-        for i in 0..self.viewport_height() {
-            let number_line = self.number_line(i as u16);
-            let mut line = String::from(" ".repeat(file_len.to_string().len()+1));
-            self.stdout
-                .queue(MoveTo(0, i as u16))?
-                .queue(Print(line.clone()))?;
-                                //условие, если курсор ниже, чем написанный текст в файле
-            if i < file_len || i as i32 - 1 < self.cursor_y.into() && self.mode != Mode::Command{
+        let gutter = self.gutter_width();
+        let color = self.theme.color("line_number");
+        let background = self.theme.background();
+
+        // only the lines up to the bottom of the window are highlighted
+        let last = self.viewport_top as usize + self.viewport_height();
+        self.syntax.update(&self.buffer.filetype, self.buffer.file.as_deref(), &self.buffer.lines, last);
+
+        let first_row = self.viewport_row();
+        for (i, row) in self.screen_rows().into_iter().enumerate() {
+            self.stdout.queue(MoveTo(0, (first_row + i) as u16))?;
+            if gutter > 0 {
+                let number = row.number.map(|n| n.to_string()).unwrap_or_default();
                 self.stdout
-                    .queue(MoveTo(0, i as u16))?
-                    .queue(style::PrintStyledContent(
-                    format!("{}",number_line.clone().unwrap())
-                    .with(Color::Rgb { 
-                        r: self.font_color.0, 
-                        g: self.font_color.1, 
-                        b: self.font_color.2 
-                    })))?;
-            }
-            if i < file_len {
-                line = match self.viewport_line(i as u16) {
-                    Some(s) => s,
-                    None => String::new(),
-                };
+                    .queue(PrintStyledContent(on_background(format!("{number:<gutter$}").with(color), background)))?;
+            }
+
+            // cells of the same colour are printed together, the selection in reverse video
+            let (from, to) = row.selected.unwrap_or((0, 0));
+            let chars: Vec<char> = row.text.chars().collect();
+            let mut start = 0;
+            while start < chars.len() {
+                let highlight = row.highlights.get(start).copied().unwrap_or(Highlight::Normal);
+                let selected = start >= from && start < to;
+                let end = (start + 1..chars.len())
+                    .find(|&c| {
+                        row.highlights.get(c).copied().unwrap_or(Highlight::Normal) != highlight
+                            || (c >= from && c < to) != selected
+                    })
+                    .unwrap_or(chars.len());
+
+                let text: String = chars[start..end].iter().collect();
+                let styled = on_background(text.with(self.theme.highlight_color(highlight)), background);
+                self.stdout.queue(PrintStyledContent(if selected { styled.reverse() } else { styled }))?;
+                start = end;
             }
-            let w = self.viewport_width();
-            self.stdout
-                .queue(MoveTo(file_len.to_string().len() as u16+1, i as u16))?
-                .queue(style::PrintStyledContent(
-                    format!("{line:<width$}", width = w as usize).with(Color::Rgb { 
-                        r: self.font_color.0, 
-                        g: self.font_color.1, 
-                        b: self.font_color.2 
-                    })))?;
         }
         Ok(())
     }
 
     pub fn draw_status_bar(&mut self) -> anyhow::Result<()> {
         if self.enable_status_bar {
-            self.status_bar.draw(&mut self.stdout, self.size)?;
+            self.status_bar.draw(&mut self.stdout, self.size, &self.theme)?;
         }
 
         Ok(())
     }
 
     pub fn draw_command_bar(&mut self) -> anyhow::Result<()> {
-        self.command_bar.draw(&mut self.stdout, self.size)?;
+        self.command_bar.draw(&mut self.stdout, self.size, &self.theme)?;
 
         Ok(())
     }
 
+    // `:colorscheme {name}`, colours from the config file stay
+    fn set_theme(&mut self, name: &str) -> anyhow::Result<()> {
+        let theme_dir = self.config_options.config_dir().map(|d| d.join("themes"));
+        let mut theme = Theme::load(name, theme_dir.as_deref())?;
+        for (element, rgb) in &self.color_overrides {
+            theme.set(element, *rgb);
+        }
+        theme.depth = self.theme.depth;
+        self.theme = theme;
+        // rows past the end of the text only get the new background when drawn again
+        if self.terminal {
+            self.stdout.queue(terminal::Clear(terminal::ClearType::All))?;
+        }
+
+        Ok(())
+    }
+
+    // rows left for the text next to the status and command bars
     fn viewport_height(&self) -> usize {
-        self.size.1 as usize - 2
+        let bars = if self.enable_status_bar { 2 } else { 1 };
+        (self.size.1 as usize).saturating_sub(bars).max(1)
     }
 
-    fn viewport_width(&self) -> u16 {
-        self.size.0
+    // terminal row of the first row of text, below the status bar when it is on top
+    fn viewport_row(&self) -> usize {
+        match self.enable_status_bar && self.status_bar.side() == Side::Top {
+            true => 1,
+            false => 0,
+        }
     }
 
-    fn handel_event(&self, ev: event::Event) -> anyhow::Result<Option<Action>> {
+    // line numbers and the space after them
+    fn gutter_width(&self) -> usize {
+        match self.number {
+            true => self.buffer.get_file_lenght().to_string().len() + 1,
+            false => 0,
+        }
+    }
+
+    // columns left for the text next to the line numbers
+    fn text_width(&self) -> usize {
+        (self.size.0 as usize).saturating_sub(self.gutter_width()).max(1)
+    }
+
+    fn handel_event(&mut self, ev: event::Event) -> anyhow::Result<Option<Action>> {
         match self.mode {
             Mode::Normal => self.handle_normal_event(ev),
-            Mode::Insert => self.handle_insert_event(ev),
+            Mode::Insert | Mode::Replace => self.handle_insert_event(ev),
+            Mode::Visual => self.handle_visual_event(ev),
             Mode::Command => self.handle_command_event(ev),
         }
     }
 
-    fn handle_normal_event(&self, ev: event::Event) -> anyhow::Result<Option<Action>> {
+    fn handle_normal_event(&mut self, ev: event::Event) -> anyhow::Result<Option<Action>> {
+        if let event::Event::Key(event) = ev {
+            if !self.pending.is_empty() {
+                return Ok(self.handle_pending_key(event));
+            }
+        }
+
         match ev {
             event::Event::Key(event) => match event.code {
                 event::KeyCode::Char(':') => Ok(Some(Action::SetMode(Mode::Command))),
 
+                event::KeyCode::Char(c) if is_count_key(&event, self.count > 0) => {
+                    self.count = self.count.saturating_mul(10).saturating_add(c as usize - '0' as usize);
+                    Ok(None)
+                }
+                event::KeyCode::Esc => {
+                    self.count = 0;
+                    Ok(None)
+                }
+
+                event::KeyCode::Char(c) if event.modifiers.contains(event::KeyModifiers::CONTROL) => {
+                    Ok(control_action(c))
+                }
+                event::KeyCode::Char(c @ ('m' | '\'' | 'r' | 'z')) => {
+                    self.pending.push(c);
+                    Ok(None)
+                }
+                event::KeyCode::Char('R') => Ok(Some(Action::SetMode(Mode::Replace))),
+                event::KeyCode::Char(c @ ('g' | 'f' | 't' | 'F' | 'T')) => {
+                    self.pending.push(c);
+                    Ok(None)
+                }
+                event::KeyCode::Char('.') => Ok(Some(Action::RepeatChange)),
+                event::KeyCode::Char('p') => Ok(Some(Action::Put(true))),
+                event::KeyCode::Char('P') => Ok(Some(Action::Put(false))),
+                event::KeyCode::Char('x') => Ok(Some(Action::DeleteChar)),
+                event::KeyCode::Char('u') => Ok(Some(Action::Undo)),
+
+                event::KeyCode::Char('a') => Ok(Some(Action::Append)),
+                event::KeyCode::Char('A') => Ok(Some(Action::AppendToEnd)),
+                event::KeyCode::Char('I') => Ok(Some(Action::InsertAtStart)),
+                event::KeyCode::Char('o') => Ok(Some(Action::OpenLine(true))),
+                event::KeyCode::Char('O') => Ok(Some(Action::OpenLine(false))),
+
                 event::KeyCode::Up | event::KeyCode::Char('k') => Ok(Some(Action::MoveUp)),
                 event::KeyCode::Down | event::KeyCode::Char('j') => Ok(Some(Action::MoveDown)),
                 event::KeyCode::Left | event::KeyCode::Char('h') => Ok(Some(Action::MoveLeft)),
                 event::KeyCode::Right | event::KeyCode::Char('l') => Ok(Some(Action::MoveRight)),
 
-                event::KeyCode::Enter => Ok(Some(Action::EnterKey)),
-
                 event::KeyCode::Char('i') => Ok(Some(Action::SetMode(Mode::Insert))),
+                event::KeyCode::Char('v') => Ok(Some(Action::SetMode(Mode::Visual))),
+
+                event::KeyCode::Char(';') => Ok(Some(Action::RepeatFind(false))),
+                event::KeyCode::Char(',') => Ok(Some(Action::RepeatFind(true))),
+                event::KeyCode::Char(c) => Ok(normal_motion(c).map(Action::Motion)),
+                event::KeyCode::Enter => Ok(Some(Action::Motion(Motion::NextLineStart))),
+                event::KeyCode::Home => Ok(Some(Action::Motion(Motion::LineStart))),
+                event::KeyCode::End => Ok(Some(Action::Motion(Motion::LineEnd))),
                 _ => Ok(None),
             },
             _ => Ok(None),
         }
     }
 
+    // second key of a two-key normal command like `ma`, `'a`, `rx`, `fx`, `gg` or `zz`
+    fn handle_pending_key(&mut self, event: KeyEvent) -> Option<Action> {
+        let prefix = std::mem::take(&mut self.pending);
+        let c = match event.code {
+            event::KeyCode::Char(c) => c,
+            // `z<CR>` is `zt` that also moves to the first non-blank character
+            event::KeyCode::Enter if prefix == "z" => return Some(Action::AlignCursor(Align::Top, true)),
+            _ => {
+                self.count = 0;
+                return None;
+            }
+        };
+
+        let find = |forward, till| {
+            Some(Action::Motion(Motion::FindChar(c, FindKind { forward, till, repeated: false })))
+        };
+
+        let action = match prefix.as_str() {
+            "r" => Some(Action::ReplaceChar(c)),
+            "f" => find(true, false),
+            "t" => find(true, true),
+            "F" => find(false, false),
+            "T" => find(false, true),
+            "g" => match c {
+                'g' => Some(Action::Motion(Motion::FirstLine)),
+                'j' => Some(Action::MoveScreenRow(true)),
+                'k' => Some(Action::MoveScreenRow(false)),
+                'e' => Some(Action::Motion(Motion::WordEndBackward { big: false })),
+                'E' => Some(Action::Motion(Motion::WordEndBackward { big: true })),
+                _ => None,
+            },
+            "z" => match c {
+                't' => Some(Action::AlignCursor(Align::Top, false)),
+                'z' => Some(Action::AlignCursor(Align::Middle, false)),
+                'b' => Some(Action::AlignCursor(Align::Bottom, false)),
+                '.' => Some(Action::AlignCursor(Align::Middle, true)),
+                '-' => Some(Action::AlignCursor(Align::Bottom, true)),
+                _ => None,
+            },
+            "m" if c.is_ascii_lowercase() => Some(Action::SetMark(c)),
+            "'" if c.is_ascii_lowercase() => Some(Action::JumpToMark(c)),
+            _ => None,
+        };
+        if action.is_none() {
+            self.count = 0;
+        }
+
+        action
+    }
+
     fn handle_insert_event(&self, ev: event::Event) -> anyhow::Result<Option<Action>> {
         match ev {
             event::Event::Key(event) => match event.code {
@@ -593,6 +1557,7 @@ impl Editor {
                 event::KeyCode::Esc => Ok(Some(Action::SetMode(Mode::Normal))),
                 event::KeyCode::Enter => Ok(Some(Action::EnterKey)),
                 event::KeyCode::Backspace => Ok(Some(Action::Backspace)),
+                event::KeyCode::Tab => Ok(Some(Action::TabKey)),
 
                 _ => Ok(None),
             },
@@ -603,6 +1568,13 @@ impl Editor {
 
 impl Drop for Editor {
     fn drop(&mut self) {
+        if !self.terminal {
+            return;
+        }
+        if self.mouse_captured {
+            let _ = self.stdout.execute(event::DisableMouseCapture);
+        }
+        let _ = self.stdout.execute(event::DisableBracketedPaste);
         let _ = self.stdout.execute(terminal::LeaveAlternateScreen); // Leave upper terminal layer
         let _ = terminal::disable_raw_mode();
 
@@ -612,3 +1584,20 @@ impl Drop for Editor {
         println!("~{} took", duration_sec);
     }
 }
+
+// `content` on the theme background, when the theme has one
+fn on_background(content: StyledContent<String>, background: Option<Color>) -> StyledContent<String> {
+    match background {
+        Some(background) => content.on(background),
+        None => content,
+    }
+}
+
+// the first of the problems found in the configuration and how many more there are
+fn error_message(mut errors: Vec<String>) -> Option<String> {
+    match errors.len() {
+        0 => None,
+        1 => Some(errors.remove(0)),
+        n => Some(format!("{} (and {} more)", errors.remove(0), n - 1)),
+    }
+}
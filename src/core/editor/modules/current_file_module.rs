@@ -1,13 +1,13 @@
-use std::io::Stdout;
-
 use super::BarModule;
 
 use super::Info;
 
+#[allow(dead_code)]
 pub struct CurrentFileModule {
     path: String,
     file: String,
-    enable: bool
+    enable: bool,
+    show_path: bool,
 }
 
 impl BarModule for CurrentFileModule {
@@ -23,20 +23,36 @@ impl BarModule for CurrentFileModule {
     }
     
     fn get_editor_info(&mut self, info: Info) {
-        self.file = *info.1.clone();
+        if *info.1 != self.file {
+            self.file = *info.1.clone();
+            let full = std::fs::canonicalize(&self.file).map(|p| p.display().to_string());
+            self.change_path(&full.unwrap_or_else(|_| self.file.clone()));
+        }
+    }
+
+    // switches between the file name as it was opened and its full path
+    fn on_click(&mut self) -> Option<String> {
+        self.show_path = !self.show_path;
+        None
     }
 }
 
+#[allow(dead_code)]
 impl CurrentFileModule {
     pub fn new() -> Self {
         Self {
             path: " ".to_string(),
             file: " ".to_string(),
-            enable: false
+            enable: false,
+            show_path: false,
         }
     }
     pub fn get_path(&self) -> String {
-        self.file.clone()
+        if self.show_path {
+            self.path.clone()
+        } else {
+            self.file.clone()
+        }
     }
 
     pub fn get_file_name(&self) -> &str {
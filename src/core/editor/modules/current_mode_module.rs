@@ -1,10 +1,9 @@
-use std::io::Stdout;
-
 use crate::core::editor::Mode;
 
 use super::BarModule;
 use super::Info;
 
+#[allow(dead_code)]
 pub struct CurrentModeModule {
     enable: bool,
     current_mode: Mode
@@ -25,6 +24,11 @@ impl BarModule for CurrentModeModule {
     fn get_editor_info(&mut self, info: Info) {
         self.current_mode = info.0;
     }
+
+    // back to Normal mode
+    fn on_click(&mut self) -> Option<String> {
+        Some("<Esc>".to_string())
+    }
     
 }
 
@@ -41,6 +45,8 @@ impl CurrentModeModule {
             Mode::Normal => "Normal".to_string(),
             Mode::Insert => "Insert".to_string(),
             Mode::Command => "Command".to_string(),
+            Mode::Replace => "Replace".to_string(),
+            Mode::Visual => "Visual".to_string(),
         }
     }
 }
@@ -1,15 +1,15 @@
-use std::io::Stdout;
 use chrono::prelude::*;
 
 use super::BarModule;
 use super::Info;
-use super::Rgb; // Rgb = (u8, u8, u8)
 
+#[allow(dead_code)]
 pub struct CurrentTimeModule {
     updatable: bool,
 
     current_time: DateTime<Local>,
     enable: bool,
+    show_seconds: bool,
 }
 
 impl BarModule for CurrentTimeModule {
@@ -24,7 +24,13 @@ impl BarModule for CurrentTimeModule {
         self.get_time_string()
     }
     
-    fn get_editor_info(&mut self, info: Info) {
+    fn get_editor_info(&mut self, _info: Info) {
+    }
+
+    // switches between showing seconds and not
+    fn on_click(&mut self) -> Option<String> {
+        self.show_seconds = !self.show_seconds;
+        None
     }
 }
 
@@ -34,6 +40,7 @@ impl CurrentTimeModule {
             current_time: Local::now(),
             updatable: true,
             enable: false,
+            show_seconds: false,
         }
     }
 
@@ -55,7 +62,10 @@ impl CurrentTimeModule {
             _minute = "0".to_string() + &_minute;
         }
         
-        let formatted_time = format!("{}:{}", _hour, _minute);
+        let mut formatted_time = format!("{}:{}", _hour, _minute);
+        if self.show_seconds {
+            formatted_time.push_str(&format!(":{:02}", self.current_time.second()));
+        }
 
         formatted_time
     }
@@ -7,11 +7,14 @@ use super::Mode;
 pub mod current_file_module;
 pub mod current_time_module;
 pub mod current_mode_module;
+pub mod plugin_module;
 
+#[allow(dead_code)]
 pub type Rgb = (u8, u8, u8);
 
 pub type Info = (Mode, Box<String>);
 
+#[allow(dead_code)]
 pub trait BarModule {
     fn enable(&mut self);
     fn disable(&mut self);
@@ -19,6 +22,11 @@ pub trait BarModule {
     fn give_display(&mut self) -> String;
                                       //(Mode, File name)
     fn get_editor_info(&mut self, info: Info); 
+
+    // keys the editor runs as if typed when the module is clicked, e.g. "<Esc>"
+    fn on_click(&mut self) -> Option<String> {
+        None
+    }
 }
 
 
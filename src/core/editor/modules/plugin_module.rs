@@ -0,0 +1,30 @@
+use super::super::plugins::host::ModuleText;
+use super::BarModule;
+use super::Info;
+
+// a module a plugin registered, the plugin sets its text
+pub struct PluginModule {
+    enable: bool,
+    text: ModuleText,
+}
+
+impl BarModule for PluginModule {
+    fn enable(&mut self) {
+        self.enable = true;
+    }
+    fn disable(&mut self) {
+        self.enable = false;
+    }
+
+    fn give_display(&mut self) -> String {
+        self.text.borrow().clone()
+    }
+
+    fn get_editor_info(&mut self, _info: Info) {}
+}
+
+impl PluginModule {
+    pub fn new(text: ModuleText) -> Self {
+        Self { enable: true, text }
+    }
+}
@@ -0,0 +1,442 @@
+use crate::core::buffer::Buffer;
+
+// place in the buffer, `col` is a byte offset into the line
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Position {
+    pub line: usize,
+    pub col: usize,
+}
+
+impl Position {
+    pub fn new(line: usize, col: usize) -> Self {
+        Self { line, col }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Motion {
+    // `big` is the WORD variant (`W`, `B`, `E`, `gE`)
+    WordForward { big: bool },
+    WordBackward { big: bool },
+    WordEnd { big: bool },
+    WordEndBackward { big: bool },
+
+    LineStart,
+    FirstNonBlank,
+    LineEnd,
+    // `Enter`/`+` and `-`
+    NextLineStart,
+    PreviousLineStart,
+    FirstLine,
+    LastLine,
+
+    ParagraphForward,
+    ParagraphBackward,
+    SentenceForward,
+    SentenceBackward,
+
+    ScreenTop,
+    ScreenMiddle,
+    ScreenBottom,
+
+    MatchPair,
+
+    // `f`, `t`, `F`, `T`
+    FindChar(char, FindKind),
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct FindKind {
+    pub forward: bool,
+    pub till: bool,
+    // set for `;` and `,`
+    pub repeated: bool,
+}
+
+impl Motion {
+    // the motion `;` (or `,` when `reverse`) repeats
+    pub fn repeat_find(&self, reverse: bool) -> Option<Motion> {
+        match *self {
+            Motion::FindChar(c, find) => Some(Motion::FindChar(c, FindKind {
+                forward: find.forward != reverse,
+                till: find.till,
+                repeated: true,
+            })),
+            _ => None,
+        }
+    }
+}
+
+// lines of the buffer shown on screen, used by `H`, `M` and `L`
+#[derive(Clone, Copy)]
+pub struct Screen {
+    pub top: usize,
+    pub height: usize,
+    // `H` and `L` stay this many lines away from the edges unless the file ends there
+    pub scrolloff: usize,
+}
+
+// applies `motion` from `from`; `count` is `None` when no count was typed,
+// `None` is returned when the motion fails (e.g. `f` finds nothing)
+pub fn apply(buffer: &Buffer, from: Position, motion: Motion, count: Option<usize>, screen: Screen) -> Option<Position> {
+    let n = count.unwrap_or(1).max(1);
+    let last_line = buffer.get_file_lenght() - 1;
+
+    let target = match motion {
+        Motion::WordForward { big } => repeat(from, n, |p| word_forward(buffer, p, big)),
+        Motion::WordBackward { big } => repeat(from, n, |p| word_backward(buffer, p, big)),
+        Motion::WordEnd { big } => repeat(from, n, |p| word_end(buffer, p, big)),
+        Motion::WordEndBackward { big } => repeat(from, n, |p| word_end_backward(buffer, p, big)),
+
+        Motion::LineStart => Some(Position::new(from.line, 0)),
+        Motion::FirstNonBlank => Some(first_non_blank(buffer, from.line)),
+        Motion::LineEnd => {
+            let line = (from.line + n - 1).min(last_line);
+            Some(Position::new(line, last_char(buffer, line)))
+        }
+        Motion::NextLineStart => {
+            if from.line == last_line {
+                return None;
+            }
+            Some(first_non_blank(buffer, (from.line + n).min(last_line)))
+        }
+        Motion::PreviousLineStart => {
+            if from.line == 0 {
+                return None;
+            }
+            Some(first_non_blank(buffer, from.line.saturating_sub(n)))
+        }
+        Motion::FirstLine => Some(first_non_blank(buffer, (n - 1).min(last_line))),
+        Motion::LastLine => {
+            let line = count.map(|c| c.max(1) - 1).unwrap_or(last_line).min(last_line);
+            Some(first_non_blank(buffer, line))
+        }
+
+        Motion::ParagraphForward => repeat(from, n, |p| paragraph(buffer, p, true)),
+        Motion::ParagraphBackward => repeat(from, n, |p| paragraph(buffer, p, false)),
+        Motion::SentenceForward => repeat(from, n, |p| sentence(buffer, p, true)),
+        Motion::SentenceBackward => repeat(from, n, |p| sentence(buffer, p, false)),
+
+        Motion::ScreenTop | Motion::ScreenMiddle | Motion::ScreenBottom => {
+            let bottom = (screen.top + screen.height).min(last_line + 1) - 1;
+            let top_margin = if screen.top == 0 { 0 } else { screen.scrolloff };
+            let bottom_margin = if bottom == last_line { 0 } else { screen.scrolloff };
+            let line = match motion {
+                Motion::ScreenTop => (screen.top + (n - 1).max(top_margin)).min(bottom),
+                Motion::ScreenBottom => bottom.saturating_sub((n - 1).max(bottom_margin)).max(screen.top),
+                _ => screen.top + (bottom - screen.top) / 2,
+            };
+            Some(first_non_blank(buffer, line))
+        }
+
+        Motion::MatchPair => match count {
+            // `50%` goes to the middle of the file
+            Some(percent) if percent <= 100 => {
+                let line = ((percent * (last_line + 1)).div_ceil(100)).max(1) - 1;
+                Some(first_non_blank(buffer, line))
+            }
+            Some(_) => None,
+            None => match_pair(buffer, from),
+        },
+
+        Motion::FindChar(c, find) => find_char(buffer, from, c, find, n),
+    };
+
+    target.map(|p| clamp(buffer, p))
+}
+
+fn repeat(from: Position, n: usize, step: impl Fn(Position) -> Option<Position>) -> Option<Position> {
+    let mut pos = from;
+    for _ in 0..n {
+        match step(pos) {
+            Some(p) => pos = p,
+            None => break,
+        }
+    }
+
+    if pos == from {
+        None
+    } else {
+        Some(pos)
+    }
+}
+
+// normal mode never rests on the end of a non-empty line
+fn clamp(buffer: &Buffer, pos: Position) -> Position {
+    let len = buffer.lines[pos.line].len();
+    if pos.col >= len {
+        return Position::new(pos.line, last_char(buffer, pos.line));
+    }
+    pos
+}
+
+// byte offset of the last character of `line`, 0 for an empty line
+pub fn last_char(buffer: &Buffer, line: usize) -> usize {
+    buffer.lines[line].char_indices().last().map(|(i, _)| i).unwrap_or(0)
+}
+
+pub fn first_non_blank(buffer: &Buffer, line: usize) -> Position {
+    let text = &buffer.lines[line];
+    let col = text.find(|c: char| !c.is_whitespace()).unwrap_or_else(|| last_char(buffer, line));
+    Position::new(line, col)
+}
+
+// character at `pos`, the end of a line reads as '\n'
+fn char_at(buffer: &Buffer, pos: Position) -> char {
+    buffer.lines[pos.line][pos.col..].chars().next().unwrap_or('\n')
+}
+
+fn next(buffer: &Buffer, pos: Position) -> Option<Position> {
+    let text = &buffer.lines[pos.line];
+    match text[pos.col..].chars().next() {
+        Some(c) => Some(Position::new(pos.line, pos.col + c.len_utf8())),
+        None if pos.line + 1 < buffer.get_file_lenght() => Some(Position::new(pos.line + 1, 0)),
+        None => None,
+    }
+}
+
+fn previous(buffer: &Buffer, pos: Position) -> Option<Position> {
+    if pos.col == 0 {
+        if pos.line == 0 {
+            return None;
+        }
+        let line = pos.line - 1;
+        return Some(Position::new(line, buffer.lines[line].len()));
+    }
+
+    let text = &buffer.lines[pos.line];
+    let c = text[..pos.col].chars().next_back()?;
+    Some(Position::new(pos.line, pos.col - c.len_utf8()))
+}
+
+fn is_empty_line(buffer: &Buffer, pos: Position) -> bool {
+    buffer.lines[pos.line].is_empty()
+}
+
+// 0 blank, 1 keyword, 2 punctuation; a WORD is anything that is not blank
+fn class(c: char, big: bool) -> u8 {
+    if c.is_whitespace() {
+        0
+    } else if big || c.is_alphanumeric() || c == '_' {
+        1
+    } else {
+        2
+    }
+}
+
+fn word_forward(buffer: &Buffer, from: Position, big: bool) -> Option<Position> {
+    let mut pos = from;
+    let start_class = class(char_at(buffer, pos), big);
+
+    if start_class != 0 {
+        while class(char_at(buffer, pos), big) == start_class {
+            match next(buffer, pos) {
+                Some(p) => pos = p,
+                // the last word of the buffer: stop on its end
+                None => return Some(pos),
+            }
+        }
+    }
+
+    // skip blanks and line breaks, an empty line counts as a word
+    while class(char_at(buffer, pos), big) == 0 {
+        if pos != from && is_empty_line(buffer, pos) {
+            break;
+        }
+        match next(buffer, pos) {
+            Some(p) => pos = p,
+            None => break,
+        }
+    }
+
+    Some(pos)
+}
+
+fn word_backward(buffer: &Buffer, from: Position, big: bool) -> Option<Position> {
+    let mut pos = previous(buffer, from)?;
+
+    while class(char_at(buffer, pos), big) == 0 {
+        if is_empty_line(buffer, pos) {
+            return Some(pos);
+        }
+        pos = previous(buffer, pos)?;
+    }
+
+    let word_class = class(char_at(buffer, pos), big);
+    while let Some(p) = previous(buffer, pos) {
+        if class(char_at(buffer, p), big) != word_class {
+            break;
+        }
+        pos = p;
+    }
+
+    Some(pos)
+}
+
+fn word_end(buffer: &Buffer, from: Position, big: bool) -> Option<Position> {
+    let mut pos = next(buffer, from)?;
+
+    while class(char_at(buffer, pos), big) == 0 {
+        pos = next(buffer, pos)?;
+    }
+
+    let word_class = class(char_at(buffer, pos), big);
+    while let Some(p) = next(buffer, pos) {
+        if class(char_at(buffer, p), big) != word_class {
+            break;
+        }
+        pos = p;
+    }
+
+    Some(pos)
+}
+
+fn word_end_backward(buffer: &Buffer, from: Position, big: bool) -> Option<Position> {
+    let mut pos = from;
+    let start_class = class(char_at(buffer, pos), big);
+
+    if start_class != 0 {
+        while class(char_at(buffer, pos), big) == start_class {
+            pos = previous(buffer, pos)?;
+        }
+    }
+
+    while class(char_at(buffer, pos), big) == 0 {
+        if pos != from && is_empty_line(buffer, pos) {
+            break;
+        }
+        pos = previous(buffer, pos)?;
+    }
+
+    Some(pos)
+}
+
+// `}` and `{`: the next (previous) empty line after a paragraph
+fn paragraph(buffer: &Buffer, from: Position, forward: bool) -> Option<Position> {
+    let last_line = buffer.get_file_lenght() - 1;
+    let blank = |line: usize| buffer.lines[line].trim().is_empty();
+    let step = |line: usize| if forward { (line < last_line).then(|| line + 1) } else { line.checked_sub(1) };
+
+    let mut line = from.line;
+    while blank(line) {
+        match step(line) {
+            Some(l) => line = l,
+            None => break,
+        }
+    }
+    while !blank(line) {
+        match step(line) {
+            Some(l) => line = l,
+            // no empty line left, go to the very end (start) of the buffer
+            None if forward => return Some(Position::new(line, buffer.lines[line].len())),
+            None => return Some(Position::new(line, 0)),
+        }
+    }
+
+    Some(Position::new(line, 0))
+}
+
+// `)` and `(`: the next (previous) sentence start
+fn sentence(buffer: &Buffer, from: Position, forward: bool) -> Option<Position> {
+    let mut pos = from;
+    loop {
+        pos = if forward { next(buffer, pos)? } else { previous(buffer, pos)? };
+        if is_sentence_start(buffer, pos) {
+            return Some(pos);
+        }
+    }
+}
+
+// a sentence starts after `.`, `!` or `?` followed by a blank, and after an empty line
+fn is_sentence_start(buffer: &Buffer, pos: Position) -> bool {
+    if is_empty_line(buffer, pos) {
+        return true;
+    }
+    if class(char_at(buffer, pos), false) == 0 {
+        return false;
+    }
+
+    let mut p = match previous(buffer, pos) {
+        Some(p) => p,
+        None => return true,
+    };
+
+    let mut blanks = false;
+    while class(char_at(buffer, p), false) == 0 {
+        if is_empty_line(buffer, p) {
+            return true;
+        }
+        blanks = true;
+        p = match previous(buffer, p) {
+            Some(p) => p,
+            None => return true,
+        };
+    }
+    if !blanks {
+        return false;
+    }
+
+    // closing characters may follow the punctuation: `end.)`
+    while matches!(char_at(buffer, p), ')' | ']' | '"' | '\'') {
+        p = match previous(buffer, p) {
+            Some(p) => p,
+            None => return false,
+        };
+    }
+
+    matches!(char_at(buffer, p), '.' | '!' | '?')
+}
+
+// `%`: the bracket under or after the cursor on this line, jumps to its partner
+fn match_pair(buffer: &Buffer, from: Position) -> Option<Position> {
+    const PAIRS: [(char, char); 3] = [('(', ')'), ('[', ']'), ('{', '}')];
+
+    let text = &buffer.lines[from.line];
+    let (offset, bracket) = text[from.col..]
+        .char_indices()
+        .find(|(_, c)| PAIRS.iter().any(|(open, close)| c == open || c == close))?;
+    let start = Position::new(from.line, from.col + offset);
+
+    let (open, close) = *PAIRS.iter().find(|(o, c)| bracket == *o || bracket == *c)?;
+    let forward = bracket == open;
+
+    let mut depth = 0;
+    let mut pos = start;
+    loop {
+        let c = char_at(buffer, pos);
+        if c == open {
+            depth += if forward { 1 } else { -1 };
+        } else if c == close {
+            depth += if forward { -1 } else { 1 };
+        }
+        if depth == 0 {
+            return Some(pos);
+        }
+
+        pos = if forward { next(buffer, pos)? } else { previous(buffer, pos)? };
+    }
+}
+
+fn find_char(buffer: &Buffer, from: Position, target: char, find: FindKind, n: usize) -> Option<Position> {
+    let chars: Vec<(usize, char)> = buffer.lines[from.line].char_indices().collect();
+    let mut index = chars.iter().position(|(i, _)| *i >= from.col).unwrap_or(chars.len());
+
+    // a repeated `t` stands right before its last match, so it looks one character further
+    if find.till && find.repeated {
+        index = if find.forward { index + 1 } else { index.checked_sub(1)? };
+    }
+
+    for _ in 0..n {
+        index = if find.forward {
+            (index + 1..chars.len()).find(|i| chars[*i].1 == target)?
+        } else {
+            (0..index).rev().find(|i| chars[*i].1 == target)?
+        };
+    }
+
+    if find.till {
+        index = if find.forward { index - 1 } else { index + 1 };
+    }
+
+    Some(Position::new(from.line, chars[index].0))
+}
@@ -0,0 +1,79 @@
+use crossterm::event::{DisableMouseCapture, EnableMouseCapture, MouseButton, MouseEvent, MouseEventKind};
+use crossterm::ExecutableCommand;
+
+use super::keys::parse_keys;
+use super::{Editor, Mode};
+
+// lines one turn of the wheel scrolls
+const WHEEL_LINES: usize = 3;
+
+impl Editor {
+    // turns mouse capture on or off when the `mouse` setting changed
+    pub(super) fn sync_mouse_capture(&mut self) -> anyhow::Result<()> {
+        if self.mouse != self.mouse_captured {
+            if self.mouse {
+                self.stdout.execute(EnableMouseCapture)?;
+            } else {
+                self.stdout.execute(DisableMouseCapture)?;
+            }
+            self.mouse_captured = self.mouse;
+        }
+
+        Ok(())
+    }
+
+    // clicks place the cursor, a drag selects text, the wheel scrolls and a click on
+    // the status bar goes to the module under it
+    pub(super) fn handle_mouse(&mut self, ev: MouseEvent) -> anyhow::Result<()> {
+        if !self.mouse {
+            return Ok(());
+        }
+
+        let in_text = (ev.row as usize)
+            .checked_sub(self.viewport_row())
+            .is_some_and(|row| row < self.viewport_height());
+        match ev.kind {
+            MouseEventKind::ScrollDown => self.scroll_lines(WHEEL_LINES, true),
+            MouseEventKind::ScrollUp => self.scroll_lines(WHEEL_LINES, false),
+
+            MouseEventKind::Down(MouseButton::Left) if self.enable_status_bar && ev.row == self.status_bar.row(self.size) => {
+                if let Some(keys) = self.status_bar.click(ev.column as usize) {
+                    self.feed_keys(&parse_keys(&keys))?;
+                }
+            }
+            MouseEventKind::Down(MouseButton::Left) if in_text => {
+                if matches!(self.mode, Mode::Command | Mode::Visual) {
+                    self.set_mode(Mode::Normal);
+                }
+                // what Replace mode overwrote can only be restored where it was typed
+                self.replaced.clear();
+                let pos = self.position_at(ev.column, ev.row);
+                self.goto_line(pos.line);
+                self.cursor_x = pos.col as u16;
+                self.preferred_col = None;
+                self.mouse_down = Some(pos);
+            }
+            MouseEventKind::Drag(MouseButton::Left) if in_text => {
+                let start = match self.mouse_down {
+                    Some(start) => start,
+                    None => return Ok(()),
+                };
+                if self.mode != Mode::Visual {
+                    self.set_mode(Mode::Normal);
+                    self.set_mode(Mode::Visual);
+                    self.visual_anchor = start;
+                }
+
+                let pos = self.position_at(ev.column, ev.row);
+                self.goto_line(pos.line);
+                self.cursor_x = pos.col as u16;
+            }
+            MouseEventKind::Up(MouseButton::Left) => {
+                self.mouse_down = None;
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+}
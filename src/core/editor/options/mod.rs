@@ -0,0 +1,199 @@
+// what kind of value an option holds
+#[derive(Clone, Copy, PartialEq)]
+pub enum Kind {
+    Bool,
+    Number,
+    Text,
+}
+
+// where the value of an option applies: everywhere, to the current buffer or to the window
+#[derive(Clone, Copy, PartialEq)]
+pub enum Scope {
+    Global,
+    Buffer,
+    Window,
+}
+
+// which values a change sets: `:set` sets both, `:setglobal` and `:setlocal` one of them
+#[derive(Clone, Copy, PartialEq)]
+pub enum Level {
+    Both,
+    Global,
+    Local,
+}
+
+#[derive(Clone, PartialEq)]
+pub enum Value {
+    Bool(bool),
+    Number(usize),
+    Text(String),
+}
+
+pub struct OptionDef {
+    pub name: &'static str,
+    pub short: &'static str,
+    pub kind: Kind,
+    pub scope: Scope,
+    default: &'static str,
+    // least value of a number option, and the values a text option may take (any when empty)
+    min: usize,
+    pub values: &'static [&'static str],
+}
+
+const fn option(name: &'static str, short: &'static str, kind: Kind, scope: Scope, default: &'static str) -> OptionDef {
+    OptionDef { name, short, kind, scope, default, min: 0, values: &[] }
+}
+
+// every option `:set` and the configuration file know, in the order they are listed
+pub const OPTIONS: &[OptionDef] = &[
+    option("scrolloff", "so", Kind::Number, Scope::Global, "1"),
+    option("sidescroll", "ss", Kind::Number, Scope::Global, "0"),
+    option("sidescrolloff", "siso", Kind::Number, Scope::Global, "0"),
+    option("wrap", "", Kind::Bool, Scope::Window, "false"),
+    option("linebreak", "lbr", Kind::Bool, Scope::Window, "false"),
+    option("showbreak", "sbr", Kind::Text, Scope::Window, ""),
+    option("number", "nu", Kind::Bool, Scope::Window, "true"),
+    option("mouse", "", Kind::Bool, Scope::Global, "true"),
+    OptionDef {
+        values: &["auto", "truecolor", "24bit", "256", "16", "8", "mono", "none"],
+        ..option("colors", "", Kind::Text, Scope::Global, "auto")
+    },
+    option("syntax", "syn", Kind::Bool, Scope::Global, "true"),
+    option("statusbar", "", Kind::Bool, Scope::Global, "true"),
+    OptionDef { values: &["bottom", "top"], ..option("statusbarside", "", Kind::Text, Scope::Global, "bottom") },
+    OptionDef { min: 1, ..option("tabstop", "ts", Kind::Number, Scope::Buffer, "4") },
+    option("shiftwidth", "sw", Kind::Number, Scope::Buffer, "4"),
+    option("softtabstop", "sts", Kind::Number, Scope::Buffer, "0"),
+    option("expandtab", "et", Kind::Bool, Scope::Buffer, "true"),
+    option("autoindent", "ai", Kind::Bool, Scope::Buffer, "true"),
+    option("smartindent", "si", Kind::Bool, Scope::Buffer, "true"),
+    option("autopairs", "ap", Kind::Bool, Scope::Global, "true"),
+    option("autoreload", "", Kind::Bool, Scope::Global, "true"),
+    option("leader", "", Kind::Text, Scope::Global, "\\"),
+    option("timeoutlen", "tm", Kind::Number, Scope::Global, "1000"),
+    OptionDef { min: 1, ..option("updatetime", "ut", Kind::Number, Scope::Global, "4000") },
+];
+
+// index of the option called `name`, by its full or short name
+pub fn find(name: &str) -> Option<usize> {
+    OPTIONS.iter().position(|o| o.name == name || (!o.short.is_empty() && o.short == name))
+}
+
+// the value `text` gives option `def`
+pub fn parse(def: &OptionDef, text: &str) -> Result<Value, String> {
+    match def.kind {
+        Kind::Bool => match text {
+            "true" => Ok(Value::Bool(true)),
+            "false" => Ok(Value::Bool(false)),
+            _ => Err(format!("expected true or false, got `{}`", text)),
+        },
+        Kind::Number => match text.parse() {
+            Ok(n) if n < def.min => Err(format!("{} must be at least {}", def.name, def.min)),
+            Ok(n) => Ok(Value::Number(n)),
+            Err(_) => Err(format!("expected a number, got `{}`", text)),
+        },
+        Kind::Text => {
+            if def.values.is_empty() || def.values.contains(&text) {
+                Ok(Value::Text(text.to_string()))
+            } else {
+                Err(format!("expected one of {}, got `{}`", def.values.join(", "), text))
+            }
+        }
+    }
+}
+
+impl Value {
+    // the value as the configuration file writes it
+    pub fn show(&self) -> String {
+        match self {
+            Value::Bool(b) => b.to_string(),
+            Value::Number(n) => n.to_string(),
+            Value::Text(s) => format!("\"{}\"", s),
+        }
+    }
+}
+
+// values of every option: the global ones, and the ones `:setlocal` gave the current
+// buffer and window, which hide the global value while they are set
+pub struct Options {
+    global: Vec<Value>,
+    local: Vec<Option<Value>>,
+}
+
+impl Options {
+    pub fn new() -> Self {
+        let global = (0..OPTIONS.len()).map(Self::default_value).collect();
+        Self { global, local: vec![None; OPTIONS.len()] }
+    }
+
+    // the value option `index` has for the current buffer and window
+    pub fn value(&self, index: usize) -> &Value {
+        self.local[index].as_ref().unwrap_or(&self.global[index])
+    }
+
+    pub fn global_value(&self, index: usize) -> &Value {
+        &self.global[index]
+    }
+
+    pub fn is_default(&self, index: usize) -> bool {
+        *self.value(index) == Self::default_value(index)
+    }
+
+    pub fn default_value(index: usize) -> Value {
+        let def = &OPTIONS[index];
+        parse(def, def.default).expect("bad default of an option")
+    }
+
+    pub fn set(&mut self, index: usize, value: Value, level: Level) {
+        // global options have a single value whatever the level
+        let local = OPTIONS[index].scope != Scope::Global;
+        match level {
+            Level::Both => {
+                if local {
+                    self.local[index] = Some(value.clone());
+                }
+                self.global[index] = value;
+            }
+            Level::Global => self.global[index] = value,
+            Level::Local if local => self.local[index] = Some(value),
+            Level::Local => self.global[index] = value,
+        }
+    }
+
+    // the values `:setlocal` gave the current buffer and window stay when the options are
+    // read again from the configuration
+    pub fn keep_local(&mut self, old: &Options) {
+        self.local.clone_from(&old.local);
+    }
+
+    // a new buffer starts with the global values of the buffer options
+    pub fn reset_buffer(&mut self) {
+        for (def, local) in OPTIONS.iter().zip(self.local.iter_mut()) {
+            if def.scope == Scope::Buffer {
+                *local = None;
+            }
+        }
+    }
+
+    fn get(&self, name: &str) -> &Value {
+        self.value(find(name).unwrap_or_else(|| panic!("unknown option {}", name)))
+    }
+
+    pub fn flag(&self, name: &str) -> bool {
+        matches!(self.get(name), Value::Bool(true))
+    }
+
+    pub fn number(&self, name: &str) -> usize {
+        match self.get(name) {
+            Value::Number(n) => *n,
+            _ => 0,
+        }
+    }
+
+    pub fn text(&self, name: &str) -> &str {
+        match self.get(name) {
+            Value::Text(s) => s,
+            _ => "",
+        }
+    }
+}
@@ -0,0 +1,105 @@
+use std::collections::HashMap;
+
+use super::config::AutoPairsSettings;
+
+// pairs used for file types without a list of their own
+const DEFAULT_PAIRS: &str = "() [] {} '' \"\"";
+
+// what typing a character does with auto-pairs
+pub enum Typed {
+    // insert the character and its closing character, the cursor goes between them
+    Pair(char, char),
+    // the closing character is already under the cursor, move over it
+    SkipOver,
+    // insert the character alone
+    Plain,
+}
+
+// pairs of characters that are inserted together, a list per file type, `enabled` is
+// the `autopairs` option
+pub struct AutoPairs {
+    pub enabled: bool,
+    default: Vec<(char, char)>,
+    by_filetype: HashMap<String, Vec<(char, char)>>,
+}
+
+// "() [] {}" into its pairs, words that are not two characters long are ignored
+pub fn parse_pairs(list: &str) -> Vec<(char, char)> {
+    list.split_whitespace()
+        .filter_map(|pair| {
+            let mut chars = pair.chars();
+            match (chars.next(), chars.next(), chars.next()) {
+                (Some(open), Some(close), None) => Some((open, close)),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+impl AutoPairs {
+    pub fn new(settings: &AutoPairsSettings) -> Self {
+        let mut by_filetype: HashMap<String, Vec<(char, char)>> = HashMap::new();
+        // Rust uses `'` for lifetimes far more often than for characters
+        by_filetype.insert("rust".to_string(), parse_pairs("() [] {} \"\""));
+        for (filetype, list) in &settings.by_filetype {
+            by_filetype.insert(filetype.clone(), parse_pairs(list));
+        }
+
+        Self {
+            enabled: true,
+            default: parse_pairs(settings.default.as_deref().unwrap_or(DEFAULT_PAIRS)),
+            by_filetype,
+        }
+    }
+
+    fn pairs(&self, filetype: &str) -> &[(char, char)] {
+        self.by_filetype.get(filetype).unwrap_or(&self.default)
+    }
+
+    // what typing `c` at byte `col` of `text` does
+    pub fn on_type(&self, filetype: &str, text: &str, col: usize, c: char) -> Typed {
+        if !self.enabled {
+            return Typed::Plain;
+        }
+        let pairs = self.pairs(filetype);
+        let before = text[..col].chars().next_back();
+        let after = text[col..].chars().next();
+
+        if after == Some(c) && pairs.iter().any(|(_, close)| *close == c) {
+            return Typed::SkipOver;
+        }
+
+        let close = match pairs.iter().find(|(open, _)| *open == c) {
+            Some((_, close)) => *close,
+            None => return Typed::Plain,
+        };
+        // nothing is paired right in front of a word
+        if after.is_some_and(is_word_char) {
+            return Typed::Plain;
+        }
+        // a quote after a letter is an apostrophe, as in "don't"
+        if c == close && before.is_some_and(is_word_char) {
+            return Typed::Plain;
+        }
+
+        Typed::Pair(c, close)
+    }
+
+    // whether Backspace at byte `col` of `text` deletes an empty pair, the cursor being
+    // between its two characters
+    pub fn on_backspace(&self, filetype: &str, text: &str, col: usize) -> bool {
+        if !self.enabled {
+            return false;
+        }
+        let before = text[..col].chars().next_back();
+        let after = text[col..].chars().next();
+
+        self.pairs(filetype)
+            .iter()
+            .any(|(open, close)| before == Some(*open) && after == Some(*close))
+    }
+}
@@ -0,0 +1,343 @@
+use std::cell::RefCell;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::rc::Rc;
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
+use std::time::{Duration, Instant};
+
+use serde_json::{json, Value};
+
+// JSON-RPC error codes
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+// how long a plugin gets to exit by itself after `shutdown`
+const SHUTDOWN_WAIT: Duration = Duration::from_millis(500);
+
+// what plugins can see and change in the editor
+pub trait PluginApi {
+    fn file(&self) -> Option<String>;
+    fn lines(&self) -> &[String];
+    // replaces the lines `start..end` by `lines`
+    fn set_lines(&mut self, start: usize, end: usize, lines: Vec<String>) -> Result<(), String>;
+    fn show_message(&mut self, text: String);
+}
+
+// an Ex command a plugin registered, running it sends `command` to the plugin
+pub struct PluginCommand {
+    pub name: String,
+    pub help: String,
+    plugin: usize,
+}
+
+// text of a status bar module a plugin registered, shared with the module in the bar
+pub type ModuleText = Rc<RefCell<String>>;
+
+struct Plugin {
+    // the command line it was started with
+    command: String,
+    child: Child,
+    stdin: Option<ChildStdin>,
+    // events it subscribed to
+    events: Vec<String>,
+    running: bool,
+}
+
+// what the reader thread of a plugin passes on
+enum Incoming {
+    Message(Value),
+    Exited,
+}
+
+// starts plugin executables and talks JSON-RPC 2.0 with them, one message per line on
+// their standard input and output
+pub struct PluginHost {
+    plugins: Vec<Plugin>,
+    sender: Sender<(usize, Incoming)>,
+    receiver: Receiver<(usize, Incoming)>,
+    next_id: u64,
+    pub commands: Vec<PluginCommand>,
+    pub modules: Vec<(String, ModuleText)>,
+}
+
+impl Default for PluginHost {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PluginHost {
+    pub fn new() -> Self {
+        let (sender, receiver) = channel();
+        Self { plugins: Vec::new(), sender, receiver, next_id: 1, commands: Vec::new(), modules: Vec::new() }
+    }
+
+    // starts `program` with `args` and sends it `initialize` with `params`
+    pub fn start(&mut self, program: &str, args: &[String], params: Value) -> anyhow::Result<()> {
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| anyhow::anyhow!("{}: {}", program, e))?;
+
+        let index = self.plugins.len();
+        let stdout = child.stdout.take().unwrap();
+        let sender = self.sender.clone();
+        std::thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let line = match line {
+                    Ok(line) => line,
+                    Err(_) => break,
+                };
+                // anything that is not a message, like a stray print, is left out
+                if let Ok(message) = serde_json::from_str(&line) {
+                    if sender.send((index, Incoming::Message(message))).is_err() {
+                        return;
+                    }
+                }
+            }
+            let _ = sender.send((index, Incoming::Exited));
+        });
+
+        let command = std::iter::once(program).chain(args.iter().map(String::as_str)).collect::<Vec<_>>().join(" ");
+        self.plugins.push(Plugin { command, stdin: child.stdin.take(), child, events: Vec::new(), running: true });
+        self.request(index, "initialize", params);
+
+        Ok(())
+    }
+
+    // whether a plugin with this command line runs already
+    pub fn is_started(&self, command: &str) -> bool {
+        self.plugins.iter().any(|p| p.running && p.command == command)
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.plugins.iter().any(|p| p.running)
+    }
+
+    // the command line of every plugin started and whether it still runs
+    pub fn list(&self) -> Vec<(String, bool)> {
+        self.plugins.iter().map(|p| (p.command.clone(), p.running)).collect()
+    }
+
+    fn send(&mut self, index: usize, message: Value) {
+        let plugin = &mut self.plugins[index];
+        let sent = match &mut plugin.stdin {
+            Some(stdin) => writeln!(stdin, "{}", message).and_then(|_| stdin.flush()).is_ok(),
+            None => false,
+        };
+        // the reader thread tells when it is gone for good
+        if !sent {
+            plugin.stdin = None;
+        }
+    }
+
+    fn request(&mut self, index: usize, method: &str, params: Value) {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.send(index, json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }));
+    }
+
+    fn notify(&mut self, index: usize, method: &str, params: Value) {
+        self.send(index, json!({ "jsonrpc": "2.0", "method": method, "params": params }));
+    }
+
+    // sends `event` to the plugins that subscribed to it, `params` is an object the
+    // event name is added to
+    pub fn emit(&mut self, event: &str, mut params: Value) {
+        params["event"] = json!(event);
+        for index in 0..self.plugins.len() {
+            let plugin = &self.plugins[index];
+            if plugin.running && plugin.events.iter().any(|e| e == event) {
+                self.notify(index, "event", params.clone());
+            }
+        }
+    }
+
+    // runs the plugin command `name`, false when no plugin registered it
+    pub fn run_command(&mut self, name: &str, args: &str, bang: bool, range: Option<(usize, usize)>) -> bool {
+        let plugin = match self.commands.iter().find(|c| c.name == name) {
+            Some(command) => command.plugin,
+            None => return false,
+        };
+        let params = json!({ "name": name, "args": args, "bang": bang, "range": range });
+        self.notify(plugin, "command", params);
+        true
+    }
+
+    // handles what the plugins sent, waiting up to `wait` for the first message; returns
+    // whether anything came
+    pub fn handle_messages(&mut self, api: &mut dyn PluginApi, wait: Duration) -> bool {
+        let first = match self.receiver.recv_timeout(wait) {
+            Ok(incoming) => incoming,
+            Err(RecvTimeoutError::Timeout) | Err(RecvTimeoutError::Disconnected) => return false,
+        };
+
+        self.handle(first, api);
+        while let Ok(incoming) = self.receiver.try_recv() {
+            self.handle(incoming, api);
+        }
+        true
+    }
+
+    fn handle(&mut self, (index, incoming): (usize, Incoming), api: &mut dyn PluginApi) {
+        let message = match incoming {
+            Incoming::Message(message) => message,
+            Incoming::Exited => {
+                let plugin = &mut self.plugins[index];
+                plugin.running = false;
+                plugin.stdin = None;
+                let _ = plugin.child.wait();
+                self.commands.retain(|c| c.plugin != index);
+                api.show_message(format!("Plugin {} exited", self.plugins[index].command));
+                return;
+            }
+        };
+
+        let method = match message["method"].as_str() {
+            Some(method) => method.to_string(),
+            // the answer to one of our requests, only errors matter
+            None => {
+                if let Some(error) = message["error"]["message"].as_str() {
+                    api.show_message(format!("{}: {}", self.plugins[index].command, error));
+                }
+                return;
+            }
+        };
+
+        let result = self.call(index, &method, &message["params"], api);
+        let id = &message["id"];
+        if id.is_null() {
+            if let Err((_, error)) = result {
+                api.show_message(format!("{}: {}", self.plugins[index].command, error));
+            }
+            return;
+        }
+        let response = match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err((code, error)) => json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": error } }),
+        };
+        self.send(index, response);
+    }
+
+    // one method a plugin called
+    fn call(&mut self, index: usize, method: &str, params: &Value, api: &mut dyn PluginApi) -> Result<Value, (i64, String)> {
+        let text = |key: &str| -> Result<String, (i64, String)> {
+            match params[key].as_str() {
+                Some(text) => Ok(text.to_string()),
+                None => Err((INVALID_PARAMS, format!("`{}` must be a string", key))),
+            }
+        };
+        let line = |key: &str, default: usize| -> Result<usize, (i64, String)> {
+            match &params[key] {
+                Value::Null => Ok(default),
+                value => match value.as_u64() {
+                    Some(n) => Ok(n as usize),
+                    None => Err((INVALID_PARAMS, format!("`{}` must be a line number", key))),
+                },
+            }
+        };
+
+        match method {
+            "registerCommand" => {
+                let name = text("name")?;
+                // the same names `:command` takes, the command line ends a name at anything
+                // that is not a letter
+                if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphabetic()) {
+                    return Err((INVALID_PARAMS, format!("E182: Invalid command name: {}", name)));
+                }
+                if !name.starts_with(|c: char| c.is_ascii_uppercase()) {
+                    return Err((INVALID_PARAMS, "E183: User defined commands must start with an uppercase letter".to_string()));
+                }
+                let help = params["help"].as_str().unwrap_or("").to_string();
+                self.commands.retain(|c| c.name != name);
+                self.commands.push(PluginCommand { name, help, plugin: index });
+                Ok(Value::Null)
+            }
+            "registerModule" => {
+                let name = text("name")?;
+                let initial = params["text"].as_str().unwrap_or("").to_string();
+                match self.modules.iter().find(|(n, _)| *n == name) {
+                    Some((_, text)) => *text.borrow_mut() = initial,
+                    None => self.modules.push((name, Rc::new(RefCell::new(initial)))),
+                }
+                Ok(Value::Null)
+            }
+            "setModuleText" => {
+                let name = text("name")?;
+                match self.modules.iter().find(|(n, _)| *n == name) {
+                    Some((_, module)) => *module.borrow_mut() = text("text")?,
+                    None => return Err((INVALID_PARAMS, format!("no module named {}", name))),
+                }
+                Ok(Value::Null)
+            }
+            "subscribe" => {
+                let events = match params["events"].as_array() {
+                    Some(events) => events,
+                    None => return Err((INVALID_PARAMS, "`events` must be a list of event names".to_string())),
+                };
+                let plugin = &mut self.plugins[index];
+                for event in events.iter().filter_map(Value::as_str) {
+                    if !plugin.events.iter().any(|e| e == event) {
+                        plugin.events.push(event.to_string());
+                    }
+                }
+                Ok(Value::Null)
+            }
+            "getText" => {
+                let lines = api.lines();
+                let end = line("end", lines.len())?.min(lines.len());
+                let start = line("start", 0)?.min(end);
+                Ok(json!({ "file": api.file(), "lineCount": lines.len(), "lines": &lines[start..end] }))
+            }
+            "setText" => {
+                let count = api.lines().len();
+                let end = line("end", count)?;
+                let start = line("start", 0)?;
+                if start > end || end > count {
+                    return Err((INVALID_PARAMS, format!("invalid line range {}..{}", start, end)));
+                }
+                let lines = match params["lines"].as_array() {
+                    Some(lines) => lines.iter().map(|l| l.as_str().unwrap_or("").to_string()).collect(),
+                    None => return Err((INVALID_PARAMS, "`lines` must be a list of strings".to_string())),
+                };
+                api.set_lines(start, end, lines).map_err(|e| (INVALID_PARAMS, e))?;
+                Ok(Value::Null)
+            }
+            "showMessage" => {
+                api.show_message(text("text")?);
+                Ok(Value::Null)
+            }
+            _ => Err((METHOD_NOT_FOUND, format!("unknown method {}", method))),
+        }
+    }
+
+    // sends `shutdown` to every plugin and ends the ones that do not exit in time
+    pub fn stop_all(&mut self) {
+        for index in 0..self.plugins.len() {
+            if self.plugins[index].running {
+                self.notify(index, "shutdown", json!({}));
+                // a closed input tells the plugin the same
+                self.plugins[index].stdin = None;
+            }
+        }
+
+        let deadline = Instant::now() + SHUTDOWN_WAIT;
+        for plugin in self.plugins.iter_mut().filter(|p| p.running) {
+            while matches!(plugin.child.try_wait(), Ok(None)) && Instant::now() < deadline {
+                std::thread::sleep(Duration::from_millis(10));
+            }
+            let _ = plugin.child.kill();
+            let _ = plugin.child.wait();
+            plugin.running = false;
+        }
+    }
+}
+
+impl Drop for PluginHost {
+    fn drop(&mut self) {
+        self.stop_all();
+    }
+}
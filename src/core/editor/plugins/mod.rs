@@ -0,0 +1,121 @@
+use std::time::Duration;
+
+use serde_json::json;
+
+use super::super::buffer::Buffer;
+use super::ex::commands::split_words;
+use super::modules::plugin_module::PluginModule;
+use super::{Editor, Mode};
+use host::PluginApi;
+
+pub mod host;
+
+// the editor as plugins see it while their messages are handled
+struct EditorApi<'a> {
+    buffer: &'a mut Buffer,
+    message: &'a mut Option<String>,
+    cursor: (usize, usize),
+}
+
+impl PluginApi for EditorApi<'_> {
+    fn file(&self) -> Option<String> {
+        self.buffer.file.clone()
+    }
+
+    fn lines(&self) -> &[String] {
+        &self.buffer.lines
+    }
+
+    fn set_lines(&mut self, start: usize, end: usize, lines: Vec<String>) -> Result<(), String> {
+        let count = self.buffer.lines.len();
+        let inserted = !lines.is_empty();
+
+        // every change of a plugin is one undo step
+        self.buffer.begin_change(self.cursor);
+        if start < end {
+            self.buffer.remove_lines(start, end - 1);
+        }
+        self.buffer.insert_lines(start, lines);
+        // removing every line leaves an empty one behind
+        if start == 0 && end == count && inserted {
+            self.buffer.lines.pop();
+        }
+        self.buffer.end_change();
+
+        Ok(())
+    }
+
+    fn show_message(&mut self, text: String) {
+        *self.message = Some(text);
+    }
+}
+
+// names of the modes in `ModeChanged` events
+pub(super) fn mode_name(mode: Mode) -> &'static str {
+    match mode {
+        Mode::Normal => "normal",
+        Mode::Insert => "insert",
+        Mode::Command => "command",
+        Mode::Replace => "replace",
+        Mode::Visual => "visual",
+    }
+}
+
+impl Editor {
+    // `:plugin {executable} [args]` starts a plugin unless it runs already, `:plugin`
+    // lists them
+    pub(super) fn ex_plugin(&mut self, args: &str) -> anyhow::Result<()> {
+        let words = split_words(args)?;
+        let (program, args) = match words.split_first() {
+            Some(words) => words,
+            None => {
+                let lines: Vec<String> = self
+                    .plugins
+                    .list()
+                    .into_iter()
+                    .map(|(command, running)| format!("{:<10} {}", if running { "running" } else { "exited" }, command))
+                    .collect();
+                match lines.len() {
+                    0 => self.command_bar.message = Some("No plugins".to_string()),
+                    1 => self.command_bar.message = lines.into_iter().next(),
+                    _ => self.command_bar.show_output(lines),
+                }
+                return Ok(());
+            }
+        };
+
+        let program = match (program.strip_prefix("~/"), std::env::var("HOME")) {
+            (Some(rest), Ok(home)) => format!("{}/{}", home, rest),
+            _ => program.clone(),
+        };
+        let command = std::iter::once(&program).chain(args).cloned().collect::<Vec<_>>().join(" ");
+        if self.plugins.is_started(&command) {
+            return Ok(());
+        }
+
+        let params = json!({ "version": env!("CARGO_PKG_VERSION"), "file": self.buffer.file });
+        self.plugins.start(&program, args, params)
+    }
+
+    // handles the messages plugins sent meanwhile, true when there were any
+    pub(super) fn handle_plugin_messages(&mut self) -> bool {
+        let cursor = self.cursor_position();
+        let mut api = EditorApi { buffer: &mut self.buffer, message: &mut self.command_bar.message, cursor };
+        if !self.plugins.handle_messages(&mut api, Duration::ZERO) {
+            return false;
+        }
+
+        // the lines under the cursor may be gone
+        self.restore_cursor(cursor);
+        self.sync_plugin_modules();
+        true
+    }
+
+    // puts the modules plugins registered into the status bar, again after it was made anew
+    pub(super) fn sync_plugin_modules(&mut self) {
+        let added = self.status_bar.plugin_modules();
+        for (_, text) in self.plugins.modules.iter().skip(added) {
+            self.status_bar.add_module(Box::new(PluginModule::new(text.clone())));
+        }
+    }
+}
@@ -0,0 +1,186 @@
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+use crossterm::event::{self, read, Event};
+use serde_json::json;
+
+use super::super::syntax::Syntax;
+use super::config::Config;
+use super::indent::Indent;
+use super::pairs::AutoPairs;
+use super::status_bar::StatusBar;
+use super::theme::Rgb;
+use super::{error_message, Editor, Mode};
+
+// how long the editor waits for a key before it looks at the configuration files again
+const WATCH_INTERVAL: Duration = Duration::from_millis(500);
+// and while plugins run, how long before it looks for their messages
+const PLUGIN_INTERVAL: Duration = Duration::from_millis(20);
+
+// modification times of the configuration files, a file that does not exist yet is
+// watched for being created
+pub struct ConfigWatch {
+    files: Vec<(PathBuf, Option<SystemTime>)>,
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+impl ConfigWatch {
+    pub fn new(files: Vec<PathBuf>) -> Self {
+        Self {
+            files: files.into_iter().map(|path| {
+                let time = modified(&path);
+                (path, time)
+            }).collect(),
+        }
+    }
+
+    // true once after any of the files was written, created or deleted
+    pub fn changed(&mut self) -> bool {
+        let mut changed = false;
+        for (path, time) in self.files.iter_mut() {
+            let now = modified(path);
+            if now != *time {
+                *time = now;
+                changed = true;
+            }
+        }
+        changed
+    }
+}
+
+// colours of the configuration file that are put over the ones of the theme
+fn color_overrides(config: &Config) -> Vec<(&'static str, Rgb)> {
+    [
+        ("text", config.editor.get_info_color()),
+        ("status_bar_background", config.status_bar.get_info_backcolor()),
+        ("command_bar_background", config.command_bar.get_info_backcolor()),
+        ("command_bar_text", config.command_bar.get_info_color()),
+    ]
+    .into_iter()
+    .filter_map(|(element, rgb)| Some((element, rgb?)))
+    .collect()
+}
+
+impl Editor {
+    // waits for the next terminal event, the configuration is read again when one of its
+    // files changes meanwhile and `None` is returned to draw the result, the same goes for
+    // messages of plugins and for `CursorHold`
+    pub(super) fn next_event(&mut self) -> anyhow::Result<Option<Event>> {
+        loop {
+            if self.handle_plugin_messages() {
+                return Ok(None);
+            }
+
+            // keys that start a mapping wait `timeoutlen` milliseconds for the rest of it
+            let mut wait = if self.keymap.is_pending() {
+                Duration::from_millis(self.config.options.number("timeoutlen") as u64)
+            } else if self.plugins.is_running() {
+                PLUGIN_INTERVAL
+            } else {
+                WATCH_INTERVAL
+            };
+            // `CursorHold` fires once the cursor stayed put in Normal mode for `updatetime`
+            // milliseconds
+            let hold = !self.held && self.mode == Mode::Normal;
+            let hold_at = self.idle_since + Duration::from_millis(self.config.options.number("updatetime") as u64);
+            if hold {
+                wait = wait.min(hold_at.saturating_duration_since(Instant::now()));
+            }
+            if event::poll(wait)? {
+                self.idle_since = Instant::now();
+                self.held = false;
+                return Ok(Some(read()?));
+            }
+            if hold && Instant::now() >= hold_at {
+                self.held = true;
+                self.emit_event("CursorHold", json!({}));
+                return Ok(None);
+            }
+            if self.keymap.is_pending() {
+                self.keys_timed_out()?;
+                return Ok(None);
+            }
+            if self.config.options.flag("autoreload") && self.config_watch.changed() {
+                self.reload_config()?;
+                return Ok(None);
+            }
+        }
+    }
+
+    // `:source` without a file reads every layer of the configuration again, options
+    // changed with `:set` that the files do not set keep their values
+    pub(super) fn reload_config(&mut self) -> anyhow::Result<()> {
+        let (mut config, mut errors) = Config::load(self.buffer.file.as_deref(), &self.config_options);
+        config.keep_set_options(&self.config);
+        self.config = config;
+        errors.extend(self.apply_config()?);
+
+        self.command_bar.message = error_message(errors).or_else(|| Some("Configuration reloaded".to_string()));
+        Ok(())
+    }
+
+    // `:source {file}` reads one more file over the configuration
+    pub(super) fn source(&mut self, path: &str) -> anyhow::Result<()> {
+        let mut errors = self.config.apply_file(Path::new(path), true);
+        errors.extend(self.apply_config()?);
+
+        self.command_bar.message = error_message(errors).or_else(|| Some(format!("Sourced {}", path)));
+        Ok(())
+    }
+
+    // builds again everything that comes from the configuration: syntax definitions,
+    // theme, status bar, pairs, indentation, command names, options, mappings, user
+    // commands and autocommands; returns the problems found in syntax and theme files and
+    // in those lines
+    pub(super) fn apply_config(&mut self) -> anyhow::Result<Vec<String>> {
+        let config_dir = self.config_options.config_dir();
+
+        let syntax_dir = config_dir.as_ref().map(|d| d.join("syntax"));
+        let (syntax, mut errors) = Syntax::load(self.config.options.flag("syntax"), syntax_dir.as_deref());
+        self.syntax = syntax;
+
+        self.color_overrides = color_overrides(&self.config);
+        let theme = self.config.editor.theme.clone();
+        if let Err(e) = self.set_theme(&theme) {
+            errors.push(e.to_string());
+            // the theme stays, the colours of the configuration still go over it
+            for (element, rgb) in &self.color_overrides {
+                self.theme.set(element, *rgb);
+            }
+        }
+
+        self.status_bar = StatusBar::new(&self.config.status_bar);
+        self.sync_plugin_modules();
+        self.auto_pairs = AutoPairs::new(&self.config.auto_pairs);
+        self.indent = Indent::new(&self.config.indent);
+
+        let commands = &self.config.commands;
+        self.qiut = commands.get_info_quit().unwrap();
+        self.save = commands.get_info_save().unwrap();
+        self.edit = commands.get_info_edit().unwrap();
+
+        self.apply_options();
+
+        // mappings use the `leader` of the options just applied
+        self.keymap.clear_config();
+        self.user_commands.clear_config();
+        self.autocmds.clear_config();
+        for (origin, command, args) in self.config.ex_lines.clone() {
+            if let Err(e) = self.run_config_line(&command, &args) {
+                errors.push(format!("{}: {}", origin, e));
+            }
+        }
+
+        // the file of the theme in use is watched too
+        let mut files = self.config.files.clone();
+        if let Some(dir) = &config_dir {
+            files.push(dir.join("themes").join(format!("{}.theme", self.theme.name)));
+        }
+        self.config_watch = ConfigWatch::new(files);
+
+        Ok(errors)
+    }
+}
@@ -0,0 +1,275 @@
+use crossterm::{terminal, QueueableCommand};
+
+use super::{motion, Editor, Mode};
+
+// where `zt`, `zz` and `zb` put the cursor line
+#[derive(Clone, Copy)]
+pub enum Align {
+    Top,
+    Middle,
+    Bottom,
+}
+
+// all viewport bookkeeping lives here: `viewport_top` is the first buffer line on screen and
+// `cursor_y` counts the lines from it to the cursor, so the cursor line is always
+// `viewport_top + cursor_y`; `viewport_left` is the first screen column shown without `wrap`.
+// Where lines end up on screen is up to the layout module
+impl Editor {
+    // `scrolloff` shrunk to fit the window, a huge value keeps the cursor line in the middle
+    pub(super) fn scrolloff(&self) -> usize {
+        self.scrolloff.min(self.viewport_height().saturating_sub(1) / 2)
+    }
+
+    fn last_line(&self) -> usize {
+        self.buffer.get_file_lenght() - 1
+    }
+
+    // sets the first line on screen and the cursor line together
+    fn set_view(&mut self, top: usize, line: usize) {
+        let line = line.min(self.last_line());
+        let top = top.min(line);
+        self.viewport_top = top as u16;
+        self.cursor_y = (line - top) as u16;
+    }
+
+    // screen rows lines `first..=last` take
+    fn rows_between(&self, first: usize, last: usize) -> usize {
+        (first..=last).map(|line| self.line_height(line)).sum()
+    }
+
+    // first line of a window that shows at most `rows` screen rows above line `line`
+    fn top_with_rows_above(&self, line: usize, rows: usize) -> usize {
+        let mut top = line;
+        let mut used = 0;
+        while top > 0 && used + self.line_height(top - 1) <= rows {
+            used += self.line_height(top - 1);
+            top -= 1;
+        }
+        top
+    }
+
+    // puts the cursor on buffer line `line` and scrolls as little as possible to keep it
+    // `scrolloff` lines away from the edges, lines far off screen end up in the middle
+    pub(super) fn scroll_to_line(&mut self, line: usize) {
+        let line = line.min(self.last_line());
+        let height = self.viewport_height();
+        let so = self.scrolloff();
+        let mut top = self.viewport_top as usize;
+
+        if line + height / 2 < top || line >= top + height + height / 2 {
+            // without showing rows past the end of the file
+            let last = self.last_line();
+            let max_top = self.top_with_rows_above(last, height.saturating_sub(self.line_height(last)));
+            top = self.top_with_rows_above(line, height.saturating_sub(self.line_height(line)) / 2).min(max_top);
+        }
+        // near the end of the file there is nothing to keep below the cursor
+        let bottom = (line + so).min(self.last_line());
+        while top < line && self.rows_between(top, bottom) > height {
+            top += 1;
+        }
+        top = top.min(line.saturating_sub(so));
+
+        self.set_view(top, line);
+    }
+
+    // puts the cursor on buffer line `line`, keeping the column inside the line
+    pub(super) fn goto_line(&mut self, line: usize) {
+        self.scroll_to_line(line);
+
+        let len = self.buffer.lines[self.current_line()].len() as u16;
+        self.cursor_x = self.cursor_x.min(len);
+    }
+
+    // the terminal changed size: the next draw lays everything out again, the cursor line
+    // only has to be brought back into the window
+    pub(super) fn resize(&mut self, width: u16, height: u16) -> anyhow::Result<()> {
+        self.size = (width, height);
+        if self.terminal {
+            self.stdout.queue(terminal::Clear(terminal::ClearType::All))?;
+        }
+        self.scroll_to_line(self.current_line());
+
+        Ok(())
+    }
+
+    // moves the cursor back inside the window after the window itself was scrolled
+    fn keep_cursor_on_screen(&mut self, top: usize, line: usize) {
+        let so = self.scrolloff();
+
+        let lowest = if top == 0 { 0 } else { top + so };
+        let last_visible = self.last_visible_line(top);
+        let highest = if last_visible >= self.last_line() {
+            last_visible
+        } else {
+            last_visible.saturating_sub(so).max(top)
+        };
+        self.set_view(top, line.max(lowest).min(highest));
+        self.clamp_cursor_x();
+    }
+
+    fn clamp_cursor_x(&mut self) {
+        let len = self.buffer.lines[self.current_line()].len() as u16;
+        self.cursor_x = self.cursor_x.min(len);
+    }
+
+    // `Ctrl-E` / `Ctrl-Y`: scrolls the window `count` lines, the cursor stays on its line
+    // as long as it is visible
+    pub(super) fn scroll_lines(&mut self, count: usize, down: bool) {
+        let top = self.viewport_top as usize;
+        let top = if down {
+            (top + count).min(self.last_line())
+        } else {
+            top.saturating_sub(count)
+        };
+
+        self.keep_cursor_on_screen(top, self.current_line());
+    }
+
+    // `Ctrl-D` / `Ctrl-U`: scrolls the window and the cursor by half a window, a count
+    // changes the amount for the following scrolls too
+    pub(super) fn scroll_half_page(&mut self, down: bool) {
+        if self.typed_count > 0 {
+            self.scroll = self.typed_count;
+        }
+        let amount = match self.scroll {
+            0 => (self.viewport_height() / 2).max(1),
+            n => n,
+        };
+
+        let top = self.viewport_top as usize;
+        let line = self.current_line();
+        let max_top = self.buffer.get_file_lenght().saturating_sub(self.viewport_height());
+        let (top, line) = if down {
+            ((top + amount).min(max_top.max(top)), line + amount)
+        } else {
+            (top.saturating_sub(amount), line.saturating_sub(amount))
+        };
+
+        self.keep_cursor_on_screen(top, line);
+    }
+
+    // `Ctrl-F` / `Ctrl-B`: scrolls `count` windows, keeping two lines of the old one visible
+    pub(super) fn scroll_page(&mut self, count: usize, down: bool) {
+        let amount = self.viewport_height().saturating_sub(2).max(1) * count;
+        let top = self.viewport_top as usize;
+        let top = if down {
+            (top + amount).min(self.last_line())
+        } else {
+            top.saturating_sub(amount)
+        };
+
+        self.keep_cursor_on_screen(top, self.current_line());
+    }
+
+    // `zt`, `zz`, `zb`: scrolls so the cursor line (or line `count`) is at the top,
+    // in the middle or at the bottom of the window
+    pub(super) fn align_cursor(&mut self, align: Align) {
+        let line = match self.typed_count {
+            0 => self.current_line(),
+            n => (n - 1).min(self.last_line()),
+        };
+        let height = self.viewport_height();
+        let so = self.scrolloff();
+
+        let rows = self.line_height(line);
+        let top = match align {
+            Align::Top => line.saturating_sub(so),
+            Align::Middle => self.top_with_rows_above(line, height.saturating_sub(rows) / 2),
+            Align::Bottom => {
+                let below = (line + 1..=(line + so).min(self.last_line())).map(|l| self.line_height(l)).sum::<usize>();
+                self.top_with_rows_above(line, height.saturating_sub(rows + below))
+            }
+        };
+
+        self.set_view(top, line);
+        self.clamp_cursor_x();
+    }
+
+    // screen column of the cursor inside its line
+    pub(super) fn cursor_col(&self) -> usize {
+        self.tabs.display_col(&self.buffer.lines[self.current_line()], self.cursor_x as usize)
+    }
+
+    // `sidescrolloff` shrunk to fit the window, at least one column is kept next to the
+    // cursor so it never sits under a `<` or `>` marker
+    fn sidescrolloff(&self) -> usize {
+        let width = self.text_width();
+        self.sidescrolloff.max(1).min(width.saturating_sub(1) / 2)
+    }
+
+    // keeps the cursor on a character of its line (or right after it while typing)
+    // and scrolls the window sideways until the cursor is visible
+    pub(super) fn fit_cursor_in_view(&mut self) {
+        if self.mode == Mode::Command {
+            return;
+        }
+        // a resize or a `:set` can leave the cursor line below the window
+        let line = self.current_line();
+        if line > self.last_visible_line(self.viewport_top as usize) {
+            self.scroll_to_line(line);
+        }
+
+        let line = self.current_line();
+        let text = &self.buffer.lines[line];
+        let mut x = (self.cursor_x as usize).min(text.len());
+        if !self.is_editing() {
+            x = x.min(motion::last_char(&self.buffer, line));
+        }
+        while !text.is_char_boundary(x) {
+            x -= 1;
+        }
+        self.cursor_x = x as u16;
+
+        if self.wrap {
+            self.viewport_left = 0;
+            return;
+        }
+
+        let width = self.text_width();
+        let so = self.sidescrolloff();
+        let col = self.cursor_col();
+        let line_width = self.tabs.display_col(text, text.len());
+        let left = self.viewport_left as usize;
+
+        // columns between the cursor and the window edges
+        let max_left = col.saturating_sub(so);
+        let right = col.max((col + so).min(line_width.saturating_sub(1)));
+        let min_left = (right + 1).saturating_sub(width);
+
+        let new_left = if left > max_left {
+            match self.sidescroll {
+                0 => col.saturating_sub(width / 2),
+                step => left.saturating_sub(step).min(max_left),
+            }
+        } else if left < min_left {
+            match self.sidescroll {
+                0 => col.saturating_sub(width / 2).max(min_left),
+                step => (left + step).max(min_left),
+            }
+        } else {
+            left
+        };
+        self.viewport_left = new_left.min(max_left) as u16;
+    }
+
+    // the part of `text` that fits in the window, with `<` and `>` where text is cut off
+    pub(super) fn visible_text(&self, text: &str) -> String {
+        let width = self.text_width();
+        let left = self.viewport_left as usize;
+        let line_width = self.tabs.display_col(text, text.len());
+
+        let mut shown: Vec<char> = self.tabs.expand(text, 0, text.len()).chars().skip(left).take(width).collect();
+        if left > 0 && line_width > 0 {
+            match shown.first_mut() {
+                Some(c) => *c = '<',
+                None => shown.push('<'),
+            }
+        }
+        if line_width > left + width {
+            shown[width - 1] = '>';
+        }
+        shown.resize(width, ' ');
+
+        shown.into_iter().collect()
+    }
+}
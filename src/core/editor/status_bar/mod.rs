@@ -1,13 +1,14 @@
 use std::io::{Stdout, Write};
 
-use crossterm::{cursor::MoveTo, style::{self, Color, Stylize}, QueueableCommand};
+use crossterm::{cursor::MoveTo, style, QueueableCommand};
 
 // adding all list of modules
 use super::modules::{get_modules, BarModule, Info};
 
 use super::config::StatusBarSettings;
+use super::theme::Theme;
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq)]
 pub enum Side {
     Top,
     Bottom
@@ -16,28 +17,32 @@ pub enum Side {
 pub struct StatusBar {
     sepatator: String,
     modules: Vec<Box<dyn BarModule>>,
+    plugin_modules: usize,
     side: Side,
-
-    background_color: (u8, u8, u8)
+    // columns every module took in the last draw, used to find the clicked one
+    spans: Vec<(usize, usize)>,
 }
 
 impl StatusBar {
-    pub fn new() -> Self {
-        let settings = StatusBarSettings::init();
+    pub fn new(settings: &StatusBarSettings) -> Self {
         Self {
             sepatator: settings.get_info_separator().unwrap(),
             modules: get_modules(), 
+            plugin_modules: 0,
             side: Side::Bottom,
-            background_color: settings.get_info_backcolor().unwrap()
+            spans: Vec::new(),
         }
     }
 
     // draw status bar and all modules in the terminal
-    pub fn draw(&mut self, _stdout: &mut Stdout, size: (u16, u16)) -> anyhow::Result<()> {
+    pub fn draw(&mut self, _stdout: &mut Stdout, size: (u16, u16), theme: &Theme) -> anyhow::Result<()> {
         let mut bar: String = String::new();
+        self.spans.clear();
         for module in self.modules.iter_mut() {
             let displayed_string = module.give_display();
-            bar.push_str(&displayed_string.as_str());
+            let start = bar.chars().count();
+            self.spans.push((start, start + displayed_string.chars().count()));
+            bar.push_str(displayed_string.as_str());
             bar.push(' ');
             bar.push_str(&self.sepatator);
             bar.push(' ');
@@ -46,28 +51,13 @@ impl StatusBar {
         self.move_to_bar(_stdout, size)?;
 
         _stdout.queue(style::PrintStyledContent(
-            " ".repeat(size.0 as usize).on(
-                Color::Rgb { 
-                    r: self.background_color.0, 
-                    g: self.background_color.1, 
-                    b: self.background_color.2 
-                })
+            theme.bar(" ".repeat(size.0 as usize), "status_bar_background", "status_bar_text")
         ))?;
         _stdout.flush()?;
 
         self.move_to_bar(_stdout, size)?;
         _stdout.queue(style::PrintStyledContent(
-        bar.as_str().on(
-            Color::Rgb { 
-                r: self.background_color.0,
-                g: self.background_color.1, 
-                b: self.background_color.2 
-            })
-            .with(Color::Rgb { 
-                r: 102, 
-                g: 0, 
-                b: 51 
-            })
+        theme.bar(bar.as_str(), "status_bar_background", "status_bar_text")
         ))?;
         
 
@@ -77,17 +67,23 @@ impl StatusBar {
         Ok(())
     }
 
-    
-    pub fn change_side(&mut self) { // changes side of the status bar
-        match self.side {
-            Side::Bottom => self.side = Side::Top,
-            Side::Top => self.side = Side::Bottom
-        }
+    // modules plugins registered go after the built-in ones
+    pub fn add_module(&mut self, module: Box<dyn BarModule>) {
+        self.modules.push(module);
+        self.plugin_modules += 1;
+    }
+
+    pub fn plugin_modules(&self) -> usize {
+        self.plugin_modules
+    }
+
+    // the `statusbarside` option
+    pub fn set_side(&mut self, side: Side) {
+        self.side = side;
     }
 
-    // changes background color of status bar
-    pub fn change_background_color(&mut self, new_color: &(u8, u8, u8)) {
-        self.background_color = new_color.clone();
+    pub fn side(&self) -> Side {
+        self.side
     }
 
 
@@ -97,11 +93,25 @@ impl StatusBar {
         }
     }
 
+    // terminal row the bar is drawn on
+    pub fn row(&self, size: (u16, u16)) -> u16 {
+        match self.side {
+            Side::Bottom => size.1.saturating_sub(2),
+            Side::Top => 0,
+        }
+    }
+
+    // lets the module under `column` react to a click
+    pub fn click(&mut self, column: usize) -> Option<String> {
+        let index = self.spans.iter().position(|(start, end)| column >= *start && column < *end)?;
+        self.modules[index].on_click()
+    }
+
     // moves cursor to the begining of status bar
     fn move_to_bar(&self, _stdout: &mut Stdout, size: (u16, u16)) -> anyhow::Result<()> {
         match self.side {
             Side::Bottom => {
-                _stdout.queue(MoveTo(0, size.1-2))?;
+                _stdout.queue(MoveTo(0, size.1.saturating_sub(2)))?;
             },
             Side::Top => {
                 _stdout.queue(MoveTo(0, 0))?;
@@ -0,0 +1,118 @@
+use super::options::Options;
+
+// how tabs are shown and typed: a tab fills the screen up to the next multiple of
+// `tabstop`, `shiftwidth` is one level of indentation (0 means `tabstop`), Tab and
+// Backspace move by `softtabstop` columns when it is set and `expandtab` types spaces
+// instead of tabs
+pub struct Tabs {
+    pub tabstop: usize,
+    pub shiftwidth: usize,
+    pub softtabstop: usize,
+    pub expandtab: bool,
+}
+
+impl Tabs {
+    pub fn new(options: &Options) -> Self {
+        Self {
+            tabstop: options.number("tabstop").max(1),
+            shiftwidth: options.number("shiftwidth"),
+            softtabstop: options.number("softtabstop"),
+            expandtab: options.flag("expandtab"),
+        }
+    }
+
+    pub fn shiftwidth(&self) -> usize {
+        match self.shiftwidth {
+            0 => self.tabstop,
+            n => n,
+        }
+    }
+
+    // screen cells character `c` takes when it starts at column `col`
+    pub fn char_width(&self, c: char, col: usize) -> usize {
+        match c {
+            '\t' => self.tabstop - col % self.tabstop,
+            _ => 1,
+        }
+    }
+
+    // screen column of byte offset `byte` in `text`
+    pub fn display_col(&self, text: &str, byte: usize) -> usize {
+        text[..byte.min(text.len())]
+            .chars()
+            .fold(0, |col, c| col + self.char_width(c, col))
+    }
+
+    // byte offset of the character shown at screen column `col`, the line length past its end
+    pub fn byte_at_col(&self, text: &str, col: usize) -> usize {
+        let mut at = 0;
+        for (i, c) in text.char_indices() {
+            at += self.char_width(c, at);
+            if at > col {
+                return i;
+            }
+        }
+        text.len()
+    }
+
+    // bytes `start..end` of `text` as shown on screen, tabs turned into spaces
+    pub fn expand(&self, text: &str, start: usize, end: usize) -> String {
+        let mut col = self.display_col(text, start);
+        let mut shown = String::with_capacity(end - start);
+        for c in text[start..end].chars() {
+            let width = self.char_width(c, col);
+            match c {
+                '\t' => shown.push_str(&" ".repeat(width)),
+                _ => shown.push(c),
+            }
+            col += width;
+        }
+        shown
+    }
+
+    // white space going from column `from` to column `to`, with as many tabs as fit
+    // unless `expandtab` is on
+    pub fn fill(&self, from: usize, to: usize) -> String {
+        let mut text = String::new();
+        let mut col = from;
+        if !self.expandtab {
+            while col < to && (col / self.tabstop + 1) * self.tabstop <= to {
+                text.push('\t');
+                col = (col / self.tabstop + 1) * self.tabstop;
+            }
+        }
+        text.push_str(&" ".repeat(to.saturating_sub(col)));
+        text
+    }
+
+    // `text` with every run of white space that holds a tab (and with `all` every run of
+    // two or more spaces) measured with tabs of `old_tabstop` columns and written again
+    // for the current settings; the text keeps its look on screen
+    pub fn retab(&self, text: &str, old_tabstop: usize, all: bool) -> String {
+        let old = Tabs { tabstop: old_tabstop.max(1), ..*self };
+        let mut result = String::with_capacity(text.len());
+        let mut col = 0;
+        let mut rest = text;
+
+        while let Some(c) = rest.chars().next() {
+            let run = rest.len() - rest.trim_start_matches([' ', '\t']).len();
+            if run == 0 {
+                col += old.char_width(c, col);
+                result.push(c);
+                rest = &rest[c.len_utf8()..];
+                continue;
+            }
+
+            let white = &rest[..run];
+            let end = white.chars().fold(col, |at, c| at + old.char_width(c, at));
+            if white.contains('\t') || (all && run > 1) {
+                result.push_str(&self.fill(col, end));
+            } else {
+                result.push_str(white);
+            }
+            col = end;
+            rest = &rest[run..];
+        }
+        result
+    }
+}
@@ -0,0 +1,167 @@
+use super::super::super::buffer::Buffer;
+use super::super::config::ConfigOptions;
+use super::{editor, editor_loading, editor_on, error, ex, lines, type_keys, TempDir};
+
+fn message(editor: &super::Editor) -> &str {
+    editor.command_bar.message.as_deref().unwrap_or("")
+}
+
+fn tabstop(editor: &super::Editor) -> usize {
+    editor.config.options.number("tabstop")
+}
+
+#[test]
+fn reading_files_and_patterns() {
+    let dir = TempDir::new();
+    let text = dir.file("a.txt", "text\n");
+    let mut editor = editor("");
+    ex(&mut editor, ":autocmd BufRead *.rs,*.txt set ts=7").unwrap();
+    ex(&mut editor, ":au BufNewFile ?.sh normal i#!/bin/sh").unwrap();
+
+    ex(&mut editor, &format!(":e {}", text.display())).unwrap();
+    assert_eq!(tabstop(&editor), 7);
+
+    ex(&mut editor, &format!(":e {}", dir.path().join("b.sh").display())).unwrap();
+    assert_eq!(lines(&editor), ["#!/bin/sh"]);
+    ex(&mut editor, &format!(":e! {}", dir.path().join("long.sh").display())).unwrap();
+    assert_eq!(lines(&editor), [""]);
+
+    // a pattern with a `/` is matched against the whole name
+    ex(&mut editor, ":set ts=4").unwrap();
+    ex(&mut editor, ":autocmd! BufRead").unwrap();
+    ex(&mut editor, &format!(":au BufRead {}/* set ts=5", dir.path().display())).unwrap();
+    ex(&mut editor, &format!(":e! {}", text.display())).unwrap();
+    assert_eq!(tabstop(&editor), 5);
+}
+
+#[test]
+fn writing_runs_before_and_after() {
+    let dir = TempDir::new();
+    let file = dir.file("a.txt", "");
+    let mut editor = editor_on(Buffer::from_file(Some(file.display().to_string())));
+    ex(&mut editor, ":autocmd BufWritePre *.txt %!tr a-z A-Z").unwrap();
+    ex(&mut editor, ":autocmd BufWritePost *.txt set ts=6").unwrap();
+    type_keys(&mut editor, "iabc<Esc>");
+    ex(&mut editor, ":w").unwrap();
+
+    assert_eq!(std::fs::read_to_string(&file).unwrap(), "ABC\n");
+    assert_eq!(lines(&editor), ["ABC"]);
+    assert_eq!(tabstop(&editor), 6);
+}
+
+#[test]
+fn mode_changes_match_old_and_new() {
+    let mut editor = editor("");
+    ex(&mut editor, ":autocmd ModeChanged *:insert set ts=2").unwrap();
+    ex(&mut editor, ":autocmd InsertLeave * set ts=3").unwrap();
+    type_keys(&mut editor, "v<Esc>");
+    assert_eq!(tabstop(&editor), 4);
+    type_keys(&mut editor, "i");
+    assert_eq!(tabstop(&editor), 2);
+    type_keys(&mut editor, "<Esc>");
+    assert_eq!(tabstop(&editor), 3);
+}
+
+#[test]
+fn listing_and_removing() {
+    let mut editor = editor("");
+    ex(&mut editor, ":au BufRead *.rs set ts=2").unwrap();
+    ex(&mut editor, ":au bufread *.py set ts=8").unwrap();
+    ex(&mut editor, ":autocmd BufRead *.rs").unwrap();
+    assert_eq!(message(&editor), "BufRead        *.rs       set ts=2");
+
+    // `!` with a command puts it in place of the ones before
+    ex(&mut editor, ":au! BufRead *.rs set ts=3").unwrap();
+    ex(&mut editor, ":autocmd BufRead *.rs").unwrap();
+    assert_eq!(message(&editor), "BufRead        *.rs       set ts=3");
+
+    ex(&mut editor, ":autocmd! *").unwrap();
+    ex(&mut editor, ":autocmd").unwrap();
+    assert_eq!(message(&editor), "No autocommands found");
+
+    assert_eq!(error(&mut editor, ":au BufOpen * set ts=2"), "E216: No such event: BufOpen");
+    assert_eq!(error(&mut editor, ":au BufRead,Nope * set ts=2"), "E216: No such event: Nope");
+    // plugins hear of every change, autocommands do not
+    assert_eq!(error(&mut editor, ":au TextChanged * set ts=2"), "E216: No such event: TextChanged");
+    ex(&mut editor, ":au BufRead").unwrap();
+    assert_eq!(message(&editor), "No autocommands found");
+}
+
+#[test]
+fn errors_name_the_event_and_pattern() {
+    let dir = TempDir::new();
+    let file = dir.file("a.txt", "");
+    let mut editor = editor_on(Buffer::from_file(Some(file.display().to_string())));
+    ex(&mut editor, ":au BufWritePost *.txt nothing").unwrap();
+    ex(&mut editor, ":w").unwrap();
+    assert_eq!(message(&editor), "BufWritePost Autocommands for \"*.txt\": E492: Not an editor command: nothing");
+}
+
+#[test]
+fn autocommands_do_not_start_autocommands() {
+    let dir = TempDir::new();
+    let file = dir.file("a.txt", "");
+    let mut editor = editor_on(Buffer::from_file(Some(file.display().to_string())));
+    // writing again from `BufWritePost` does not write a third time
+    ex(&mut editor, ":au BufWritePost * normal Ax").unwrap();
+    ex(&mut editor, ":au BufWritePost * w").unwrap();
+    ex(&mut editor, ":w").unwrap();
+    assert_eq!(std::fs::read_to_string(&file).unwrap(), "x\n");
+    assert_eq!(lines(&editor), ["x"]);
+}
+
+#[test]
+fn a_file_changed_by_a_shell_command_is_read_again() {
+    let dir = TempDir::new();
+    let file = dir.file("a.txt", "old\n");
+    let mut editor = editor_on(Buffer::from_file(Some(file.display().to_string())));
+    ex(&mut editor, ":au BufRead *.txt set ts=6").unwrap();
+    ex(&mut editor, ":!echo new > %").unwrap();
+    assert_eq!(lines(&editor), ["new"]);
+    assert_eq!(tabstop(&editor), 6);
+
+    // nothing is read when the buffer has changes of its own
+    ex(&mut editor, ":set ts=4").unwrap();
+    type_keys(&mut editor, "x");
+    ex(&mut editor, ":!echo newer > %").unwrap();
+    assert_eq!(lines(&editor), ["ew"]);
+    assert_eq!(tabstop(&editor), 4);
+}
+
+#[test]
+fn project_autocommands_need_exrc() {
+    let dir = TempDir::new();
+    std::fs::create_dir_all(dir.path().join("user")).unwrap();
+    std::fs::create_dir_all(dir.path().join("project")).unwrap();
+    dir.file("project/.edit.conf", "autocmd BufWritePre * %!tr a-z A-Z\n");
+    let file = dir.file("project/a.txt", "");
+    let options = ConfigOptions { dir: Some(dir.path().join("user")), ..ConfigOptions::default() };
+
+    let editor = editor_loading(&file, &options);
+    assert!(editor.autocmds.list.is_empty());
+    assert_eq!(
+        message(&editor),
+        format!("{}:1: `autocmd` lines of a project are read only with `exrc = true`", dir.path().join("project/.edit.conf").display())
+    );
+}
+
+#[test]
+fn nothing_runs_before_a_write_that_cannot_happen() {
+    let mut editor = editor("abc");
+    ex(&mut editor, ":au BufWritePre * normal x").unwrap();
+    assert_eq!(error(&mut editor, ":w"), "E32: No file name");
+    assert_eq!(error(&mut editor, ":1w"), "E140: Use ! to write partial buffer");
+    assert_eq!(lines(&editor), ["abc"]);
+}
+
+#[test]
+fn range_writes_run_them_too() {
+    let dir = TempDir::new();
+    let file = dir.file("a.txt", "");
+    let mut editor = editor("one\ntwo\nthree");
+    ex(&mut editor, ":au BufWritePre *.txt 3d").unwrap();
+    ex(&mut editor, ":au BufWritePost *.txt set ts=6").unwrap();
+    ex(&mut editor, &format!(":2,3w {}", file.display())).unwrap();
+    assert_eq!(std::fs::read_to_string(&file).unwrap(), "two\n");
+    assert_eq!(tabstop(&editor), 6);
+}
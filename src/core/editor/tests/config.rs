@@ -0,0 +1,90 @@
+use std::path::Path;
+
+use super::super::config::Config;
+use super::TempDir;
+
+// the shipped example, read from the root of the repository
+const EXAMPLE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/edit.conf");
+
+#[test]
+fn the_shipped_file_reads_without_errors() {
+    let mut config = Config::default();
+    assert_eq!(config.apply_file(Path::new(EXAMPLE), true), Vec::<String>::new());
+    assert_eq!(config.editor.font_color, Some((120, 200, 3)));
+    assert_eq!(config.status_bar.separator_symbol, "?");
+    assert_eq!(config.commands.save, ":w");
+}
+
+#[test]
+fn values_comments_and_quotes() {
+    let dir = TempDir::new();
+    let path = dir.file(
+        "edit.conf",
+        "# a comment\n\n  theme = gruvbox  \nstatus_bar_separator = \" | \"\ntabstop = 8\ncommand_bar_font_color = #0a0b0c\n",
+    );
+    let mut config = Config::default();
+    assert!(config.apply_file(&path, true).is_empty());
+    assert_eq!(config.editor.theme, "gruvbox");
+    assert_eq!(config.status_bar.separator_symbol, " | ");
+    assert_eq!(config.options.number("tabstop"), 8);
+    assert_eq!(config.command_bar.font_color, Some((10, 11, 12)));
+}
+
+#[test]
+fn older_names_still_work() {
+    let mut config = Config::default();
+    config.apply("cmd_save", ":write").unwrap();
+    config.apply("colorscheme", "nord").unwrap();
+    config.apply("auto_pairs", "false").unwrap();
+    assert_eq!(config.commands.save, ":write");
+    assert_eq!(config.editor.theme, "nord");
+    assert!(!config.options.flag("autopairs"));
+    assert_eq!(config.value("cmd_write").as_deref(), Some("\":write\""));
+}
+
+#[test]
+fn problems_are_reported_with_their_line() {
+    let dir = TempDir::new();
+    let path = dir.file(
+        "edit.conf",
+        "theme = default\nnot a setting\ncolour = red\neditor_font_color = (1, 2)\ntabstop = many\ntabstop = 2\n",
+    );
+    let mut config = Config::default();
+    let errors = config.apply_file(&path, true);
+    let file = path.display();
+    assert_eq!(
+        errors,
+        [
+            format!("{}:2: expected `key = value`, got `not a setting`", file),
+            format!("{}:3: unknown setting `colour`", file),
+            format!("{}:4: expected a colour like (255, 204, 229) or #ffccee, got `(1, 2)`", file),
+            format!("{}:5: expected a number, got `many`", file),
+        ]
+    );
+    // the good lines still count, a bad one leaves the setting as it was
+    assert_eq!(config.editor.font_color, None);
+    assert_eq!(config.options.number("tabstop"), 2);
+}
+
+#[test]
+fn missing_files() {
+    let dir = TempDir::new();
+    let path = dir.path().join("nothing.conf");
+    let mut config = Config::default();
+    assert!(config.apply_file(&path, false).is_empty());
+    let errors = config.apply_file(&path, true);
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].starts_with(&path.display().to_string()));
+}
+
+#[test]
+fn command_style_lines_are_kept_for_the_editor() {
+    let dir = TempDir::new();
+    let path = dir.file("edit.conf", "nmap <C-s> :w<CR>\ncommand Fmt !rustfmt %\nalias W w\nscrolloff = 3\n");
+    let mut config = Config::default();
+    assert!(config.apply_file(&path, true).is_empty());
+
+    let lines: Vec<(&str, &str)> = config.ex_lines.iter().map(|(_, c, a)| (c.as_str(), a.as_str())).collect();
+    assert_eq!(lines, [("nmap", "<C-s> :w<CR>"), ("command", "Fmt !rustfmt %"), ("alias", "W w")]);
+    assert_eq!(config.ex_lines[2].0, format!("{}:3", path.display()));
+}
@@ -0,0 +1,52 @@
+use super::{editor, error, ex, lines, type_keys};
+
+const TEXT: &str = "apple\nbanana\navocado\ncherry";
+
+#[test]
+fn delete_matching_and_other_lines() {
+    let mut editor = editor(TEXT);
+    ex(&mut editor, ":g/^a/d").unwrap();
+    assert_eq!(lines(&editor), ["banana", "cherry"]);
+
+    let mut editor = super::editor(TEXT);
+    ex(&mut editor, ":v/^a/d").unwrap();
+    assert_eq!(lines(&editor), ["apple", "avocado"]);
+}
+
+#[test]
+fn lines_follow_moves() {
+    // every line moved to the top reverses the file
+    let mut editor = editor(TEXT);
+    ex(&mut editor, ":g/^/m0").unwrap();
+    assert_eq!(lines(&editor), ["cherry", "avocado", "banana", "apple"]);
+}
+
+#[test]
+fn range_normal_and_last_pattern() {
+    let mut editor = editor(TEXT);
+    ex(&mut editor, ":2,$g/a/normal A!").unwrap();
+    assert_eq!(lines(&editor), ["apple", "banana!", "avocado!", "cherry"]);
+
+    ex(&mut editor, ":/cherry/").unwrap();
+    ex(&mut editor, ":g//d").unwrap();
+    assert_eq!(lines(&editor), ["apple", "banana!", "avocado!"]);
+}
+
+#[test]
+fn one_undo_step() {
+    let mut editor = editor(TEXT);
+    ex(&mut editor, ":g/an/t$").unwrap();
+    ex(&mut editor, ":g/o/d").unwrap();
+    assert_eq!(lines(&editor), ["apple", "banana", "cherry", "banana"]);
+    type_keys(&mut editor, "u");
+    assert_eq!(lines(&editor), ["apple", "banana", "avocado", "cherry", "banana"]);
+}
+
+#[test]
+fn errors() {
+    let mut editor = editor(TEXT);
+    assert!(error(&mut editor, ":g/zzz/d").starts_with("E486"));
+    assert!(error(&mut editor, ":g/a/g/b/d").starts_with("E147"));
+    assert!(error(&mut editor, ":g").starts_with("E476"));
+    assert!(error(&mut editor, ":g/[/d").starts_with("E383"));
+}
@@ -0,0 +1,60 @@
+use super::super::super::buffer::Buffer;
+use super::{cursor, editor, editor_on, ex, lines, type_keys};
+
+#[test]
+fn autoindent_copies_the_line_above() {
+    let mut editor = editor("    one");
+    ex(&mut editor, ":set nosi").unwrap();
+    type_keys(&mut editor, "A<CR>two<Esc>");
+    assert_eq!(lines(&editor), ["    one", "    two"]);
+    type_keys(&mut editor, "Othree<Esc>");
+    assert_eq!(lines(&editor)[1], "    three");
+
+    // a line left with only indentation is emptied
+    type_keys(&mut editor, "o<CR>x<Esc>");
+    assert_eq!(lines(&editor)[2..4], ["", "    x"]);
+}
+
+#[test]
+fn smartindent_adds_a_level_after_brackets() {
+    let mut editor = editor("fn main() {");
+    ex(&mut editor, ":set noap").unwrap();
+    type_keys(&mut editor, "A<CR>x<Esc>");
+    assert_eq!(lines(&editor), ["fn main() {", "    x"]);
+    type_keys(&mut editor, "ofoo(<Esc>");
+    type_keys(&mut editor, "oy<Esc>");
+    assert_eq!(lines(&editor)[2..], ["    foo(", "        y"]);
+}
+
+#[test]
+fn enter_inside_brackets_splits_them() {
+    let mut editor = editor("");
+    type_keys(&mut editor, "iif x {<CR>");
+    assert_eq!(lines(&editor), ["if x {", "    ", "}"]);
+    assert_eq!(cursor(&editor), (1, 4));
+}
+
+#[test]
+fn smartindent_works_without_autoindent() {
+    let mut editor = editor("  if x {");
+    ex(&mut editor, ":set noai noap").unwrap();
+    type_keys(&mut editor, "A<CR>y<Esc>");
+    assert_eq!(lines(&editor), ["  if x {", "      y"]);
+    type_keys(&mut editor, "oz<Esc>");
+    assert_eq!(lines(&editor)[2], "      z");
+
+    // with both off nothing is indented
+    ex(&mut editor, ":set nosi").unwrap();
+    type_keys(&mut editor, "o{<CR>w<Esc>");
+    assert_eq!(lines(&editor)[3..], ["{", "w"]);
+}
+
+#[test]
+fn python_blocks_start_after_a_colon() {
+    let mut buffer = Buffer::from_file(None);
+    buffer.filetype = "python".to_string();
+    buffer.lines = vec!["def f():".to_string()];
+    let mut editor = editor_on(buffer);
+    type_keys(&mut editor, "oreturn<Esc>");
+    assert_eq!(lines(&editor), ["def f():", "    return"]);
+}
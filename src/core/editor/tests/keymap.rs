@@ -0,0 +1,145 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use super::super::keymap::map_command;
+use super::super::keys::{parse_keys, same_key};
+use super::{cursor, editor, error, ex, lines, type_keys};
+
+fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+    KeyEvent::new(code, modifiers)
+}
+
+#[test]
+fn key_names() {
+    let none = KeyModifiers::NONE;
+    assert_eq!(
+        parse_keys("a<Esc><C-S><A-j><S-Tab><lt><F5>"),
+        [
+            key(KeyCode::Char('a'), none),
+            key(KeyCode::Esc, none),
+            key(KeyCode::Char('s'), KeyModifiers::CONTROL),
+            key(KeyCode::Char('j'), KeyModifiers::ALT),
+            key(KeyCode::BackTab, none),
+            key(KeyCode::Char('<'), none),
+            key(KeyCode::F(5), none),
+        ]
+    );
+    // names that are not keys are typed as they are
+    assert_eq!(parse_keys("<x>").len(), 3);
+    // terminals send upper case letters with Shift
+    assert!(same_key(&key(KeyCode::Char('A'), KeyModifiers::SHIFT), &parse_keys("A")[0]));
+}
+
+#[test]
+fn map_command_names() {
+    assert_eq!(map_command("nmap"), Some(("n", false, false)));
+    assert_eq!(map_command("noremap"), Some(("nv", true, false)));
+    assert_eq!(map_command("xnoremap"), Some(("v", true, false)));
+    assert_eq!(map_command("iunmap"), Some(("i", false, true)));
+    assert_eq!(map_command("cmap"), Some(("c", false, false)));
+    assert_eq!(map_command("nmapx"), None);
+}
+
+#[test]
+fn mappings_to_keys_and_commands() {
+    let mut editor = editor("one\ntwo\nthree");
+    ex(&mut editor, ":nmap <C-d> jj").unwrap();
+    ex(&mut editor, ":nmap Q :1d<CR>").unwrap();
+    type_keys(&mut editor, "<C-d>");
+    assert_eq!(cursor(&editor), (2, 0));
+    type_keys(&mut editor, "Q");
+    assert_eq!(lines(&editor), ["two", "three"]);
+}
+
+#[test]
+fn mappings_per_mode() {
+    let mut editor = editor("");
+    ex(&mut editor, ":imap jk <Esc>").unwrap();
+    ex(&mut editor, ":cmap <C-a> abc").unwrap();
+    type_keys(&mut editor, "ihijk");
+    assert_eq!(lines(&editor), ["hi"]);
+    assert!(editor.mode == super::super::Mode::Normal);
+
+    // `j` alone is typed once the next key does not go on with the mapping
+    type_keys(&mut editor, "aj!<Esc>");
+    assert_eq!(lines(&editor), ["hij!"]);
+
+    type_keys(&mut editor, ":<C-a>");
+    assert_eq!(editor.command_bar.command, ":abc");
+}
+
+#[test]
+fn a_partial_mapping_runs_when_the_time_is_up() {
+    let mut editor = editor("abc");
+    ex(&mut editor, ":nmap x $").unwrap();
+    ex(&mut editor, ":nmap xy 0").unwrap();
+    type_keys(&mut editor, "x");
+    assert!(editor.keymap.is_pending());
+    editor.keys_timed_out().unwrap();
+    assert_eq!(cursor(&editor), (0, 2));
+}
+
+#[test]
+fn noremap_and_recursion() {
+    let mut editor = editor("abc");
+    ex(&mut editor, ":nmap a x").unwrap();
+    ex(&mut editor, ":nnoremap b a").unwrap();
+    type_keys(&mut editor, "bZ<Esc>");
+    assert_eq!(lines(&editor), ["aZbc"]);
+
+    // a mapping that starts with its own keys does not loop
+    let mut editor = super::editor("abc");
+    ex(&mut editor, ":nmap x xl").unwrap();
+    type_keys(&mut editor, "x");
+    assert_eq!(lines(&editor), ["bc"]);
+
+    let mut editor = super::editor("abc");
+    ex(&mut editor, ":nmap p q").unwrap();
+    ex(&mut editor, ":nmap q p").unwrap();
+    type_keys(&mut editor, "p");
+    assert_eq!(editor.command_bar.message.as_deref(), Some("E223: recursive mapping"));
+}
+
+#[test]
+fn the_leader_key() {
+    let mut editor = editor("abc");
+    ex(&mut editor, ":set leader=,").unwrap();
+    ex(&mut editor, ":nmap <leader>e $").unwrap();
+    type_keys(&mut editor, ",e");
+    assert_eq!(cursor(&editor), (0, 2));
+}
+
+#[test]
+fn listing_and_unmapping() {
+    let mut editor = editor("abc");
+    ex(&mut editor, ":nnoremap Q $").unwrap();
+    ex(&mut editor, ":nmap Q").unwrap();
+    assert_eq!(editor.command_bar.message.as_deref(), Some("n  Q            * $"));
+
+    ex(&mut editor, ":nunmap Q").unwrap();
+    type_keys(&mut editor, "Q");
+    assert_eq!(cursor(&editor), (0, 0));
+    assert_eq!(error(&mut editor, ":nunmap Q"), "E31: No such mapping");
+    ex(&mut editor, ":map").unwrap();
+    assert_eq!(editor.command_bar.message.as_deref(), Some("No mapping found"));
+}
+
+#[test]
+fn normal_uses_the_mappings_and_normal_bang_does_not() {
+    let mut editor = editor("abc\nabc");
+    ex(&mut editor, ":nmap Q x").unwrap();
+    ex(&mut editor, ":1normal Q").unwrap();
+    ex(&mut editor, ":2normal! Q").unwrap();
+    assert_eq!(lines(&editor), ["bc", "abc"]);
+
+    // keys typed after the command still go through the mappings
+    type_keys(&mut editor, ":normal! $<CR>Q");
+    assert_eq!(lines(&editor), ["bc", "ab"]);
+}
+
+#[test]
+fn normal_running_itself_stops() {
+    let mut editor = editor("abc");
+    ex(&mut editor, ":nmap Q :normal Q<CR>").unwrap();
+    type_keys(&mut editor, "Q");
+    assert_eq!(editor.command_bar.message.as_deref(), Some("E192: Recursive use of :normal too deep"));
+}
@@ -0,0 +1,145 @@
+use super::super::config::{Config, ConfigOptions};
+use super::{editor, editor_loading, error, ex, lines, type_keys, TempDir};
+
+// a user directory, a project with a file in a directory below it and a file given
+// with `--config`, each setting something the layer before set too
+fn layers(dir: &TempDir) -> (String, ConfigOptions) {
+    std::fs::create_dir_all(dir.path().join("user")).unwrap();
+    std::fs::create_dir_all(dir.path().join("project/src")).unwrap();
+    dir.file("user/edit.conf", "tabstop = 2\nscrolloff = 2\ntheme = nord\nwrap = true\n");
+    dir.file("project/.edit.conf", "tabstop = 3\nscrolloff = 3\n");
+    let extra = dir.file("extra.conf", "scrolloff = 4\n");
+    let file = dir.file("project/src/main.rs", "");
+
+    let options = ConfigOptions {
+        file: Some(extra),
+        sets: vec!["theme=gruvbox".to_string()],
+        dir: Some(dir.path().join("user")),
+    };
+    (file.display().to_string(), options)
+}
+
+#[test]
+fn each_layer_overrides_the_one_before() {
+    let dir = TempDir::new();
+    let (file, options) = layers(&dir);
+    let (config, errors) = Config::load(Some(&file), &options);
+    assert!(errors.is_empty(), "{:?}", errors);
+
+    assert!(config.options.flag("wrap"));
+    assert_eq!(config.options.number("tabstop"), 3);
+    assert_eq!(config.options.number("scrolloff"), 4);
+    assert_eq!(config.editor.theme, "gruvbox");
+
+    let user = dir.path().join("user/edit.conf");
+    let project = dir.path().join("project/.edit.conf");
+    assert_eq!(config.files, [user.clone(), project.clone(), dir.path().join("extra.conf")]);
+
+    // `:config` says where each value came from
+    assert_eq!(config.describe("wrap").unwrap(), format!("wrap = true  ({}:4)", user.display()));
+    assert_eq!(config.describe("ts").unwrap(), format!("tabstop = 3  ({}:1)", project.display()));
+    assert_eq!(config.describe("theme").unwrap(), "theme = gruvbox  (--set)");
+    assert_eq!(config.describe("number").unwrap(), "number = true  (default)");
+}
+
+#[test]
+fn the_project_is_found_from_the_edited_file() {
+    let dir = TempDir::new();
+    let (_, options) = layers(&dir);
+    let outside = dir.file("outside.rs", "");
+    let (config, _) = Config::load(Some(&outside.display().to_string()), &options);
+    assert_eq!(config.options.number("tabstop"), 2);
+    assert_eq!(config.files.len(), 2);
+}
+
+#[test]
+fn command_line_problems() {
+    let dir = TempDir::new();
+    let options = ConfigOptions {
+        file: Some(dir.path().join("missing.conf")),
+        sets: vec!["wrap".to_string(), "nothing=1".to_string(), "ts=0".to_string()],
+        dir: Some(dir.path().to_path_buf()),
+    };
+    let (config, errors) = Config::load(None, &options);
+    assert_eq!(errors.len(), 4);
+    assert!(errors[0].starts_with(&dir.path().join("missing.conf").display().to_string()));
+    assert_eq!(errors[1], "--set wrap: expected `key=value`, got `wrap`");
+    assert_eq!(errors[2], "--set nothing=1: unknown setting `nothing`");
+    assert!(errors[3].starts_with("--set ts=0: "));
+    assert_eq!(config.options.number("tabstop"), 4);
+}
+
+#[test]
+fn config_command_shows_values_and_origins() {
+    let mut editor = editor("");
+    ex(&mut editor, ":set so=7").unwrap();
+    ex(&mut editor, ":config scrolloff").unwrap();
+    assert_eq!(editor.command_bar.message.as_deref(), Some("scrolloff = 7  (:set)"));
+    ex(&mut editor, ":config cmd_quit").unwrap();
+    assert_eq!(editor.command_bar.message.as_deref(), Some("cmd_quit = \":q\"  (default)"));
+    assert_eq!(super::error(&mut editor, ":config nothing"), "Unknown setting: nothing");
+}
+
+// a project whose file maps keys, defines a command and sets `exrc` for itself
+fn project(dir: &TempDir) -> (std::path::PathBuf, std::path::PathBuf) {
+    std::fs::create_dir_all(dir.path().join("user")).unwrap();
+    std::fs::create_dir_all(dir.path().join("project")).unwrap();
+    let conf = dir.file("project/.edit.conf", "tabstop = 3\nnmap Q x\ncommand Fmt !true\nexrc = true\n");
+    (dir.file("project/a.txt", "abc\n"), conf)
+}
+
+#[test]
+fn project_files_run_nothing_without_exrc() {
+    let dir = TempDir::new();
+    let (file, conf) = project(&dir);
+    let options = ConfigOptions { dir: Some(dir.path().join("user")), ..ConfigOptions::default() };
+    let (config, errors) = Config::load(Some(&file.display().to_string()), &options);
+
+    let conf = conf.display();
+    assert_eq!(errors, [
+        format!("{}:4: `exrc` is set in the user's file or on the command line", conf),
+        format!("{}:2: `nmap` lines of a project are read only with `exrc = true`", conf),
+        format!("{}:3: `command` lines of a project are read only with `exrc = true`", conf),
+    ]);
+    assert!(config.ex_lines.is_empty());
+    assert!(!config.exrc);
+    assert_eq!(config.options.number("tabstop"), 3);
+
+    let mut editor = editor_loading(&file, &options);
+    assert_eq!(editor.command_bar.message, Some(format!("{} (and 2 more)", errors[0])));
+    type_keys(&mut editor, "Q");
+    assert_eq!(lines(&editor), ["abc"]);
+    assert!(error(&mut editor, ":Fmt").starts_with("E492"));
+}
+
+#[test]
+fn exrc_from_the_user_or_the_command_line() {
+    let dir = TempDir::new();
+    let (file, _) = project(&dir);
+    let user = ConfigOptions { dir: Some(dir.path().join("user")), ..ConfigOptions::default() };
+    dir.file("user/edit.conf", "exrc = true\n");
+    let options = [
+        user.clone(),
+        ConfigOptions { dir: Some(dir.path().to_path_buf()), sets: vec!["exrc=true".to_string()], ..user.clone() },
+        ConfigOptions { dir: Some(dir.path().to_path_buf()), file: Some(dir.path().join("user/edit.conf")), ..user },
+    ];
+
+    for options in &options {
+        let (config, errors) = Config::load(Some(&file.display().to_string()), options);
+        assert_eq!(errors.len(), 1, "{:?}", errors);
+        assert!(config.exrc);
+        assert_eq!(config.ex_lines.len(), 2);
+
+        let mut editor = editor_loading(&file, options);
+        type_keys(&mut editor, "Q");
+        assert_eq!(lines(&editor), ["bc"]);
+        ex(&mut editor, ":Fmt").unwrap();
+    }
+}
+
+#[test]
+fn exrc_is_listed_with_the_settings() {
+    let mut editor = editor("");
+    ex(&mut editor, ":config exrc").unwrap();
+    assert_eq!(editor.command_bar.message.as_deref(), Some("exrc = false  (default)"));
+}
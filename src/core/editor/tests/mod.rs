@@ -0,0 +1,129 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use super::super::buffer::Buffer;
+use super::config::{Config, ConfigOptions};
+use super::keys::parse_keys;
+use super::Editor;
+
+mod autocmds;
+mod config;
+mod global;
+mod indent;
+mod keymap;
+mod layers;
+mod motions;
+mod mouse;
+mod normal_keys;
+mod options;
+mod pairs;
+mod palette;
+mod paste;
+mod ranges;
+mod registry;
+mod reload;
+mod replace;
+mod resize;
+mod scrolling;
+mod sidescroll;
+mod syntax;
+mod tabs;
+mod theme;
+mod user_commands;
+mod wrap;
+
+// a directory of its own under the system's temporary directory, removed again at the end
+// of the test
+struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    fn new() -> Self {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let name = format!("edit-test-{}-{}", std::process::id(), NEXT.fetch_add(1, Ordering::Relaxed));
+        let path = std::env::temp_dir().join(name);
+        std::fs::create_dir_all(&path).unwrap();
+        Self { path }
+    }
+
+    // writes `text` into the file `name` of the directory and returns its path
+    fn file(&self, name: &str, text: &str) -> PathBuf {
+        let path = self.path.join(name);
+        std::fs::write(&path, text).unwrap();
+        path
+    }
+
+    fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
+
+// an editor on the lines of `text` with the default configuration, it never touches
+// the terminal
+fn editor(text: &str) -> Editor {
+    let mut buffer = Buffer::from_file(None);
+    if !text.is_empty() {
+        buffer.lines = text.lines().map(String::from).collect();
+    }
+    editor_on(buffer)
+}
+
+fn editor_on(buffer: Buffer) -> Editor {
+    editor_with(buffer, Config::default())
+}
+
+fn editor_with(buffer: Buffer, config: Config) -> Editor {
+    let mut editor = Editor::with_config(buffer, config, &no_user_config(), (80, 24)).unwrap();
+    editor.apply_options();
+    editor
+}
+
+// an editor on `file` set up like `Editor::new` sets one up, with the configuration of
+// `options`; the problems found in it are in the message
+fn editor_loading(file: &Path, options: &ConfigOptions) -> Editor {
+    let buffer = Buffer::from_file(Some(file.display().to_string()));
+    let (config, mut errors) = Config::load(buffer.file.as_deref(), options);
+    let mut editor = Editor::with_config(buffer, config, options, (80, 24)).unwrap();
+    errors.extend(editor.apply_config().unwrap());
+    editor.command_bar.message = super::error_message(errors);
+    editor
+}
+
+// command line options that keep the user's own configuration directory out of the tests
+fn no_user_config() -> ConfigOptions {
+    ConfigOptions { dir: Some(std::env::temp_dir().join("edit-test-no-config")), ..ConfigOptions::default() }
+}
+
+// types `keys` as they would come from the terminal, written like the right side of a
+// mapping: `3rx`, `ihi<Esc>`
+fn type_keys(editor: &mut Editor, keys: &str) {
+    for key in parse_keys(keys) {
+        editor.handle_key(key).unwrap();
+    }
+}
+
+// runs one command line, with or without its `:`
+fn ex(editor: &mut Editor, command: &str) -> anyhow::Result<()> {
+    editor.execute_command(command.to_string())
+}
+
+fn lines(editor: &Editor) -> Vec<&str> {
+    editor.buffer.lines.iter().map(String::as_str).collect()
+}
+
+// line and byte column of the cursor, both from 0
+fn cursor(editor: &Editor) -> (usize, usize) {
+    editor.cursor_position()
+}
+
+// the message an Ex command failed with
+fn error(editor: &mut Editor, command: &str) -> String {
+    ex(editor, command).unwrap_err().to_string()
+}
@@ -0,0 +1,109 @@
+use super::{cursor, editor, ex, lines, type_keys};
+
+#[test]
+fn words_and_big_words() {
+    let mut editor = editor("foo.bar baz-qux é1 end");
+    type_keys(&mut editor, "w");
+    assert_eq!(cursor(&editor), (0, 3));
+    type_keys(&mut editor, "W");
+    assert_eq!(cursor(&editor), (0, 8));
+    type_keys(&mut editor, "e");
+    assert_eq!(cursor(&editor), (0, 10));
+    type_keys(&mut editor, "E");
+    assert_eq!(cursor(&editor), (0, 14));
+    type_keys(&mut editor, "w");
+    assert_eq!(cursor(&editor), (0, 16));
+    type_keys(&mut editor, "b");
+    assert_eq!(cursor(&editor), (0, 12));
+    type_keys(&mut editor, "B");
+    assert_eq!(cursor(&editor), (0, 8));
+    type_keys(&mut editor, "2b");
+    assert_eq!(cursor(&editor), (0, 3));
+}
+
+#[test]
+fn words_cross_lines() {
+    let mut editor = editor("one\n  two");
+    type_keys(&mut editor, "w");
+    assert_eq!(cursor(&editor), (1, 2));
+    type_keys(&mut editor, "b");
+    assert_eq!(cursor(&editor), (0, 0));
+}
+
+#[test]
+fn line_motions() {
+    let mut editor = editor("  first line\nsecond\n    third");
+    type_keys(&mut editor, "$");
+    assert_eq!(cursor(&editor), (0, 11));
+    type_keys(&mut editor, "^");
+    assert_eq!(cursor(&editor), (0, 2));
+    type_keys(&mut editor, "0");
+    assert_eq!(cursor(&editor), (0, 0));
+    type_keys(&mut editor, "2+");
+    assert_eq!(cursor(&editor), (2, 4));
+    type_keys(&mut editor, "-");
+    assert_eq!(cursor(&editor), (1, 0));
+    type_keys(&mut editor, "G");
+    assert_eq!(cursor(&editor).0, 2);
+    type_keys(&mut editor, "gg");
+    assert_eq!(cursor(&editor).0, 0);
+    type_keys(&mut editor, "2G");
+    assert_eq!(cursor(&editor).0, 1);
+}
+
+#[test]
+fn delete_at_the_end_of_a_line() {
+    let mut editor = editor("aé");
+    type_keys(&mut editor, "$x");
+    assert_eq!(lines(&editor), ["a"]);
+
+    // the cursor goes back to the start of the last character
+    let mut editor = super::editor("éa");
+    type_keys(&mut editor, "$x");
+    assert_eq!(cursor(&editor), (0, 0));
+
+    let mut editor = super::editor("éa");
+    ex(&mut editor, ":normal $xix").unwrap();
+    assert_eq!(lines(&editor), ["xé"]);
+}
+
+#[test]
+fn paragraphs() {
+    let mut editor = editor("a\nb\n\nc\nd\n\ne");
+    type_keys(&mut editor, "}");
+    assert_eq!(cursor(&editor).0, 2);
+    type_keys(&mut editor, "}");
+    assert_eq!(cursor(&editor).0, 5);
+    type_keys(&mut editor, "{");
+    assert_eq!(cursor(&editor).0, 2);
+    type_keys(&mut editor, "2}");
+    assert_eq!(cursor(&editor).0, 6);
+}
+
+#[test]
+fn find_characters_and_repeat() {
+    let mut editor = editor("a,b,c,d");
+    type_keys(&mut editor, "f,");
+    assert_eq!(cursor(&editor), (0, 1));
+    type_keys(&mut editor, ";");
+    assert_eq!(cursor(&editor), (0, 3));
+    type_keys(&mut editor, ",");
+    assert_eq!(cursor(&editor), (0, 1));
+    type_keys(&mut editor, "tc");
+    assert_eq!(cursor(&editor), (0, 3));
+    type_keys(&mut editor, "$Fb");
+    assert_eq!(cursor(&editor), (0, 2));
+    type_keys(&mut editor, "Ta");
+    assert_eq!(cursor(&editor), (0, 1));
+}
+
+#[test]
+fn matching_pairs() {
+    let mut editor = editor("f(a, [b]) {\n}");
+    type_keys(&mut editor, "%");
+    assert_eq!(cursor(&editor), (0, 8));
+    type_keys(&mut editor, "%");
+    assert_eq!(cursor(&editor), (0, 1));
+    type_keys(&mut editor, "$%");
+    assert_eq!(cursor(&editor), (1, 0));
+}
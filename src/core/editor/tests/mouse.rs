@@ -0,0 +1,75 @@
+use crossterm::event::{KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+
+use super::super::{Editor, Mode};
+use super::{cursor, editor, ex, lines, type_keys};
+
+fn mouse(editor: &mut Editor, kind: MouseEventKind, column: u16, row: u16) {
+    let ev = MouseEvent { kind, column, row, modifiers: KeyModifiers::NONE };
+    editor.handle_mouse(ev).unwrap();
+}
+
+// a click on terminal cell (`column`, `row`), the line numbers take the first two columns;
+// the cursor is then fitted in the line like before a draw
+fn click(editor: &mut Editor, column: u16, row: u16) {
+    mouse(editor, MouseEventKind::Down(MouseButton::Left), column, row);
+    mouse(editor, MouseEventKind::Up(MouseButton::Left), column, row);
+    editor.fit_cursor_in_view();
+}
+
+#[test]
+fn clicks_place_the_cursor() {
+    let mut editor = editor("one\ntwo\nthree");
+    click(&mut editor, 4, 2);
+    assert_eq!(cursor(&editor), (2, 2));
+    // past the end of a line lands on its last character, below the text on the last line
+    click(&mut editor, 40, 0);
+    assert_eq!(cursor(&editor), (0, 2));
+    click(&mut editor, 3, 10);
+    assert_eq!(cursor(&editor), (2, 1));
+
+    ex(&mut editor, ":set nomouse").unwrap();
+    click(&mut editor, 2, 0);
+    assert_eq!(cursor(&editor), (2, 1));
+}
+
+#[test]
+fn a_drag_selects() {
+    let mut editor = editor("one two three");
+    mouse(&mut editor, MouseEventKind::Down(MouseButton::Left), 2, 0);
+    mouse(&mut editor, MouseEventKind::Drag(MouseButton::Left), 9, 0);
+    mouse(&mut editor, MouseEventKind::Up(MouseButton::Left), 9, 0);
+    assert!(editor.mode == Mode::Visual);
+    type_keys(&mut editor, "d");
+    assert_eq!(lines(&editor), ["three"]);
+}
+
+#[test]
+fn a_selection_yanked_puts_as_text() {
+    let mut editor = editor("éab");
+    type_keys(&mut editor, "vly$p");
+    assert_eq!(lines(&editor), ["éabéa"]);
+    type_keys(&mut editor, "0P");
+    assert_eq!(lines(&editor), ["éaéabéa"]);
+}
+
+#[test]
+fn the_wheel_scrolls() {
+    let text: Vec<String> = (1..=50).map(|n| n.to_string()).collect();
+    let mut editor = editor(&text.join("\n"));
+    mouse(&mut editor, MouseEventKind::ScrollDown, 0, 0);
+    assert_eq!(editor.viewport_top, 3);
+    mouse(&mut editor, MouseEventKind::ScrollUp, 0, 0);
+    assert_eq!(editor.viewport_top, 0);
+}
+
+#[test]
+fn a_click_in_replace_mode_forgets_what_was_overwritten() {
+    let mut editor = editor("abc\ndef");
+    type_keys(&mut editor, "Rxy");
+    click(&mut editor, 4, 1);
+    assert_eq!(cursor(&editor), (1, 2));
+
+    // Backspace only moves over text it did not overwrite here
+    type_keys(&mut editor, "<BS><BS><Esc>");
+    assert_eq!(lines(&editor), ["xyc", "def"]);
+}
@@ -0,0 +1,49 @@
+use super::{cursor, editor, ex, lines, type_keys};
+
+#[test]
+fn append_after_a_multibyte_character() {
+    let mut editor = editor("éa");
+    ex(&mut editor, ":normal ax").unwrap();
+    assert_eq!(lines(&editor), ["éxa"]);
+
+    let mut editor = super::editor("éa");
+    type_keys(&mut editor, "ay<Esc>");
+    assert_eq!(lines(&editor), ["éya"]);
+}
+
+#[test]
+fn append_insert_and_open_lines() {
+    let mut editor = editor("  ab\ncd");
+    type_keys(&mut editor, "A!<Esc>");
+    assert_eq!(lines(&editor), ["  ab!", "cd"]);
+    type_keys(&mut editor, "I-<Esc>");
+    assert_eq!(lines(&editor), ["  -ab!", "cd"]);
+
+    type_keys(&mut editor, "jonew<Esc>");
+    assert_eq!(lines(&editor), ["  -ab!", "cd", "new"]);
+    type_keys(&mut editor, "kOabove<Esc>");
+    assert_eq!(lines(&editor), ["  -ab!", "above", "cd", "new"]);
+}
+
+#[test]
+fn delete_characters() {
+    let mut editor = editor("éèab");
+    type_keys(&mut editor, "xx");
+    assert_eq!(lines(&editor), ["ab"]);
+
+    // the cursor goes back to the start of the last character
+    let mut editor = super::editor("aé");
+    type_keys(&mut editor, "lx");
+    assert_eq!(lines(&editor), ["a"]);
+    assert_eq!(cursor(&editor), (0, 0));
+}
+
+#[test]
+fn put_lines() {
+    let mut editor = editor("one\ntwo");
+    ex(&mut editor, ":1y").unwrap();
+    type_keys(&mut editor, "jp");
+    assert_eq!(lines(&editor), ["one", "two", "one"]);
+    type_keys(&mut editor, "kkP");
+    assert_eq!(lines(&editor), ["one", "one", "two", "one"]);
+}
@@ -0,0 +1,93 @@
+use super::{editor, error, ex, lines};
+
+fn message(editor: &super::Editor) -> &str {
+    editor.command_bar.message.as_deref().unwrap_or("")
+}
+
+#[test]
+fn booleans() {
+    let mut editor = editor("");
+    ex(&mut editor, ":set nowrap").unwrap();
+    assert!(!editor.wrap);
+    ex(&mut editor, ":set wrap").unwrap();
+    assert!(editor.wrap);
+    ex(&mut editor, ":set invwrap").unwrap();
+    assert!(!editor.wrap);
+    ex(&mut editor, ":set wrap!").unwrap();
+    assert!(editor.wrap);
+    ex(&mut editor, ":set wrap?").unwrap();
+    assert_eq!(message(&editor), "wrap");
+    ex(&mut editor, ":set wrap&").unwrap();
+    assert!(!editor.wrap);
+}
+
+#[test]
+fn numbers_and_text() {
+    let mut editor = editor("");
+    ex(&mut editor, ":set so=5 sbr=>\\ >").unwrap();
+    assert_eq!(editor.scrolloff, 5);
+    assert_eq!(editor.showbreak, "> >");
+    ex(&mut editor, ":set so sbr?").unwrap();
+    assert_eq!(message(&editor), "scrolloff=5  showbreak=> >");
+
+    // without arguments the options that are not at their defaults
+    let mut editor = super::editor("");
+    ex(&mut editor, ":set").unwrap();
+    assert_eq!(message(&editor), "All options are at their defaults");
+    ex(&mut editor, ":set so=5").unwrap();
+    ex(&mut editor, ":set").unwrap();
+    assert_eq!(message(&editor), "scrolloff=5");
+}
+
+#[test]
+fn changes_show_at_once() {
+    let mut editor = editor("a\nb");
+    assert_eq!(editor.gutter_width(), 2);
+    ex(&mut editor, ":set nonu").unwrap();
+    assert_eq!(editor.gutter_width(), 0);
+    ex(&mut editor, ":set nostatusbar").unwrap();
+    assert_eq!(editor.viewport_height(), 23);
+    ex(&mut editor, ":set ts=8").unwrap();
+    assert_eq!(editor.tabs.tabstop, 8);
+}
+
+#[test]
+fn local_and_global_values() {
+    let mut editor = editor("");
+    ex(&mut editor, ":setlocal ts=2").unwrap();
+    ex(&mut editor, ":setglobal ts?").unwrap();
+    assert_eq!(message(&editor), "tabstop=4");
+    ex(&mut editor, ":set ts?").unwrap();
+    assert_eq!(message(&editor), "tabstop=2");
+
+    ex(&mut editor, ":setglobal ts=6").unwrap();
+    assert_eq!(editor.tabs.tabstop, 2);
+}
+
+#[test]
+fn bad_set_commands() {
+    let mut editor = editor("");
+    assert_eq!(error(&mut editor, ":set nothing"), "E518: Unknown option: nothing");
+    assert_eq!(error(&mut editor, ":set nots"), "E518: Unknown option: nots");
+    assert_eq!(error(&mut editor, ":set so=x"), "E521: Number required after =: so=x");
+    assert_eq!(error(&mut editor, ":set ts=0"), "E487: Argument must be positive: ts=0");
+    assert_eq!(error(&mut editor, ":set colors=many"), "E474: Invalid argument: colors=many");
+    assert_eq!(error(&mut editor, ":set wrap=1"), "E474: Invalid argument: wrap=1");
+}
+
+#[test]
+fn retab_sets_tabstop_like_set_does() {
+    let mut editor = editor("\tx");
+    ex(&mut editor, ":set noet").unwrap();
+    // the tab keeps its width of 4 columns
+    ex(&mut editor, ":retab 8").unwrap();
+    assert_eq!(lines(&editor), ["    x"]);
+    ex(&mut editor, ":set ts?").unwrap();
+    assert_eq!(message(&editor), "tabstop=8");
+    ex(&mut editor, ":config ts").unwrap();
+    assert_eq!(message(&editor), "tabstop = 8  (:set)");
+
+    // a later `:set` of another option used to bring the old tab width back
+    ex(&mut editor, ":set sw=2").unwrap();
+    assert_eq!(editor.tabs.tabstop, 8);
+}
@@ -0,0 +1,74 @@
+use super::super::super::buffer::Buffer;
+use super::super::config::Config;
+use super::super::pairs::parse_pairs;
+use super::{cursor, editor, editor_with, ex, lines, type_keys};
+
+#[test]
+fn pairs_are_typed_together() {
+    let mut editor = editor("");
+    type_keys(&mut editor, "ifn(");
+    assert_eq!(lines(&editor), ["fn()"]);
+    assert_eq!(cursor(&editor), (0, 3));
+
+    // the closing character under the cursor is moved over
+    type_keys(&mut editor, "x)");
+    assert_eq!(lines(&editor), ["fn(x)"]);
+    assert_eq!(cursor(&editor), (0, 5));
+}
+
+#[test]
+fn backspace_takes_an_empty_pair() {
+    let mut editor = editor("");
+    type_keys(&mut editor, "i[<BS>");
+    assert_eq!(lines(&editor), [""]);
+    type_keys(&mut editor, "[a<BS><BS>");
+    assert_eq!(lines(&editor), [""]);
+}
+
+#[test]
+fn no_pairs_before_words_or_for_apostrophes() {
+    let mut editor = editor("word");
+    type_keys(&mut editor, "i(");
+    assert_eq!(lines(&editor), ["(word"]);
+
+    let mut editor = super::editor("");
+    type_keys(&mut editor, "idon'");
+    assert_eq!(lines(&editor), ["don'"]);
+    type_keys(&mut editor, " '");
+    assert_eq!(lines(&editor), ["don' ''"]);
+}
+
+#[test]
+fn lists_per_file_type() {
+    assert_eq!(parse_pairs("() <> abc x {}"), [('(', ')'), ('<', '>'), ('{', '}')]);
+
+    let mut config = Config::default();
+    config.apply("auto_pairs_default", "<>").unwrap();
+
+    let mut editor = editor_with(Buffer::from_file(None), config);
+    type_keys(&mut editor, "i<(");
+    assert_eq!(lines(&editor), ["<(>"]);
+
+    // Rust has a list of its own that leaves out `'`
+    let mut buffer = Buffer::from_file(None);
+    buffer.filetype = "rust".to_string();
+    let mut editor = super::editor_on(buffer);
+    type_keys(&mut editor, "i'a");
+    assert_eq!(lines(&editor), ["'a"]);
+
+    let mut buffer = Buffer::from_file(None);
+    buffer.filetype = "python".to_string();
+    let mut config = Config::default();
+    config.apply("auto_pairs_python", "() ''").unwrap();
+    let mut editor = editor_with(buffer, config);
+    type_keys(&mut editor, "i'[");
+    assert_eq!(lines(&editor), ["'['"]);
+}
+
+#[test]
+fn the_option_turns_them_off() {
+    let mut editor = editor("");
+    ex(&mut editor, ":set noap").unwrap();
+    type_keys(&mut editor, "i(\"<BS>");
+    assert_eq!(lines(&editor), ["("]);
+}
@@ -0,0 +1,62 @@
+use crossterm::style::{Attribute, Color};
+
+use super::super::theme::palette::ColorDepth;
+use super::{editor, error, ex};
+
+#[test]
+fn depths_by_name() {
+    assert!(ColorDepth::parse("truecolor") == Some(ColorDepth::TrueColor));
+    assert!(ColorDepth::parse("24bit") == Some(ColorDepth::TrueColor));
+    assert!(ColorDepth::parse("256") == Some(ColorDepth::Ansi256));
+    assert!(ColorDepth::parse("16") == Some(ColorDepth::Ansi16));
+    assert!(ColorDepth::parse("mono") == Some(ColorDepth::Mono));
+    assert!(ColorDepth::parse("many").is_none());
+}
+
+#[test]
+fn colours_closest_to_rgb() {
+    let rgb = |r, g, b| Color::Rgb { r, g, b };
+    assert_eq!(ColorDepth::TrueColor.convert((1, 2, 3)), rgb(1, 2, 3));
+
+    // the colour cube and the grey ramp of the 256 colour palette
+    assert_eq!(ColorDepth::Ansi256.convert((255, 0, 0)), Color::AnsiValue(196));
+    assert_eq!(ColorDepth::Ansi256.convert((0, 0, 0)), Color::AnsiValue(16));
+    assert_eq!(ColorDepth::Ansi256.convert((95, 135, 175)), Color::AnsiValue(67));
+    assert_eq!(ColorDepth::Ansi256.convert((128, 128, 128)), Color::AnsiValue(244));
+
+    assert_eq!(ColorDepth::Ansi16.convert((250, 10, 10)), Color::Red);
+    assert_eq!(ColorDepth::Ansi16.convert((120, 0, 120)), Color::DarkMagenta);
+    assert_eq!(ColorDepth::Ansi16.convert((200, 200, 200)), Color::Grey);
+
+    assert_eq!(ColorDepth::Mono.convert((250, 10, 10)), Color::Reset);
+}
+
+#[test]
+fn the_option_sets_the_depth() {
+    let mut editor = editor("");
+    ex(&mut editor, ":set colors=256").unwrap();
+    assert!(editor.theme.depth == ColorDepth::Ansi256);
+    assert_eq!(editor.theme.color("text"), Color::AnsiValue(231));
+
+    // a theme switched to keeps the depth
+    ex(&mut editor, ":colorscheme gruvbox").unwrap();
+    assert!(editor.theme.depth == ColorDepth::Ansi256);
+    assert!(editor.theme.background().is_some());
+
+    assert!(error(&mut editor, ":set colors=many").starts_with("E474"));
+}
+
+#[test]
+fn monochrome_drops_colours_and_reverses_the_bars() {
+    let mut editor = editor("");
+    ex(&mut editor, ":colorscheme gruvbox").unwrap();
+    ex(&mut editor, ":set colors=mono").unwrap();
+    assert_eq!(editor.theme.background(), None);
+    assert_eq!(editor.theme.color("keyword"), Color::Reset);
+
+    let bar = editor.theme.bar("x", "status_bar_background", "status_bar_text");
+    assert!(bar.style().attributes.has(Attribute::Reverse));
+    ex(&mut editor, ":set colors=16").unwrap();
+    let bar = editor.theme.bar("x", "status_bar_background", "status_bar_text");
+    assert!(!bar.style().attributes.has(Attribute::Reverse));
+}
@@ -0,0 +1,103 @@
+use crossterm::event::Event;
+
+use super::super::{Editor, Mode};
+use super::{cursor, editor, lines, type_keys};
+
+fn paste(editor: &mut Editor, text: &str) {
+    editor.process_event(Event::Paste(text.to_string())).unwrap();
+}
+
+#[test]
+fn paste_in_insert_mode() {
+    let mut editor = editor("ab");
+    type_keys(&mut editor, "a");
+    paste(&mut editor, "x(\r\ny");
+    assert_eq!(lines(&editor), ["ax(", "yb"]);
+    assert_eq!(cursor(&editor), (1, 1));
+
+    // the paste is undone on its own
+    type_keys(&mut editor, "z<Esc>u");
+    assert_eq!(lines(&editor), ["ax(", "yb"]);
+    type_keys(&mut editor, "u");
+    assert_eq!(lines(&editor), ["ab"]);
+}
+
+#[test]
+fn paste_in_normal_mode_puts_before_the_cursor() {
+    let mut editor = editor("ab");
+    type_keys(&mut editor, "l");
+    paste(&mut editor, "xyz");
+    assert_eq!(lines(&editor), ["axyzb"]);
+    assert_eq!(cursor(&editor), (0, 3));
+    type_keys(&mut editor, "u");
+    assert_eq!(lines(&editor), ["ab"]);
+}
+
+#[test]
+fn paste_on_the_command_line() {
+    let mut editor = editor("ab");
+    type_keys(&mut editor, ":");
+    paste(&mut editor, "s/a/\nc/");
+    type_keys(&mut editor, "<CR>");
+    assert_eq!(lines(&editor), ["ab"]);
+    assert_eq!(editor.command_bar.command, ":");
+}
+
+#[test]
+fn paste_in_replace_mode_overwrites() {
+    let mut editor = editor("abcdef");
+    type_keys(&mut editor, "R");
+    paste(&mut editor, "xéz");
+    assert_eq!(lines(&editor), ["xézdef"]);
+    assert_eq!(cursor(&editor), (0, 4));
+
+    // Backspace brings back what the paste overwrote
+    type_keys(&mut editor, "<BS><BS>");
+    assert_eq!(lines(&editor), ["xbcdef"]);
+
+    // past the end of the line the text is added
+    let mut editor = super::editor("ab");
+    type_keys(&mut editor, "lR");
+    paste(&mut editor, "xyz");
+    assert_eq!(lines(&editor), ["axyz"]);
+}
+
+#[test]
+fn paste_of_several_lines_in_replace_mode() {
+    let mut editor = editor("abcd\nefgh");
+    type_keys(&mut editor, "R");
+    paste(&mut editor, "x\ny");
+    assert_eq!(lines(&editor), ["x", "ycd", "efgh"]);
+    assert_eq!(cursor(&editor), (1, 1));
+
+    type_keys(&mut editor, "<BS><BS><Esc>");
+    assert_eq!(lines(&editor), ["x", "bcd", "efgh"]);
+    type_keys(&mut editor, "u");
+    assert_eq!(lines(&editor), ["x", "ycd", "efgh"]);
+    type_keys(&mut editor, "u");
+    assert_eq!(lines(&editor), ["abcd", "efgh"]);
+}
+
+#[test]
+fn paste_in_visual_mode_replaces_the_selection() {
+    let mut editor = editor("hello world");
+    type_keys(&mut editor, "lv3l");
+    paste(&mut editor, "i");
+    assert_eq!(lines(&editor), ["hi world"]);
+    assert_eq!(cursor(&editor), (0, 1));
+    assert!(editor.mode == Mode::Normal);
+    // the registers keep what they had
+    assert!(!editor.registers.contains_key(&'"'));
+
+    type_keys(&mut editor, "u");
+    assert_eq!(lines(&editor), ["hello world"]);
+}
+
+#[test]
+fn paste_of_several_lines_over_a_selection() {
+    let mut editor = editor("ab\ncd");
+    type_keys(&mut editor, "lvj");
+    paste(&mut editor, "1\n2");
+    assert_eq!(lines(&editor), ["a1", "2"]);
+    assert_eq!(cursor(&editor), (1, 0));
+}
@@ -0,0 +1,97 @@
+use super::super::super::buffer::Buffer;
+use super::{cursor, editor, editor_on, error, ex, lines, type_keys, TempDir};
+
+const TEXT: &str = "one\ntwo\nthree\nfour\nfive";
+
+#[test]
+fn numbers_dot_dollar_and_percent() {
+    let mut editor = editor(TEXT);
+    ex(&mut editor, ":2,3d").unwrap();
+    assert_eq!(lines(&editor), ["one", "four", "five"]);
+    assert_eq!(cursor(&editor), (1, 0));
+
+    ex(&mut editor, ":.,$d").unwrap();
+    assert_eq!(lines(&editor), ["one"]);
+
+    let mut editor = super::editor(TEXT);
+    ex(&mut editor, ":%d").unwrap();
+    assert_eq!(lines(&editor), [""]);
+}
+
+#[test]
+fn relative_offsets() {
+    let mut editor = editor(TEXT);
+    type_keys(&mut editor, "j");
+    ex(&mut editor, ":.+1,$-1d").unwrap();
+    assert_eq!(lines(&editor), ["one", "two", "five"]);
+}
+
+#[test]
+fn marks_and_patterns() {
+    let mut editor = editor(TEXT);
+    type_keys(&mut editor, "jmajjmb");
+    ex(&mut editor, ":'a,'by").unwrap();
+    ex(&mut editor, ":$put").unwrap();
+    assert_eq!(lines(&editor), ["one", "two", "three", "four", "five", "two", "three", "four"]);
+
+    let mut editor = super::editor(TEXT);
+    ex(&mut editor, ":/th/,/fi/d").unwrap();
+    assert_eq!(lines(&editor), ["one", "two"]);
+}
+
+#[test]
+fn move_copy_and_shift() {
+    let mut editor = editor(TEXT);
+    ex(&mut editor, ":1,2m$").unwrap();
+    assert_eq!(lines(&editor), ["three", "four", "five", "one", "two"]);
+    ex(&mut editor, ":1t0").unwrap();
+    assert_eq!(lines(&editor), ["three", "three", "four", "five", "one", "two"]);
+    ex(&mut editor, ":2co$").unwrap();
+    assert_eq!(lines(&editor)[6], "three");
+
+    let mut editor = super::editor(TEXT);
+    ex(&mut editor, ":2,3>").unwrap();
+    assert_eq!(lines(&editor)[1..3], ["    two", "    three"]);
+    ex(&mut editor, ":3<").unwrap();
+    assert_eq!(lines(&editor)[1..3], ["    two", "three"]);
+}
+
+#[test]
+fn normal_on_a_range() {
+    let mut editor = editor(TEXT);
+    ex(&mut editor, ":2,4normal A;").unwrap();
+    assert_eq!(lines(&editor), ["one", "two;", "three;", "four;", "five"]);
+}
+
+#[test]
+fn write_a_range_into_another_file() {
+    let dir = TempDir::new();
+    let path = dir.path().join("part.txt");
+    let mut editor = editor(TEXT);
+    ex(&mut editor, &format!(":2,3w {}", path.display())).unwrap();
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "two\nthree\n");
+}
+
+#[test]
+fn bad_ranges_are_errors() {
+    let mut editor = editor(TEXT);
+    assert!(error(&mut editor, ":0d").starts_with("E16"));
+    assert!(error(&mut editor, ":4,9d").starts_with("E16"));
+    assert!(error(&mut editor, ":/nowhere/d").starts_with("E486"));
+    assert!(error(&mut editor, ":'q").starts_with("E20"));
+    assert!(error(&mut editor, ":3,4m3").starts_with("E134"));
+    assert_eq!(lines(&editor), TEXT.lines().collect::<Vec<_>>());
+}
+
+#[test]
+fn empty_file_has_one_line() {
+    let dir = TempDir::new();
+    let path = dir.file("empty.txt", "");
+    let buffer = Buffer::from_file(Some(path.display().to_string()));
+    assert_eq!(buffer.lines, [""]);
+
+    // moving on it used to panic
+    let mut editor = editor_on(buffer);
+    type_keys(&mut editor, "jk");
+    assert_eq!(cursor(&editor), (0, 0));
+}
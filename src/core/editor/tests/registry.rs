@@ -0,0 +1,69 @@
+use super::super::ex::commands::{resolve, split_words};
+use super::{editor, error, ex, lines, type_keys};
+
+#[test]
+fn names_and_abbreviations() {
+    assert_eq!(resolve("d").unwrap().name, "delete");
+    assert_eq!(resolve("del").unwrap().name, "delete");
+    assert_eq!(resolve("delete").unwrap().name, "delete");
+    assert_eq!(resolve("w").unwrap().name, "write");
+    assert_eq!(resolve("wq").unwrap().name, "wq");
+    assert_eq!(resolve("delc").unwrap().name, "delcommand");
+    assert!(resolve("deletes").is_none());
+    assert!(resolve("conf").is_none());
+    assert!(resolve("whatever").is_none());
+}
+
+#[test]
+fn what_commands_refuse() {
+    let mut editor = editor("one\ntwo");
+    assert_eq!(error(&mut editor, ":whatever"), "E492: Not an editor command: whatever");
+    assert_eq!(error(&mut editor, ":2whatever!"), "E492: Not an editor command: whatever!");
+    assert_eq!(error(&mut editor, ":1,2undo"), "E481: No range allowed");
+    assert_eq!(error(&mut editor, ":yank!"), "E477: No ! allowed");
+    assert_eq!(error(&mut editor, ":undo now"), "E488: Trailing characters: now");
+    assert_eq!(error(&mut editor, ":colo a b"), "E488: Trailing characters: b");
+    assert_eq!(error(&mut editor, ":w a b"), "E172: Only one file name allowed");
+    assert_eq!(lines(&editor), ["one", "two"]);
+}
+
+#[test]
+fn words_with_quotes_and_escapes() {
+    assert_eq!(split_words("a  b").unwrap(), ["a", "b"]);
+    assert_eq!(split_words("\"a b\" c").unwrap(), ["a b", "c"]);
+    assert_eq!(split_words("'a \\ b'").unwrap(), ["a \\ b"]);
+    assert_eq!(split_words("a\\ b").unwrap(), ["a b"]);
+    assert_eq!(split_words("\"\"").unwrap(), [""]);
+    assert_eq!(split_words("").unwrap(), Vec::<String>::new());
+    assert_eq!(split_words("\"a b").unwrap_err().to_string(), "E114: Missing quote: \"a b");
+}
+
+#[test]
+fn help_for_one_command() {
+    let mut editor = editor("");
+    ex(&mut editor, ":help delete").unwrap();
+    assert_eq!(editor.command_bar.message.as_deref(), Some(":[range]d[elete] [x] [count]  Delete lines into register x"));
+    ex(&mut editor, ":h :wq").unwrap();
+    assert_eq!(editor.command_bar.message.as_deref(), Some(":wq[!] [file]  Write the buffer and quit"));
+    assert_eq!(error(&mut editor, ":help nothing"), "E149: Sorry, no help for nothing");
+}
+
+#[test]
+fn completion_of_names_and_arguments() {
+    let mut editor = editor("");
+    let mut complete = |line: &str| {
+        type_keys(&mut editor, &format!("{}<Tab>", line));
+        let command = editor.command_bar.command.clone();
+        type_keys(&mut editor, "<Esc>");
+        command
+    };
+
+    assert_eq!(complete(":delc"), ":delcommand");
+    assert_eq!(complete(":2,3dele"), ":2,3delete");
+    assert_eq!(complete(":syntax of"), ":syntax off");
+    assert_eq!(complete(":set nowr"), ":set nowrap");
+    assert_eq!(complete(":help wq"), ":help wq");
+    assert_eq!(complete(":xyz"), ":xyz");
+    // nothing follows a name without a space
+    assert_eq!(complete(":setx"), ":setx");
+}
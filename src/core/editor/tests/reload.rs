@@ -0,0 +1,115 @@
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+use super::super::config::ConfigOptions;
+use super::super::reload::ConfigWatch;
+use super::super::Editor;
+use super::{editor_loading, ex, lines, type_keys, TempDir};
+
+// an editor on a file of the directory with the user's configuration file holding `conf`
+fn editor_with_conf(dir: &TempDir, conf: &str) -> (Editor, PathBuf) {
+    std::fs::create_dir_all(dir.path().join("user/themes")).unwrap();
+    let conf_path = dir.file("user/edit.conf", conf);
+    let file = dir.file("file.txt", "one\ntwo\n");
+    let options = ConfigOptions { dir: Some(dir.path().join("user")), ..ConfigOptions::default() };
+    (editor_loading(&file, &options), conf_path)
+}
+
+fn message(editor: &Editor) -> &str {
+    editor.command_bar.message.as_deref().unwrap_or("")
+}
+
+#[test]
+fn source_reads_every_layer_again() {
+    let dir = TempDir::new();
+    let (mut editor, conf) = editor_with_conf(&dir, "scrolloff = 2\n");
+    assert_eq!(editor.scrolloff, 2);
+
+    std::fs::write(&conf, "scrolloff = 6\nnmap Q x\n").unwrap();
+    ex(&mut editor, ":source").unwrap();
+    assert_eq!(message(&editor), "Configuration reloaded");
+    assert_eq!(editor.scrolloff, 6);
+    type_keys(&mut editor, "Q");
+    assert_eq!(lines(&editor), ["ne", "two"]);
+
+    // a mapping taken out of the file goes away, one made while editing stays
+    ex(&mut editor, ":nmap W x").unwrap();
+    std::fs::write(&conf, "").unwrap();
+    ex(&mut editor, ":source").unwrap();
+    assert_eq!(editor.scrolloff, 1);
+    type_keys(&mut editor, "QW");
+    assert_eq!(lines(&editor), ["e", "two"]);
+}
+
+#[test]
+fn options_set_while_editing_stay_unless_the_file_sets_them() {
+    let dir = TempDir::new();
+    let (mut editor, conf) = editor_with_conf(&dir, "");
+    ex(&mut editor, ":set ts=7 so=9").unwrap();
+
+    std::fs::write(&conf, "scrolloff = 3\n").unwrap();
+    ex(&mut editor, ":source").unwrap();
+    assert_eq!(editor.tabs.tabstop, 7);
+    assert_eq!(editor.scrolloff, 3);
+}
+
+#[test]
+fn problems_are_shown_after_a_reload() {
+    let dir = TempDir::new();
+    let (mut editor, conf) = editor_with_conf(&dir, "nothing = 1\n");
+    assert_eq!(message(&editor), format!("{}:1: unknown setting `nothing`", conf.display()));
+
+    std::fs::write(&conf, "nothing = 1\ntheme = missing\n").unwrap();
+    ex(&mut editor, ":source").unwrap();
+    assert_eq!(message(&editor), format!("{}:1: unknown setting `nothing` (and 1 more)", conf.display()));
+    assert_eq!(editor.theme.name, "default");
+}
+
+#[test]
+fn themes_and_colours_come_back_on_reload() {
+    let dir = TempDir::new();
+    let (mut editor, conf) = editor_with_conf(&dir, "");
+    let theme = dir.file("user/themes/mine.theme", "keyword = #010101\n");
+    std::fs::write(&conf, "theme = mine\neditor_font_color = (9, 9, 9)\n").unwrap();
+    ex(&mut editor, ":source").unwrap();
+    assert_eq!(editor.theme.name, "mine");
+    assert_eq!(editor.theme.rgb("keyword"), Some((1, 1, 1)));
+    assert_eq!(editor.theme.rgb("text"), Some((9, 9, 9)));
+
+    std::fs::write(&theme, "keyword = #020202\n").unwrap();
+    ex(&mut editor, ":source").unwrap();
+    assert_eq!(editor.theme.rgb("keyword"), Some((2, 2, 2)));
+    assert_eq!(editor.theme.rgb("text"), Some((9, 9, 9)));
+}
+
+#[test]
+fn source_one_more_file() {
+    let dir = TempDir::new();
+    let (mut editor, _) = editor_with_conf(&dir, "scrolloff = 2\n");
+    let extra = dir.file("extra.conf", "sidescroll = 4\n");
+    ex(&mut editor, &format!(":source {}", extra.display())).unwrap();
+    assert_eq!(message(&editor), format!("Sourced {}", extra.display()));
+    assert_eq!((editor.scrolloff, editor.sidescroll), (2, 4));
+
+    ex(&mut editor, ":source nothing.conf").unwrap();
+    assert!(message(&editor).starts_with("nothing.conf: "));
+}
+
+#[test]
+fn the_watch_sees_files_change_and_appear() {
+    let dir = TempDir::new();
+    let conf = dir.file("edit.conf", "");
+    let later = dir.path().join("later.conf");
+    let mut watch = ConfigWatch::new(vec![conf.clone(), later.clone()]);
+    assert!(!watch.changed());
+
+    let file = std::fs::File::options().write(true).open(&conf).unwrap();
+    file.set_modified(SystemTime::now() + Duration::from_secs(10)).unwrap();
+    assert!(watch.changed());
+    assert!(!watch.changed());
+
+    std::fs::write(&later, "").unwrap();
+    assert!(watch.changed());
+    std::fs::remove_file(&later).unwrap();
+    assert!(watch.changed());
+}
@@ -0,0 +1,79 @@
+use super::super::Mode;
+use super::{cursor, editor, ex, lines, type_keys};
+
+#[test]
+fn replace_one_character() {
+    let mut editor = editor("aébc");
+    type_keys(&mut editor, "lrX");
+    assert_eq!(lines(&editor), ["aXbc"]);
+    assert_eq!(cursor(&editor), (0, 1));
+
+    type_keys(&mut editor, "hrè");
+    assert_eq!(lines(&editor), ["èXbc"]);
+}
+
+#[test]
+fn replace_with_a_count() {
+    let mut editor = editor("aébc");
+    type_keys(&mut editor, "l3rX");
+    assert_eq!(lines(&editor), ["aXXX"]);
+    assert_eq!(cursor(&editor), (0, 3));
+
+    // too few characters left changes nothing
+    let mut editor = super::editor("aébc");
+    type_keys(&mut editor, "l4rX");
+    assert_eq!(lines(&editor), ["aébc"]);
+
+    let mut editor = super::editor("ab\ncd");
+    ex(&mut editor, ":%normal 2rè").unwrap();
+    assert_eq!(lines(&editor), ["èè", "èè"]);
+}
+
+#[test]
+fn replace_mode_overwrites_and_backspace_restores() {
+    let mut editor = editor("héllo");
+    type_keys(&mut editor, "lRab");
+    assert!(editor.mode == Mode::Replace);
+    assert_eq!(lines(&editor), ["hablo"]);
+
+    // past the end of the line characters are added
+    type_keys(&mut editor, "cdef");
+    assert_eq!(lines(&editor), ["habcdef"]);
+    type_keys(&mut editor, "<BS><BS><BS><BS>");
+    assert_eq!(lines(&editor), ["hablo"]);
+    type_keys(&mut editor, "<BS><BS><BS>");
+    assert_eq!(lines(&editor), ["héllo"]);
+    assert_eq!(cursor(&editor), (0, 0));
+
+    type_keys(&mut editor, "<Esc>");
+    assert!(editor.mode == Mode::Normal);
+}
+
+#[test]
+fn counts_dot_and_undo() {
+    let mut editor = editor("12345\n12345");
+    type_keys(&mut editor, "2Rab<Esc>");
+    assert_eq!(lines(&editor), ["abab5", "12345"]);
+
+    type_keys(&mut editor, "jhhhh.");
+    assert_eq!(lines(&editor), ["abab5", "abab5"]);
+
+    type_keys(&mut editor, "u");
+    assert_eq!(lines(&editor), ["abab5", "12345"]);
+    type_keys(&mut editor, "u");
+    assert_eq!(lines(&editor), ["12345", "12345"]);
+}
+
+#[test]
+fn counts_repeat_deletes_and_puts() {
+    let mut editor = editor("éèab");
+    type_keys(&mut editor, "2x");
+    assert_eq!(lines(&editor), ["ab"]);
+    type_keys(&mut editor, "u");
+    assert_eq!(lines(&editor), ["éèab"]);
+
+    let mut editor = super::editor("one\ntwo");
+    ex(&mut editor, ":1y").unwrap();
+    type_keys(&mut editor, "j2p");
+    assert_eq!(lines(&editor), ["one", "two", "one", "one"]);
+}
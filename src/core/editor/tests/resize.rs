@@ -0,0 +1,63 @@
+use crossterm::event::Event;
+
+use super::super::Editor;
+use super::{cursor, editor, ex, type_keys};
+
+fn long_file() -> Editor {
+    let text: Vec<String> = (1..=100).map(|n| format!("line {} {}", n, "x".repeat(100))).collect();
+    editor(&text.join("\n"))
+}
+
+fn resize(editor: &mut Editor, width: u16, height: u16) {
+    editor.process_event(Event::Resize(width, height)).unwrap();
+    editor.fit_cursor_in_view();
+}
+
+#[test]
+fn the_cursor_line_stays_in_the_window() {
+    let mut editor = long_file();
+    type_keys(&mut editor, "20j");
+    resize(&mut editor, 80, 10);
+    assert_eq!(editor.size, (80, 10));
+    assert_eq!(editor.viewport_height(), 8);
+
+    let top = editor.viewport_top as usize;
+    assert!(top <= 20 && 20 <= editor.last_visible_line(top));
+    assert_eq!(cursor(&editor).0, 20);
+    assert!(editor.cursor_screen_pos().1 < 8);
+    assert_eq!(editor.screen_rows().len(), 8);
+
+    // growing again keeps the window where it was
+    resize(&mut editor, 80, 40);
+    assert_eq!(editor.viewport_top as usize, top);
+    assert_eq!(editor.screen_rows().len(), 38);
+}
+
+#[test]
+fn narrower_windows_scroll_sideways_and_wrap_more() {
+    let mut editor = long_file();
+    ex(&mut editor, ":set nonu").unwrap();
+    type_keys(&mut editor, "60l");
+    editor.fit_cursor_in_view();
+    assert_eq!(editor.viewport_left, 0);
+
+    resize(&mut editor, 40, 24);
+    assert!(editor.viewport_left > 0);
+    assert!(editor.cursor_screen_pos().0 < 40);
+    assert_eq!(editor.screen_rows()[0].text.chars().count(), 40);
+
+    ex(&mut editor, ":set wrap").unwrap();
+    assert_eq!(editor.line_height(0), 3);
+    resize(&mut editor, 120, 24);
+    assert_eq!(editor.line_height(0), 1);
+}
+
+#[test]
+fn a_tiny_window_still_has_a_row() {
+    let mut editor = long_file();
+    type_keys(&mut editor, "50j");
+    resize(&mut editor, 1, 1);
+    assert_eq!(editor.viewport_height(), 1);
+    assert_eq!(editor.viewport_top, 50);
+    assert_eq!(editor.screen_rows().len(), 1);
+}
@@ -0,0 +1,87 @@
+use super::{cursor, editor, ex, type_keys};
+
+use super::super::Editor;
+
+// a file much longer than the 22 rows of text the window has
+fn long_file() -> Editor {
+    let text: Vec<String> = (1..=100).map(|n| format!("line {}", n)).collect();
+    editor(&text.join("\n"))
+}
+
+fn top(editor: &Editor) -> usize {
+    editor.viewport_top as usize
+}
+
+#[test]
+fn half_pages() {
+    let mut editor = long_file();
+    assert_eq!(editor.viewport_height(), 22);
+
+    // the cursor moves as far as the window, then `scrolloff` pulls it one line further
+    type_keys(&mut editor, "<C-d>");
+    assert_eq!((top(&editor), cursor(&editor).0), (11, 12));
+    type_keys(&mut editor, "<C-u>");
+    assert_eq!((top(&editor), cursor(&editor).0), (0, 1));
+
+    // a count sticks for the next ones
+    type_keys(&mut editor, "5<C-d><C-d>");
+    assert_eq!((top(&editor), cursor(&editor).0), (10, 11));
+}
+
+#[test]
+fn whole_pages_keep_two_lines() {
+    let mut editor = long_file();
+    type_keys(&mut editor, "<C-f>");
+    assert_eq!(top(&editor), 20);
+    // the cursor is pulled in, `scrolloff` lines away from the top
+    assert_eq!(cursor(&editor).0, 21);
+
+    type_keys(&mut editor, "<C-b>");
+    assert_eq!(top(&editor), 0);
+    // and pushed up again, `scrolloff` lines away from the bottom
+    assert_eq!(cursor(&editor).0, 20);
+}
+
+#[test]
+fn single_lines_leave_the_cursor_alone() {
+    let mut editor = long_file();
+    type_keys(&mut editor, "10j3<C-e>");
+    assert_eq!((top(&editor), cursor(&editor).0), (3, 10));
+    type_keys(&mut editor, "9<C-e>");
+    assert_eq!((top(&editor), cursor(&editor).0), (12, 13));
+    type_keys(&mut editor, "<C-y>");
+    assert_eq!((top(&editor), cursor(&editor).0), (11, 13));
+}
+
+#[test]
+fn align_the_cursor_line() {
+    let mut editor = long_file();
+    type_keys(&mut editor, "50Gzt");
+    assert_eq!((top(&editor), cursor(&editor).0), (48, 49));
+    type_keys(&mut editor, "zz");
+    assert_eq!(top(&editor), 39);
+    type_keys(&mut editor, "zb");
+    assert_eq!(top(&editor), 29);
+
+    // with a count the line moves there first
+    type_keys(&mut editor, "20zt");
+    assert_eq!((top(&editor), cursor(&editor).0), (18, 19));
+}
+
+#[test]
+fn scrolloff_keeps_lines_around_the_cursor() {
+    let mut editor = long_file();
+    ex(&mut editor, ":set so=5").unwrap();
+    type_keys(&mut editor, "20j");
+    assert_eq!((top(&editor), cursor(&editor).0), (4, 20));
+
+    // near the end of the file there is nothing to keep
+    type_keys(&mut editor, "G");
+    assert_eq!((top(&editor), cursor(&editor).0), (78, 99));
+
+    // a huge value keeps the cursor line in the middle
+    ex(&mut editor, ":set so=999").unwrap();
+    type_keys(&mut editor, "50G");
+    type_keys(&mut editor, "j");
+    assert_eq!(top(&editor), 39);
+}
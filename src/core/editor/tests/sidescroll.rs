@@ -0,0 +1,90 @@
+use super::{cursor, editor, editor_on, ex, type_keys, TempDir};
+
+use super::super::super::buffer::Buffer;
+use super::super::Editor;
+
+// types `keys` and fits the window around the cursor, like the main loop does before
+// every draw
+fn keys(editor: &mut Editor, keys: &str) {
+    type_keys(editor, keys);
+    editor.fit_cursor_in_view();
+}
+
+// without line numbers all 80 columns show text
+fn long_line_editor() -> Editor {
+    let mut editor = editor(&long_line());
+    ex(&mut editor, ":set nonu").unwrap();
+    editor
+}
+
+fn long_line() -> String {
+    (0..200).map(|n| char::from(b'a' + (n % 26) as u8)).collect()
+}
+
+#[test]
+fn the_cursor_stays_on_the_line() {
+    let mut editor = editor("abc\nlonger line");
+    keys(&mut editor, "10l");
+    assert_eq!(cursor(&editor), (0, 2));
+    keys(&mut editor, "$");
+    assert_eq!(cursor(&editor), (0, 2));
+}
+
+#[test]
+fn up_and_down_keep_the_column() {
+    let mut editor = editor("long line\nab\nanother line");
+    type_keys(&mut editor, "6l");
+    type_keys(&mut editor, "j");
+    assert_eq!(cursor(&editor), (1, 1));
+    type_keys(&mut editor, "j");
+    assert_eq!(cursor(&editor), (2, 6));
+}
+
+#[test]
+fn half_a_window_at_a_time() {
+    let mut editor = long_line_editor();
+    // the last column is kept free so the cursor is never under the `>`
+    keys(&mut editor, "78l");
+    assert_eq!(editor.viewport_left, 0);
+    keys(&mut editor, "l");
+    assert_eq!(editor.viewport_left, 39);
+    keys(&mut editor, "0");
+    assert_eq!(editor.viewport_left, 0);
+}
+
+#[test]
+fn sidescroll_and_sidescrolloff() {
+    let mut editor = long_line_editor();
+    ex(&mut editor, ":set ss=1 siso=5").unwrap();
+    keys(&mut editor, "74l");
+    assert_eq!(editor.viewport_left, 0);
+    keys(&mut editor, "l");
+    assert_eq!(editor.viewport_left, 1);
+    keys(&mut editor, "$");
+    assert_eq!(editor.viewport_left, 120);
+}
+
+#[test]
+fn markers_for_text_cut_off() {
+    let mut editor = long_line_editor();
+    let text = long_line();
+    assert_eq!(editor.visible_text(&text), format!("{}>", &text[..79]));
+
+    keys(&mut editor, "100l");
+    let shown = editor.visible_text(&text);
+    assert_eq!(shown.chars().count(), 80);
+    assert!(shown.starts_with('<') && shown.ends_with('>'));
+    assert_eq!(&shown[1..79], &text[editor.viewport_left as usize + 1..][..78]);
+
+    // short lines scrolled out of view only show where they were cut
+    assert_eq!(editor.visible_text("abc").trim_end(), "<");
+}
+
+#[test]
+fn an_empty_file_fits() {
+    let dir = TempDir::new();
+    let path = dir.file("empty.txt", "");
+    let mut editor = editor_on(Buffer::from_file(Some(path.display().to_string())));
+    keys(&mut editor, "G");
+    assert_eq!(cursor(&editor), (0, 0));
+}
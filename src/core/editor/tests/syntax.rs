@@ -0,0 +1,105 @@
+use super::super::super::syntax::language::{Language, BUILTIN};
+use super::super::super::syntax::{Highlight, Syntax};
+use super::TempDir;
+
+use Highlight::{Comment, Heading, Keyword, Normal, Number, Type};
+
+// the highlight of every byte of each of `lines`, for a file called `file`
+fn highlight(syntax: &mut Syntax, file: &str, lines: &[&str]) -> Vec<Vec<Highlight>> {
+    let lines: Vec<String> = lines.iter().map(|l| l.to_string()).collect();
+    syntax.update("", Some(file), &lines, lines.len() - 1);
+    lines.iter().enumerate().map(|(i, l)| syntax.line_highlights(i, l.len())).collect()
+}
+
+// `count` bytes of `highlight`
+fn run(highlight: Highlight, count: usize) -> Vec<Highlight> {
+    vec![highlight; count]
+}
+
+#[test]
+fn the_builtin_definitions_parse() {
+    let names: Vec<String> = BUILTIN.iter().map(|d| Language::parse(d).unwrap().name).collect();
+    assert_eq!(names, ["rust", "toml", "markdown", "sh", "json"]);
+}
+
+#[test]
+fn words_strings_numbers_and_comments() {
+    let (mut syntax, errors) = Syntax::load(true, None);
+    assert!(errors.is_empty());
+    let lines = highlight(&mut syntax, "main.rs", &["let x: u8 = 42; // \"no\"", "\"a\\\"b\""]);
+
+    let mut line = run(Keyword, 3);
+    line.extend(run(Normal, 4));
+    line.extend(run(Type, 2));
+    line.extend(run(Normal, 3));
+    line.extend(run(Number, 2));
+    line.extend(run(Normal, 2));
+    line.extend(run(Comment, 7));
+    assert_eq!(lines[0], line);
+    // an escaped quote does not end the string
+    assert_eq!(lines[1], run(Highlight::String, 6));
+}
+
+#[test]
+fn nested_block_comments_go_over_lines() {
+    let (mut syntax, _) = Syntax::load(true, None);
+    let lines = highlight(&mut syntax, "lib.rs", &["/* a /* b */", "c */ fn"]);
+    assert_eq!(lines[0], run(Comment, 12));
+    assert_eq!(lines[1][..4], run(Comment, 4));
+    assert_eq!(lines[1][5..], run(Keyword, 2));
+}
+
+#[test]
+fn edits_above_change_the_lines_below() {
+    let (mut syntax, _) = Syntax::load(true, None);
+    let lines = highlight(&mut syntax, "lib.rs", &["x", "fn"]);
+    assert_eq!(lines[1], run(Keyword, 2));
+
+    // opening a comment on the first line reaches the unchanged second one
+    let lines = highlight(&mut syntax, "lib.rs", &["/*", "fn"]);
+    assert_eq!(lines[1], run(Comment, 2));
+}
+
+#[test]
+fn headings_and_unknown_files() {
+    let (mut syntax, _) = Syntax::load(true, None);
+    let lines = highlight(&mut syntax, "README.md", &["# Title", "text"]);
+    assert_eq!(lines[0], run(Heading, 7));
+    assert_eq!(lines[1], run(Normal, 4));
+
+    let lines = highlight(&mut syntax, "notes.txt", &["fn 1"]);
+    assert_eq!(lines[0], run(Normal, 4));
+
+    let (mut syntax, _) = Syntax::load(false, None);
+    let lines = highlight(&mut syntax, "main.rs", &["fn"]);
+    assert_eq!(lines[0], run(Normal, 2));
+}
+
+#[test]
+fn definition_files_add_and_replace_languages() {
+    let dir = TempDir::new();
+    dir.file("lisp.syntax", "name = lisp\nextensions = el\nkeywords = defun\nline_comment = ;");
+    dir.file("rust.syntax", "name = rust\nextensions = rs\nkeywords = only");
+    dir.file("bad.syntax", "name = bad\ncolour = red");
+    dir.file("notes.txt", "not a definition");
+
+    let (mut syntax, errors) = Syntax::load(true, Some(dir.path()));
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].contains("bad.syntax") && errors[0].contains("unknown key `colour`"));
+
+    let lines = highlight(&mut syntax, "init.el", &["(defun f) ; x"]);
+    assert_eq!(lines[0][1..6], run(Keyword, 5));
+    assert_eq!(lines[0][10..], run(Comment, 3));
+
+    let lines = highlight(&mut syntax, "main.rs", &["fn only"]);
+    assert_eq!(lines[0], [run(Normal, 3), run(Keyword, 4)].concat());
+}
+
+#[test]
+fn bad_definitions() {
+    let error = |definition: &str| Language::parse(definition).err().unwrap().to_string();
+    assert!(error("keywords = a").contains("no name"));
+    assert!(Language::parse("name = x\nblock_comment = /*").is_err());
+    assert!(Language::parse("name = x\nnumbers = yes").is_err());
+    assert!(error("name = x\nkeywords").contains("line 2"));
+}
@@ -0,0 +1,93 @@
+use super::super::Editor;
+use super::{cursor, editor, ex, lines, type_keys};
+
+// an editor on `text` with real tabs of 8 columns and indentation of 4
+fn with_tabs(text: &str) -> Editor {
+    let mut editor = editor(text);
+    ex(&mut editor, ":set ts=8 sw=4 noet nonu").unwrap();
+    editor
+}
+
+#[test]
+fn tabs_take_the_columns_up_to_the_next_stop() {
+    let editor = with_tabs("a\tb\t\tc");
+    let tabs = &editor.tabs;
+    let text = &editor.buffer.lines[0];
+    assert_eq!(tabs.display_col(text, 2), 8);
+    assert_eq!(tabs.display_col(text, 5), 24);
+    assert_eq!(tabs.expand(text, 0, text.len()), "a       b               c");
+
+    // a column inside a tab is the tab
+    assert_eq!(tabs.byte_at_col(text, 5), 1);
+    assert_eq!(tabs.byte_at_col(text, 8), 2);
+    assert_eq!(tabs.byte_at_col(text, 100), text.len());
+}
+
+#[test]
+fn the_cursor_sits_on_the_last_cell_of_a_tab() {
+    let mut editor = with_tabs("\tx");
+    type_keys(&mut editor, "l");
+    assert_eq!(editor.cursor_screen_pos().0, 8);
+    type_keys(&mut editor, "h");
+    assert_eq!(cursor(&editor), (0, 0));
+}
+
+#[test]
+fn tab_key_with_and_without_expandtab() {
+    let mut editor = with_tabs("");
+    type_keys(&mut editor, "i<Tab>x<Tab>");
+    assert_eq!(lines(&editor), ["\tx\t"]);
+
+    let mut editor = super::editor("");
+    ex(&mut editor, ":set et ts=4").unwrap();
+    type_keys(&mut editor, "iab<Tab>x");
+    assert_eq!(lines(&editor), ["ab  x"]);
+}
+
+#[test]
+fn softtabstop_mixes_tabs_and_spaces() {
+    let mut editor = with_tabs("");
+    ex(&mut editor, ":set sts=4").unwrap();
+    type_keys(&mut editor, "i<Tab>");
+    assert_eq!(lines(&editor), ["    "]);
+    type_keys(&mut editor, "<Tab>");
+    assert_eq!(lines(&editor), ["\t"]);
+
+    // Backspace goes back one stop of `softtabstop`
+    type_keys(&mut editor, "<BS>");
+    assert_eq!(lines(&editor), ["    "]);
+    type_keys(&mut editor, "<BS>");
+    assert_eq!(lines(&editor), [""]);
+}
+
+#[test]
+fn shifting_uses_shiftwidth() {
+    let mut editor = with_tabs("x");
+    ex(&mut editor, ":>").unwrap();
+    assert_eq!(lines(&editor), ["    x"]);
+    ex(&mut editor, ":>").unwrap();
+    assert_eq!(lines(&editor), ["\tx"]);
+    ex(&mut editor, ":<").unwrap();
+    assert_eq!(lines(&editor), ["    x"]);
+}
+
+#[test]
+fn retab_converts_between_tabs_and_spaces() {
+    let mut editor = with_tabs("\tx\n  \ty\nz       w");
+    ex(&mut editor, ":set et").unwrap();
+    ex(&mut editor, ":retab").unwrap();
+    assert_eq!(lines(&editor), ["        x", "        y", "z       w"]);
+
+    ex(&mut editor, ":set noet").unwrap();
+    ex(&mut editor, ":retab!").unwrap();
+    assert_eq!(lines(&editor), ["\tx", "\ty", "z\tw"]);
+
+    // only the lines of a range
+    let mut editor = with_tabs("\ta\n\tb");
+    ex(&mut editor, ":set et").unwrap();
+    ex(&mut editor, ":2retab").unwrap();
+    assert_eq!(lines(&editor), ["\ta", "        b"]);
+
+    assert!(super::error(&mut editor, ":retab 0").starts_with("E487"));
+    assert!(super::error(&mut editor, ":retab x").starts_with("E475"));
+}
@@ -0,0 +1,83 @@
+use crossterm::style::Color;
+
+use super::super::theme::{parse_rgb, Theme};
+use super::{editor, error, ex, TempDir};
+
+#[test]
+fn colours_are_triples_or_hex() {
+    assert_eq!(parse_rgb("(1, 2, 3)"), Some((1, 2, 3)));
+    assert_eq!(parse_rgb(" #ff8000 "), Some((255, 128, 0)));
+    assert_eq!(parse_rgb("#ff80"), None);
+    assert_eq!(parse_rgb("(1, 2)"), None);
+    assert_eq!(parse_rgb("(1, 2, 300)"), None);
+    assert_eq!(parse_rgb("red"), None);
+}
+
+#[test]
+fn themes_start_from_the_default() {
+    let default = Theme::load("default", None).unwrap();
+    let gruvbox = Theme::load("gruvbox", None).unwrap();
+    assert_eq!(gruvbox.rgb("background"), Some((0x28, 0x28, 0x28)));
+    // what a theme leaves out comes from the one it is based on
+    let partial = Theme::parse("partial", "text = #000000", Some(&gruvbox)).unwrap();
+    assert_eq!(partial.rgb("text"), Some((0, 0, 0)));
+    assert_eq!(partial.rgb("keyword"), gruvbox.rgb("keyword"));
+    assert_eq!(default.rgb("background"), None);
+    assert_eq!(default.background(), None);
+    assert_eq!(default.color("text"), Color::Rgb { r: 255, g: 255, b: 255 });
+
+    assert!(Theme::load("nothing", None).err().unwrap().to_string().starts_with("E185"));
+}
+
+#[test]
+fn bad_theme_definitions() {
+    let error = |definition: &str| Theme::parse("t", definition, None).err().unwrap().to_string();
+    assert_eq!(error("text (1, 2, 3)"), "line 1: expected `element = colour`");
+    assert_eq!(error("; comment\nborder = #000000"), "line 2: unknown element `border`");
+    assert_eq!(error("text = blue"), "line 1: `blue` is not a colour");
+}
+
+#[test]
+fn theme_files_add_and_replace_themes() {
+    let dir = TempDir::new();
+    dir.file("paper.theme", "text = #000000\nbackground = #ffffff");
+    dir.file("gruvbox.theme", "keyword = (1, 1, 1)");
+    dir.file("broken.theme", "text = 1");
+    dir.file("notes.txt", "");
+
+    let paper = Theme::load("paper", Some(dir.path())).unwrap();
+    assert_eq!(paper.rgb("background"), Some((255, 255, 255)));
+    assert_eq!(paper.rgb("comment"), Theme::load("default", None).unwrap().rgb("comment"));
+
+    // a file replaces the built-in theme of its name as a whole
+    let gruvbox = Theme::load("gruvbox", Some(dir.path())).unwrap();
+    assert_eq!(gruvbox.rgb("keyword"), Some((1, 1, 1)));
+    assert_eq!(gruvbox.rgb("background"), None);
+
+    let broken = Theme::load("broken", Some(dir.path())).err().unwrap().to_string();
+    assert!(broken.contains("broken.theme") && broken.contains("not a colour"));
+
+    assert_eq!(Theme::names(Some(dir.path())), ["broken", "default", "gruvbox", "nord", "paper", "solarized-light"]);
+}
+
+#[test]
+fn colorscheme_switches_at_runtime() {
+    let mut editor = editor("");
+    ex(&mut editor, ":colorscheme gruvbox").unwrap();
+    assert_eq!(editor.theme.name, "gruvbox");
+    ex(&mut editor, ":colo").unwrap();
+    assert_eq!(editor.command_bar.message.as_deref(), Some("gruvbox"));
+
+    // a scheme that does not exist leaves the one in use
+    assert!(error(&mut editor, ":colorscheme nothing").starts_with("E185"));
+    assert_eq!(editor.theme.name, "gruvbox");
+}
+
+#[test]
+fn colours_of_the_configuration_stay_over_any_theme() {
+    let mut editor = editor("");
+    editor.color_overrides = vec![("text", (1, 2, 3))];
+    ex(&mut editor, ":colorscheme gruvbox").unwrap();
+    assert_eq!(editor.theme.rgb("text"), Some((1, 2, 3)));
+    assert_eq!(editor.theme.rgb("background"), Some((0x28, 0x28, 0x28)));
+}
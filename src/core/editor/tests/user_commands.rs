@@ -0,0 +1,116 @@
+use super::super::super::buffer::Buffer;
+use super::{editor, editor_on, error, ex, lines, type_keys, TempDir};
+
+fn message(editor: &super::Editor) -> &str {
+    editor.command_bar.message.as_deref().unwrap_or("")
+}
+
+#[test]
+fn commands_with_arguments() {
+    let mut editor = editor("one\ntwo\nthree");
+    ex(&mut editor, ":command Drop <args>d").unwrap();
+    ex(&mut editor, ":Drop 2").unwrap();
+    assert_eq!(lines(&editor), ["one", "three"]);
+
+    ex(&mut editor, ":command Dup t<args>").unwrap();
+    ex(&mut editor, ":1Dup $").unwrap();
+    assert_eq!(lines(&editor), ["one", "three", "one"]);
+
+    // `<q-args>` quotes them, `<bang>` is the `!` the command was given
+    ex(&mut editor, ":command Say echo <q-args><bang>").unwrap();
+    ex(&mut editor, ":help Say").unwrap();
+    assert_eq!(message(&editor), ":Say  runs :echo <q-args><bang>");
+}
+
+#[test]
+fn aliases_take_the_range_and_the_bang() {
+    let mut editor = editor("one\ntwo\nthree");
+    ex(&mut editor, ":alias D delete").unwrap();
+    ex(&mut editor, ":2,3D").unwrap();
+    assert_eq!(lines(&editor), ["one"]);
+
+    ex(&mut editor, ":alias").unwrap();
+    assert_eq!(message(&editor), "D            delete");
+    ex(&mut editor, ":unalias D").unwrap();
+    assert_eq!(error(&mut editor, ":D"), "E492: Not an editor command: D");
+    assert_eq!(error(&mut editor, ":unalias D"), "No such alias: D");
+}
+
+#[test]
+fn percent_is_the_current_file() {
+    let dir = TempDir::new();
+    let file = dir.file("a.txt", "text\n");
+    let mut editor = editor_on(Buffer::from_file(Some(file.display().to_string())));
+    ex(&mut editor, ":command Count !wc -l %").unwrap();
+    ex(&mut editor, ":Count").unwrap();
+    assert_eq!(message(&editor), format!("1 {}", file.display()));
+
+    ex(&mut editor, ":!echo \\%").unwrap();
+    assert_eq!(message(&editor), "%");
+    let mut editor = super::editor("");
+    assert!(error(&mut editor, ":!echo %").starts_with("E499"));
+}
+
+#[test]
+fn shell_filters_and_output() {
+    let mut editor = editor("b\na\nc");
+    ex(&mut editor, ":%!sort").unwrap();
+    assert_eq!(lines(&editor), ["a", "b", "c"]);
+    ex(&mut editor, ":2!tr a-z A-Z").unwrap();
+    assert_eq!(lines(&editor), ["a", "B", "c"]);
+
+    ex(&mut editor, ":!exit 3").unwrap();
+    assert_eq!(message(&editor), "shell returned 3");
+    assert_eq!(error(&mut editor, ":!"), "E471: Argument required");
+}
+
+#[test]
+fn bad_definitions() {
+    let mut editor = editor("");
+    ex(&mut editor, ":command Fmt !true").unwrap();
+    assert_eq!(error(&mut editor, ":command Fmt !false"), "E174: Command already exists: add ! to replace it");
+    ex(&mut editor, ":command! Fmt !false").unwrap();
+    assert_eq!(error(&mut editor, ":command fmt x"), "E183: User defined commands must start with an uppercase letter");
+    assert_eq!(error(&mut editor, ":command F1 x"), "E182: Invalid command name: F1");
+
+    ex(&mut editor, ":delcommand Fmt").unwrap();
+    assert_eq!(error(&mut editor, ":delcommand Fmt"), "E184: No such user-defined command: Fmt");
+    ex(&mut editor, ":command").unwrap();
+    assert_eq!(message(&editor), "No user-defined commands found");
+}
+
+#[test]
+fn commands_that_run_themselves_stop() {
+    let mut editor = editor("");
+    ex(&mut editor, ":command Ping Pong").unwrap();
+    ex(&mut editor, ":command Pong Ping").unwrap();
+    assert_eq!(error(&mut editor, ":Ping"), "E169: Command too recursive");
+    // and the next command runs as it should
+    ex(&mut editor, ":command Dup t.").unwrap();
+    ex(&mut editor, ":Dup").unwrap();
+    assert_eq!(lines(&editor), ["", ""]);
+}
+
+#[test]
+fn aliases_chain_and_can_name_the_command_under_them() {
+    let mut editor = editor("one\ntwo\nthree");
+    ex(&mut editor, ":alias Rm Del").unwrap();
+    ex(&mut editor, ":alias Del delete").unwrap();
+    ex(&mut editor, ":1Rm").unwrap();
+    assert_eq!(lines(&editor), ["two", "three"]);
+
+    ex(&mut editor, ":alias delete delete").unwrap();
+    ex(&mut editor, ":1delete").unwrap();
+    assert_eq!(lines(&editor), ["three"]);
+}
+
+#[test]
+fn completion_knows_user_commands() {
+    let mut editor = editor("");
+    ex(&mut editor, ":command Format !true").unwrap();
+    ex(&mut editor, ":alias Fix s/a/b/").unwrap();
+    type_keys(&mut editor, ":Fo<Tab>");
+    assert_eq!(editor.command_bar.command, ":Format");
+    type_keys(&mut editor, "<Esc>:delc <Tab>");
+    assert_eq!(editor.command_bar.command, ":delc Format");
+}
@@ -0,0 +1,85 @@
+use super::super::layout::wrap_line;
+use super::super::Editor;
+use super::{cursor, editor, ex, type_keys};
+
+// one line of 200 characters and a short one, shown in 80 columns without line numbers
+fn wrapped() -> Editor {
+    let long: String = (0..200).map(|n| char::from(b'a' + (n % 26) as u8)).collect();
+    let mut editor = editor(&format!("{}\nshort", long));
+    ex(&mut editor, ":set wrap nonu").unwrap();
+    editor
+}
+
+#[test]
+fn rows_of_a_line() {
+    let editor = wrapped();
+    let tabs = &editor.tabs;
+    let text = &editor.buffer.lines[0];
+    assert_eq!(wrap_line(text, 80, 80, false, tabs), [(0, 80), (80, 160), (160, 200)]);
+    assert_eq!(wrap_line(text, 80, 77, false, tabs), [(0, 80), (80, 157), (157, 200)]);
+    assert_eq!(wrap_line("", 80, 80, false, tabs), [(0, 0)]);
+
+    // tabs take the columns they are shown in
+    assert_eq!(wrap_line("\t\tabcd", 10, 10, false, tabs), [(0, 4), (4, 6)]);
+    // and a character is never split
+    assert_eq!(wrap_line("ééé", 2, 2, false, tabs), [(0, 4), (4, 6)]);
+}
+
+#[test]
+fn linebreak_breaks_at_words() {
+    let editor = wrapped();
+    let tabs = &editor.tabs;
+    assert_eq!(wrap_line("one two three", 10, 10, false, tabs), [(0, 10), (10, 13)]);
+    assert_eq!(wrap_line("one two three", 10, 10, true, tabs), [(0, 8), (8, 13)]);
+    // a word longer than the row is cut where it has to be
+    assert_eq!(wrap_line("abcdefghijkl", 10, 10, true, tabs), [(0, 10), (10, 12)]);
+}
+
+#[test]
+fn screen_rows_with_showbreak() {
+    let mut editor = wrapped();
+    ex(&mut editor, ":set sbr=>>\\ nu").unwrap();
+    let rows = editor.screen_rows();
+    assert_eq!(rows.len(), editor.viewport_height());
+
+    // the number only goes on the first row of a line
+    let numbers: Vec<_> = rows[..5].iter().map(|r| r.number).collect();
+    assert_eq!(numbers, [Some(1), None, None, Some(2), None]);
+    assert!(rows[1].text.starts_with(">> "));
+    assert_eq!(rows[3].text.trim_end(), "short");
+    assert_eq!(rows[4].text.trim_end(), "");
+}
+
+#[test]
+fn j_moves_by_line_and_gj_by_row() {
+    let mut editor = wrapped();
+    type_keys(&mut editor, "5l");
+    type_keys(&mut editor, "gj");
+    assert_eq!(cursor(&editor), (0, 85));
+    assert_eq!(editor.cursor_screen_pos(), (5, 1));
+    type_keys(&mut editor, "gjgj");
+    assert_eq!(cursor(&editor), (1, 4));
+    type_keys(&mut editor, "gk");
+    assert_eq!(cursor(&editor), (0, 164));
+
+    type_keys(&mut editor, "gg0j");
+    assert_eq!(cursor(&editor), (1, 0));
+    type_keys(&mut editor, "k");
+    assert_eq!(cursor(&editor), (0, 0));
+}
+
+#[test]
+fn wrapped_lines_fill_the_window_sooner() {
+    let text: Vec<String> = (0..30).map(|_| "x".repeat(100)).collect();
+    let mut editor = editor(&text.join("\n"));
+    ex(&mut editor, ":set wrap nonu so=0").unwrap();
+    // every line takes two of the 22 rows
+    assert_eq!(editor.last_visible_line(0), 10);
+
+    type_keys(&mut editor, "11j");
+    assert_eq!(editor.viewport_top, 1);
+    // no sideways scrolling while wrapping
+    type_keys(&mut editor, "$");
+    editor.fit_cursor_in_view();
+    assert_eq!(editor.viewport_left, 0);
+}
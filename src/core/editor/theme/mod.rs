@@ -0,0 +1,239 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::path::Path;
+
+use crossterm::style::{Color, ContentStyle, StyledContent, Stylize};
+
+use crate::core::syntax::Highlight;
+use palette::ColorDepth;
+
+pub mod palette;
+
+pub type Rgb = (u8, u8, u8);
+
+// every element a theme colours; `background` is the only one a theme may leave out,
+// the terminal's own background shows then
+pub const ELEMENTS: &[&str] = &[
+    "text",
+    "background",
+    "line_number",
+    "status_bar_background",
+    "status_bar_text",
+    "command_bar_background",
+    "command_bar_text",
+    "message",
+    "keyword",
+    "type",
+    "constant",
+    "string",
+    "comment",
+    "number",
+    "heading",
+];
+
+// themes that come with the editor, a file in the theme directory with the same name
+// replaces one
+const BUILTIN: &[(&str, &str)] = &[
+    ("default", r#"
+text = (255, 255, 255)
+line_number = (255, 255, 255)
+status_bar_background = (255, 204, 229)
+status_bar_text = (102, 0, 51)
+command_bar_background = (255, 255, 255)
+command_bar_text = (0, 0, 0)
+message = (255, 255, 255)
+keyword = (198, 120, 221)
+type = (229, 192, 123)
+constant = (209, 154, 102)
+string = (152, 195, 121)
+comment = (127, 132, 142)
+number = (209, 154, 102)
+heading = (97, 175, 239)
+"#),
+    ("gruvbox", r#"
+text = #ebdbb2
+background = #282828
+line_number = #7c6f64
+status_bar_background = #504945
+status_bar_text = #ebdbb2
+command_bar_background = #3c3836
+command_bar_text = #ebdbb2
+message = #ebdbb2
+keyword = #fb4934
+type = #fabd2f
+constant = #d3869b
+string = #b8bb26
+comment = #928374
+number = #d3869b
+heading = #83a598
+"#),
+    ("nord", r#"
+text = #d8dee9
+background = #2e3440
+line_number = #4c566a
+status_bar_background = #3b4252
+status_bar_text = #88c0d0
+command_bar_background = #3b4252
+command_bar_text = #d8dee9
+message = #d8dee9
+keyword = #81a1c1
+type = #8fbcbb
+constant = #b48ead
+string = #a3be8c
+comment = #616e80
+number = #b48ead
+heading = #88c0d0
+"#),
+    ("solarized-light", r#"
+text = #657b83
+background = #fdf6e3
+line_number = #93a1a1
+status_bar_background = #eee8d5
+status_bar_text = #586e75
+command_bar_background = #eee8d5
+command_bar_text = #586e75
+message = #657b83
+keyword = #859900
+type = #b58900
+constant = #2aa198
+string = #2aa198
+comment = #93a1a1
+number = #d33682
+heading = #268bd2
+"#),
+];
+
+// colours of the UI elements and syntax groups by name, drawn with as many colours as
+// the terminal has
+pub struct Theme {
+    pub name: String,
+    colors: HashMap<String, Rgb>,
+    pub depth: ColorDepth,
+}
+
+// `(r, g, b)` or `#rrggbb`
+pub fn parse_rgb(value: &str) -> Option<Rgb> {
+    let value = value.trim();
+    if let Some(hex) = value.strip_prefix('#') {
+        if hex.len() != 6 {
+            return None;
+        }
+        let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+        return Some((channel(0)?, channel(2)?, channel(4)?));
+    }
+
+    let inner = value.strip_prefix('(')?.strip_suffix(')')?;
+    let channels: Vec<u8> = inner.split(',').map(|c| c.trim().parse().ok()).collect::<Option<_>>()?;
+    match channels[..] {
+        [r, g, b] => Some((r, g, b)),
+        _ => None,
+    }
+}
+
+impl Theme {
+    // `name = colour` lines, elements left out keep the colours of `base`
+    pub fn parse(name: &str, definition: &str, base: Option<&Theme>) -> anyhow::Result<Self> {
+        let mut colors = base.map(|b| b.colors.clone()).unwrap_or_default();
+
+        for (i, line) in definition.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with(';') {
+                continue;
+            }
+            let (element, value) = match line.split_once('=') {
+                Some((element, value)) => (element.trim(), value.trim()),
+                None => anyhow::bail!("line {}: expected `element = colour`", i + 1),
+            };
+            if !ELEMENTS.contains(&element) {
+                anyhow::bail!("line {}: unknown element `{}`", i + 1, element);
+            }
+            match parse_rgb(value) {
+                Some(rgb) => colors.insert(element.to_string(), rgb),
+                None => anyhow::bail!("line {}: `{}` is not a colour", i + 1, value),
+            };
+        }
+
+        Ok(Self { name: name.to_string(), colors, depth: ColorDepth::TrueColor })
+    }
+
+    // the theme called `name`, a `<name>.theme` file in the theme directory `dir` or one
+    // of the built-in ones
+    pub fn load(name: &str, dir: Option<&Path>) -> anyhow::Result<Self> {
+        let default = Theme::parse("default", BUILTIN[0].1, None)?;
+
+        if let Some(path) = dir.map(|d| d.join(format!("{}.theme", name))) {
+            if let Ok(definition) = std::fs::read_to_string(&path) {
+                return Theme::parse(name, &definition, Some(&default))
+                    .map_err(|e| anyhow::anyhow!("{}: {}", path.display(), e));
+            }
+        }
+
+        match BUILTIN.iter().find(|(n, _)| *n == name) {
+            Some((name, definition)) => Theme::parse(name, definition, Some(&default)),
+            None => anyhow::bail!("E185: Cannot find color scheme '{}'", name),
+        }
+    }
+
+    // names of the built-in themes and of the theme files in `dir`
+    pub fn names(dir: Option<&Path>) -> Vec<String> {
+        let mut names: Vec<String> = BUILTIN.iter().map(|(name, _)| name.to_string()).collect();
+        if let Some(entries) = dir.and_then(|d| std::fs::read_dir(d).ok()) {
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if path.extension().is_some_and(|e| e == "theme") {
+                    if let Some(name) = path.file_stem().and_then(|s| s.to_str()) {
+                        names.push(name.to_string());
+                    }
+                }
+            }
+        }
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    pub fn set(&mut self, element: &str, rgb: Rgb) {
+        self.colors.insert(element.to_string(), rgb);
+    }
+
+    pub fn rgb(&self, element: &str) -> Option<Rgb> {
+        self.colors.get(element).copied()
+    }
+
+    // colour of an element, every element but `background` has one
+    pub fn color(&self, element: &str) -> Color {
+        self.depth.convert(self.rgb(element).unwrap_or((255, 255, 255)))
+    }
+
+    pub fn background(&self) -> Option<Color> {
+        match self.depth {
+            ColorDepth::Mono => None,
+            depth => self.rgb("background").map(|rgb| depth.convert(rgb)),
+        }
+    }
+
+    // `content` in the colours of a bar, without colours the bars are drawn in reverse
+    // video to stand out
+    pub fn bar<D: Display>(&self, content: D, background: &str, text: &str) -> StyledContent<D> {
+        let styled = StyledContent::new(ContentStyle::new(), content).with(self.color(text)).on(self.color(background));
+        if self.depth == ColorDepth::Mono {
+            styled.reverse()
+        } else {
+            styled
+        }
+    }
+
+    // element a syntax group is drawn with
+    pub fn highlight_color(&self, highlight: Highlight) -> Color {
+        self.color(match highlight {
+            Highlight::Normal => "text",
+            Highlight::Keyword => "keyword",
+            Highlight::Type => "type",
+            Highlight::Constant => "constant",
+            Highlight::String => "string",
+            Highlight::Comment => "comment",
+            Highlight::Number => "number",
+            Highlight::Heading => "heading",
+        })
+    }
+}
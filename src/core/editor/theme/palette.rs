@@ -0,0 +1,113 @@
+use crossterm::style::Color;
+
+use super::Rgb;
+
+// how many colours the terminal shows
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ColorDepth {
+    TrueColor,
+    Ansi256,
+    Ansi16,
+    Mono,
+}
+
+// the 16 basic colours with the RGB values most terminals use for them
+const ANSI16: &[(Color, Rgb)] = &[
+    (Color::Black, (0, 0, 0)),
+    (Color::DarkRed, (128, 0, 0)),
+    (Color::DarkGreen, (0, 128, 0)),
+    (Color::DarkYellow, (128, 128, 0)),
+    (Color::DarkBlue, (0, 0, 128)),
+    (Color::DarkMagenta, (128, 0, 128)),
+    (Color::DarkCyan, (0, 128, 128)),
+    (Color::Grey, (192, 192, 192)),
+    (Color::DarkGrey, (128, 128, 128)),
+    (Color::Red, (255, 0, 0)),
+    (Color::Green, (0, 255, 0)),
+    (Color::Yellow, (255, 255, 0)),
+    (Color::Blue, (0, 0, 255)),
+    (Color::Magenta, (255, 0, 255)),
+    (Color::Cyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+// channel values of the 6x6x6 colour cube of the 256 colour palette
+const CUBE: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+impl ColorDepth {
+    // `truecolor`, `256`, `16`, `mono` or `auto`, which asks the environment
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "truecolor" | "24bit" => Some(ColorDepth::TrueColor),
+            "256" => Some(ColorDepth::Ansi256),
+            "16" | "8" => Some(ColorDepth::Ansi16),
+            "mono" | "none" => Some(ColorDepth::Mono),
+            "auto" => Some(detect()),
+            _ => None,
+        }
+    }
+
+    // the colour closest to `rgb` the terminal can show, monochrome leaves the terminal's own
+    pub fn convert(&self, rgb: Rgb) -> Color {
+        let (r, g, b) = rgb;
+        match self {
+            ColorDepth::TrueColor => Color::Rgb { r, g, b },
+            ColorDepth::Ansi256 => Color::AnsiValue(nearest_256(rgb)),
+            ColorDepth::Ansi16 => nearest_16(rgb),
+            ColorDepth::Mono => Color::Reset,
+        }
+    }
+}
+
+// colour support from `NO_COLOR`, `COLORTERM` and `TERM`
+pub fn detect() -> ColorDepth {
+    let var = |name: &str| std::env::var(name).unwrap_or_default().to_lowercase();
+    if std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty()) {
+        return ColorDepth::Mono;
+    }
+    if matches!(var("COLORTERM").as_str(), "truecolor" | "24bit") {
+        return ColorDepth::TrueColor;
+    }
+
+    let term = var("TERM");
+    if term.is_empty() || term == "dumb" {
+        ColorDepth::Mono
+    } else if term.contains("direct") || term.contains("truecolor") {
+        ColorDepth::TrueColor
+    } else if term.contains("256") {
+        ColorDepth::Ansi256
+    } else {
+        ColorDepth::Ansi16
+    }
+}
+
+fn distance(a: Rgb, b: Rgb) -> u32 {
+    let d = |x: u8, y: u8| (x as i32 - y as i32).pow(2) as u32;
+    d(a.0, b.0) + d(a.1, b.1) + d(a.2, b.2)
+}
+
+// index of the closest entry of the colour cube (16..=231) or the grey ramp (232..=255)
+fn nearest_256(rgb: Rgb) -> u8 {
+    let level = |c: u8| (0..CUBE.len()).min_by_key(|&i| (CUBE[i] as i32 - c as i32).abs()).unwrap_or(0);
+    let (r, g, b) = (level(rgb.0), level(rgb.1), level(rgb.2));
+    let cube = (16 + 36 * r + 6 * g + b) as u8;
+    let cube_rgb = (CUBE[r], CUBE[g], CUBE[b]);
+
+    let average = (rgb.0 as u32 + rgb.1 as u32 + rgb.2 as u32) / 3;
+    let step = (average.saturating_sub(8) / 10).min(23) as u8;
+    let grey = 8 + 10 * step;
+
+    if distance(rgb, (grey, grey, grey)) < distance(rgb, cube_rgb) {
+        232 + step
+    } else {
+        cube
+    }
+}
+
+fn nearest_16(rgb: Rgb) -> Color {
+    ANSI16
+        .iter()
+        .min_by_key(|(_, entry)| distance(rgb, *entry))
+        .map(|(color, _)| *color)
+        .unwrap_or(Color::Reset)
+}
@@ -0,0 +1,99 @@
+use crossterm::event::{self, KeyEvent};
+
+use super::motion::Position;
+use super::{Action, Editor, Mode, Register};
+
+impl Editor {
+    // selected text as `from..to`, the character under the cursor is included and a
+    // selection ending on an empty line takes its line break
+    pub(super) fn selection(&self) -> Option<(Position, Position)> {
+        if self.mode != Mode::Visual {
+            return None;
+        }
+
+        let cursor = self.cursor_pos();
+        let (from, last) = if cursor < self.visual_anchor {
+            (cursor, self.visual_anchor)
+        } else {
+            (self.visual_anchor, cursor)
+        };
+
+        let text = &self.buffer.lines[last.line];
+        let to = match text[last.col..].chars().next() {
+            Some(c) => Position::new(last.line, last.col + c.len_utf8()),
+            None if last.line + 1 < self.buffer.get_file_lenght() => Position::new(last.line + 1, 0),
+            None => Position::new(last.line, text.len()),
+        };
+
+        Some((from, to))
+    }
+
+    // Visual mode takes the normal mode keys that move the cursor and a few of its own
+    pub(super) fn handle_visual_event(&mut self, ev: event::Event) -> anyhow::Result<Option<Action>> {
+        if let event::Event::Key(key) = ev {
+            if self.pending.is_empty() && self.count == 0 {
+                if let Some(action) = visual_action(key) {
+                    return Ok(Some(action));
+                }
+            }
+        }
+
+        let action = self.handle_normal_event(ev)?;
+        Ok(action.filter(|a| a.moves_cursor()))
+    }
+
+    pub(super) fn yank_selection(&mut self) {
+        if let Some((from, to)) = self.selection() {
+            let text = self.buffer.text_range((from.line, from.col), (to.line, to.col));
+            self.registers.insert('"', Register::charwise(text));
+            self.goto_line(from.line);
+            self.cursor_x = from.col as u16;
+        }
+        self.set_mode(Mode::Normal);
+    }
+
+    pub(super) fn delete_selection(&mut self) {
+        if let Some((from, to)) = self.selection() {
+            self.begin_change();
+            let text = self.buffer.remove_text((from.line, from.col), (to.line, to.col));
+            self.registers.insert('"', Register::charwise(text));
+            self.goto_line(from.line);
+            self.cursor_x = from.col as u16;
+            self.buffer.end_change();
+        }
+        self.set_mode(Mode::Normal);
+    }
+
+    // puts a characterwise register after the cursor (or before it), the cursor ends up
+    // on the last character put
+    pub(super) fn put_text(&mut self, register: char, after: bool) -> anyhow::Result<()> {
+        let text = match self.registers.get(&register) {
+            Some(r) => r.lines.clone(),
+            None => anyhow::bail!("E353: Nothing in register {}", register),
+        };
+
+        let mut at = self.cursor_pos();
+        if after {
+            if let Some(c) = self.buffer.lines[at.line][at.col..].chars().next() {
+                at.col += c.len_utf8();
+            }
+        }
+
+        let (line, col) = self.buffer.insert_text((at.line, at.col), &text);
+        self.goto_line(line);
+        let put = &self.buffer.lines[line][..col];
+        self.cursor_x = put.char_indices().next_back().map(|(i, _)| i).unwrap_or(0) as u16;
+
+        Ok(())
+    }
+}
+
+fn visual_action(key: KeyEvent) -> Option<Action> {
+    match key.code {
+        event::KeyCode::Esc | event::KeyCode::Char('v') => Some(Action::SetMode(Mode::Normal)),
+        event::KeyCode::Char(':') => Some(Action::SetMode(Mode::Command)),
+        event::KeyCode::Char('d' | 'x') => Some(Action::DeleteSelection),
+        event::KeyCode::Char('y') => Some(Action::YankSelection),
+        _ => None,
+    }
+}
@@ -1,3 +1,4 @@
 pub mod editor;
 pub mod timer;
 pub mod buffer;
+pub mod syntax;
@@ -0,0 +1,148 @@
+// a language definition, read from `key = value` lines:
+//
+//   name = rust
+//   extensions = rs
+//   keywords = fn let mut
+//   line_comment = //
+//   block_comment = /* */
+//
+// lists are separated by white space and lines starting with `;` are comments
+pub struct Language {
+    pub name: String,
+    pub extensions: Vec<String>,
+    pub keywords: Vec<String>,
+    pub types: Vec<String>,
+    pub constants: Vec<String>,
+    pub line_comments: Vec<String>,
+    pub block_comment: Option<(String, String)>,
+    // block comments inside block comments, like in Rust
+    pub nested_comments: bool,
+    // characters strings start and end with
+    pub strings: Vec<char>,
+    // strings that are not closed go on on the next line
+    pub multiline_strings: bool,
+    pub escape: Option<char>,
+    pub numbers: bool,
+    // lines starting with one of these are highlighted as a whole, like Markdown headings
+    pub headings: Vec<String>,
+}
+
+fn words(value: &str) -> Vec<String> {
+    value.split_whitespace().map(|w| w.to_string()).collect()
+}
+
+fn flag(key: &str, value: &str) -> anyhow::Result<bool> {
+    match value {
+        "true" => Ok(true),
+        "false" => Ok(false),
+        _ => anyhow::bail!("{} must be true or false", key),
+    }
+}
+
+impl Language {
+    pub fn parse(definition: &str) -> anyhow::Result<Self> {
+        let mut language = Language {
+            name: String::new(),
+            extensions: Vec::new(),
+            keywords: Vec::new(),
+            types: Vec::new(),
+            constants: Vec::new(),
+            line_comments: Vec::new(),
+            block_comment: None,
+            nested_comments: false,
+            strings: Vec::new(),
+            multiline_strings: false,
+            escape: None,
+            numbers: false,
+            headings: Vec::new(),
+        };
+
+        for (i, line) in definition.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with(';') {
+                continue;
+            }
+            let (key, value) = match line.split_once('=') {
+                Some((key, value)) => (key.trim(), value.trim()),
+                None => anyhow::bail!("line {}: expected `key = value`", i + 1),
+            };
+
+            match key {
+                "name" => language.name = value.to_string(),
+                "extensions" => language.extensions = words(value),
+                "keywords" => language.keywords = words(value),
+                "types" => language.types = words(value),
+                "constants" => language.constants = words(value),
+                "line_comment" => language.line_comments = words(value),
+                "block_comment" => match value.split_whitespace().collect::<Vec<_>>()[..] {
+                    [open, close] => language.block_comment = Some((open.to_string(), close.to_string())),
+                    _ => anyhow::bail!("line {}: block_comment takes an opening and a closing marker", i + 1),
+                },
+                "nested_comments" => language.nested_comments = flag(key, value)?,
+                "strings" => language.strings = value.split_whitespace().filter_map(|s| s.chars().next()).collect(),
+                "multiline_strings" => language.multiline_strings = flag(key, value)?,
+                "escape" => language.escape = value.chars().next(),
+                "numbers" => language.numbers = flag(key, value)?,
+                "headings" => language.headings = words(value),
+                _ => anyhow::bail!("line {}: unknown key `{}`", i + 1, key),
+            }
+        }
+
+        if language.name.is_empty() {
+            anyhow::bail!("the definition has no name");
+        }
+        Ok(language)
+    }
+}
+
+// definitions that come with the editor, files in the syntax directory replace them
+pub const BUILTIN: &[&str] = &[
+    r#"
+name = rust
+extensions = rs
+keywords = as async await break const continue crate dyn else enum extern fn for if impl in let loop match mod move mut pub ref return self Self static struct super trait type unsafe use where while
+types = bool char str u8 u16 u32 u64 u128 usize i8 i16 i32 i64 i128 isize f32 f64 String Vec Option Result Box
+constants = true false None Some Ok Err
+line_comment = //
+block_comment = /* */
+nested_comments = true
+strings = "
+multiline_strings = true
+escape = \
+numbers = true
+"#,
+    r#"
+name = toml
+extensions = toml
+constants = true false
+line_comment = #
+strings = " '
+escape = \
+numbers = true
+headings = [
+"#,
+    r#"
+name = markdown
+extensions = md markdown
+strings = `
+headings = #
+"#,
+    r#"
+name = sh
+extensions = sh bash zsh
+keywords = if then else elif fi for while until do done case esac function in return local export select break continue
+constants = true false
+line_comment = #
+strings = " '
+escape = \
+numbers = true
+"#,
+    r#"
+name = json
+extensions = json
+constants = true false null
+strings = "
+escape = \
+numbers = true
+"#,
+];
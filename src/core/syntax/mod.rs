@@ -0,0 +1,248 @@
+use std::path::Path;
+
+use language::{Language, BUILTIN};
+
+pub mod language;
+
+// what a piece of text is, the editor picks a colour for each
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Highlight {
+    Normal,
+    Keyword,
+    Type,
+    Constant,
+    String,
+    Comment,
+    Number,
+    Heading,
+}
+
+// where a line starts: in code, inside a block comment (at some depth) or inside a string
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum State {
+    Code,
+    Comment(usize),
+    Str(char),
+}
+
+// highlighted byte range of a line
+type Span = (usize, usize, Highlight);
+
+// a highlighted line, kept until its text or the state it starts in changes
+struct CachedLine {
+    text: String,
+    start: State,
+    end: State,
+    spans: Vec<Span>,
+}
+
+// highlights the lines of a buffer with the language of its file type; lines are only
+// highlighted again when they (or the lines above them) changed
+pub struct Syntax {
+    pub enabled: bool,
+    languages: Vec<Language>,
+    current: Option<usize>,
+    cache: Vec<CachedLine>,
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+impl Syntax {
+    // the built-in languages and the `<name>.syntax` files in directory `dir`, with the
+    // errors of definitions that could not be read
+    pub fn load(enabled: bool, dir: Option<&Path>) -> (Self, Vec<String>) {
+        let mut languages: Vec<Language> = BUILTIN.iter().filter_map(|d| Language::parse(d).ok()).collect();
+        let mut errors = Vec::new();
+
+        let mut files: Vec<_> = match dir.map(std::fs::read_dir) {
+            Some(Ok(dir)) => dir.filter_map(|e| e.ok()).map(|e| e.path()).collect(),
+            _ => Vec::new(),
+        };
+        files.sort();
+        for path in files.into_iter().filter(|p| p.extension().is_some_and(|e| e == "syntax")) {
+            let result = std::fs::read_to_string(&path)
+                .map_err(anyhow::Error::from)
+                .and_then(|text| Language::parse(&text));
+            match result {
+                Ok(language) => {
+                    languages.retain(|l| l.name != language.name);
+                    languages.push(language);
+                }
+                Err(e) => errors.push(format!("{}: {}", path.display(), e)),
+            }
+        }
+
+        let syntax = Self { enabled, languages, current: None, cache: Vec::new() };
+        (syntax, errors)
+    }
+
+    // language for a file type, or for the extension of `file` when no language has its name
+    fn language_for(&self, filetype: &str, file: Option<&str>) -> Option<usize> {
+        let extension = file
+            .and_then(|f| std::path::Path::new(f.trim()).extension())
+            .and_then(|e| e.to_str())
+            .unwrap_or_default();
+
+        self.languages
+            .iter()
+            .position(|l| !filetype.is_empty() && l.name == filetype)
+            .or_else(|| self.languages.iter().position(|l| l.extensions.iter().any(|e| e == extension)))
+    }
+
+    // brings the highlighting of lines `0..=last` up to date
+    pub fn update(&mut self, filetype: &str, file: Option<&str>, lines: &[String], last: usize) {
+        let current = if self.enabled { self.language_for(filetype, file) } else { None };
+        if current != self.current {
+            self.current = current;
+            self.cache.clear();
+        }
+        let language = match self.current {
+            Some(i) => &self.languages[i],
+            None => return,
+        };
+
+        self.cache.truncate(lines.len());
+        let mut state = State::Code;
+        for (i, text) in lines.iter().enumerate().take(last + 1) {
+            match self.cache.get(i) {
+                Some(cached) if cached.start == state && cached.text == *text => {}
+                _ => {
+                    let (spans, end) = highlight_line(language, text, state);
+                    let line = CachedLine { text: text.clone(), start: state, end, spans };
+                    if i < self.cache.len() {
+                        self.cache[i] = line;
+                    } else {
+                        self.cache.push(line);
+                    }
+                }
+            }
+            state = self.cache[i].end;
+        }
+    }
+
+    // highlight of every byte of line `line`, as of the last `update`
+    pub fn line_highlights(&self, line: usize, len: usize) -> Vec<Highlight> {
+        let mut highlights = vec![Highlight::Normal; len];
+        if self.current.is_none() {
+            return highlights;
+        }
+        if let Some(cached) = self.cache.get(line) {
+            for &(from, to, highlight) in &cached.spans {
+                for h in &mut highlights[from.min(len)..to.min(len)] {
+                    *h = highlight;
+                }
+            }
+        }
+        highlights
+    }
+}
+
+// highlights one line that starts in `state`, returns its spans and the state the next
+// line starts in
+fn highlight_line(language: &Language, text: &str, mut state: State) -> (Vec<Span>, State) {
+    let mut spans = Vec::new();
+
+    if state == State::Code && language.headings.iter().any(|h| text.trim_start().starts_with(h.as_str())) {
+        spans.push((0, text.len(), Highlight::Heading));
+        return (spans, state);
+    }
+
+    let mut i = 0;
+    let mut start = 0;
+    while i < text.len() {
+        let rest = &text[i..];
+        let c = rest.chars().next().unwrap_or_default();
+
+        match state {
+            State::Comment(depth) => {
+                let (open, close) = match &language.block_comment {
+                    Some(markers) => markers,
+                    None => return (spans, State::Code),
+                };
+                if rest.starts_with(close.as_str()) {
+                    i += close.len();
+                    if depth == 1 {
+                        spans.push((start, i, Highlight::Comment));
+                        state = State::Code;
+                    } else {
+                        state = State::Comment(depth - 1);
+                    }
+                } else if language.nested_comments && rest.starts_with(open.as_str()) {
+                    i += open.len();
+                    state = State::Comment(depth + 1);
+                } else {
+                    i += c.len_utf8();
+                }
+            }
+            State::Str(delimiter) => {
+                if Some(c) == language.escape {
+                    i += c.len_utf8();
+                    i += text[i..].chars().next().map_or(0, |c| c.len_utf8());
+                } else {
+                    i += c.len_utf8();
+                    if c == delimiter {
+                        spans.push((start, i, Highlight::String));
+                        state = State::Code;
+                    }
+                }
+            }
+            State::Code => {
+                start = i;
+                if language.line_comments.iter().any(|m| rest.starts_with(m.as_str())) {
+                    spans.push((i, text.len(), Highlight::Comment));
+                    return (spans, State::Code);
+                }
+                if let Some((open, _)) = &language.block_comment {
+                    if rest.starts_with(open.as_str()) {
+                        i += open.len();
+                        state = State::Comment(1);
+                        continue;
+                    }
+                }
+                if language.strings.contains(&c) {
+                    i += c.len_utf8();
+                    state = State::Str(c);
+                    continue;
+                }
+
+                let after_word = text[..i].chars().next_back().is_some_and(is_word_char);
+                if language.numbers && c.is_ascii_digit() && !after_word {
+                    i += rest.find(|c: char| !(is_word_char(c) || c == '.')).unwrap_or(rest.len());
+                    spans.push((start, i, Highlight::Number));
+                } else if is_word_char(c) {
+                    i += rest.find(|c: char| !is_word_char(c)).unwrap_or(rest.len());
+                    let word = &text[start..i];
+                    let highlight = if language.keywords.iter().any(|k| k == word) {
+                        Highlight::Keyword
+                    } else if language.types.iter().any(|t| t == word) {
+                        Highlight::Type
+                    } else if language.constants.iter().any(|k| k == word) {
+                        Highlight::Constant
+                    } else {
+                        Highlight::Normal
+                    };
+                    if highlight != Highlight::Normal {
+                        spans.push((start, i, highlight));
+                    }
+                } else {
+                    i += c.len_utf8();
+                }
+            }
+        }
+    }
+
+    // comments and strings still open at the end of the line
+    match state {
+        State::Comment(_) => spans.push((start, text.len(), Highlight::Comment)),
+        State::Str(_) => {
+            spans.push((start, text.len(), Highlight::String));
+            if !language.multiline_strings {
+                state = State::Code;
+            }
+        }
+        State::Code => {}
+    }
+    (spans, state)
+}
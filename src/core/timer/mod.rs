@@ -1,5 +1,6 @@
 use chrono::prelude::*;
 
+#[derive(Default)]
 pub struct Timer {
     start_time: Option<DateTime<Local>>,
     end_time: Option<DateTime<Local>>
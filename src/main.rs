@@ -1,14 +1,48 @@
-use anyhow;
-use core::{editor::Editor, buffer::Buffer};
+use edit::core::{editor::{ConfigOptions, Editor}, buffer::Buffer};
 
-// mods
-mod core;
+const USAGE: &str = "usage: edit [-c|--config <path>] [--set <key>=<value>]... [file]";
+
+// `edit [-c|--config <path>] [--set key=value]... [file]`
+fn parse_args() -> anyhow::Result<(Option<String>, ConfigOptions)> {
+    let mut file = None;
+    let mut options = ConfigOptions::default();
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let (name, inline) = match arg.split_once('=') {
+            Some((name, value)) if name.starts_with("--") => (name.to_string(), Some(value.to_string())),
+            _ => (arg.clone(), None),
+        };
+        match name.as_str() {
+            "-c" | "--config" | "--set" => {
+                let value = match inline.or_else(|| args.next()) {
+                    Some(value) => value,
+                    None => anyhow::bail!("{} needs a value\n{}", name, USAGE),
+                };
+                if name == "--set" {
+                    options.sets.push(value);
+                } else {
+                    options.file = Some(value.into());
+                }
+            }
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                std::process::exit(0);
+            }
+            _ if arg.starts_with('-') && arg.len() > 1 => anyhow::bail!("unknown option {}\n{}", arg, USAGE),
+            _ if file.is_some() => anyhow::bail!("only one file can be edited\n{}", USAGE),
+            _ => file = Some(arg),
+        }
+    }
+
+    Ok((file, options))
+}
 
 fn main() -> anyhow::Result<()> {
-    let file = std::env::args().nth(1);
+    let (file, options) = parse_args()?;
     let buffer = Buffer::from_file(file);
     
-    let mut editor = Editor::new(buffer).unwrap();
+    let mut editor = Editor::new(buffer, &options).unwrap();
     editor.start()?;
 
     Ok(())
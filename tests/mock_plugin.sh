@@ -0,0 +1,34 @@
+#!/bin/sh
+# A plugin with fixed answers for tests/plugins.rs: it registers a command and a module,
+# edits the buffer when the command runs and counts the events it gets.
+
+read -r initialize
+# subscribing first, the module it registers last tells the test everything arrived
+echo '{"jsonrpc":"2.0","method":"subscribe","params":{"events":["TextChanged"]}}'
+echo '{"jsonrpc":"2.0","id":1,"method":"registerCommand","params":{"name":"Shout","help":"Upper case the first line"}}'
+echo '{"jsonrpc":"2.0","id":2,"method":"registerModule","params":{"name":"mock","text":"ready"}}'
+
+events=0
+while read -r line; do
+    case "$line" in
+        *'"method":"command"'*)
+            echo '{"jsonrpc":"2.0","id":3,"method":"getText","params":{"start":0,"end":1}}'
+            ;;
+        *'"id":3,'*'"lines":["hello"]'*)
+            echo '{"jsonrpc":"2.0","id":4,"method":"setText","params":{"start":0,"end":1,"lines":["HELLO","WORLD"]}}'
+            echo '{"jsonrpc":"2.0","method":"showMessage","params":{"text":"shouted"}}'
+            ;;
+        *'"method":"event"'*'"event":"TextChanged"'*)
+            events=$((events + 1))
+            echo '{"jsonrpc":"2.0","id":5,"method":"setModuleText","params":{"name":"mock","text":"'"$events"' changes"}}'
+            ;;
+        *'"method":"shutdown"'*)
+            exit 0
+            ;;
+    esac
+    # an unknown method gets an error back
+    case "$line" in
+        *'"id":5,'*) echo '{"jsonrpc":"2.0","id":6,"method":"noSuchMethod","params":{}}' ;;
+        *'"id":6,'*) echo '{"jsonrpc":"2.0","method":"showMessage","params":{"text":"got an error"}}' ;;
+    esac
+done
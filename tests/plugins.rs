@@ -0,0 +1,126 @@
+use std::time::{Duration, Instant};
+
+use edit::core::editor::plugins::host::{PluginApi, PluginHost};
+use serde_json::json;
+
+// a buffer and a message line in place of the editor
+struct FakeEditor {
+    lines: Vec<String>,
+    messages: Vec<String>,
+}
+
+impl PluginApi for FakeEditor {
+    fn file(&self) -> Option<String> {
+        None
+    }
+
+    fn lines(&self) -> &[String] {
+        &self.lines
+    }
+
+    fn set_lines(&mut self, start: usize, end: usize, lines: Vec<String>) -> Result<(), String> {
+        self.lines.splice(start..end, lines);
+        Ok(())
+    }
+
+    fn show_message(&mut self, text: String) {
+        self.messages.push(text);
+    }
+}
+
+// handles messages of the plugin until `done` holds, for a few seconds at most
+fn wait_for(host: &mut PluginHost, editor: &mut FakeEditor, done: impl Fn(&PluginHost, &FakeEditor) -> bool) {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while !done(host, editor) {
+        assert!(Instant::now() < deadline, "the plugin did not answer in time, messages: {:?}", editor.messages);
+        host.handle_messages(editor, Duration::from_millis(50));
+    }
+}
+
+fn module_text(host: &PluginHost, name: &str) -> Option<String> {
+    host.modules.iter().find(|(n, _)| n == name).map(|(_, text)| text.borrow().clone())
+}
+
+#[test]
+fn mock_plugin_registers_edits_and_gets_events() {
+    let script = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/mock_plugin.sh");
+    let mut host = PluginHost::new();
+    let mut editor = FakeEditor { lines: vec!["hello".to_string(), "there".to_string()], messages: Vec::new() };
+
+    host.start("sh", &[script.to_string()], json!({ "file": null })).unwrap();
+    assert!(host.is_running());
+
+    // it registers a command and a status bar module after `initialize`
+    wait_for(&mut host, &mut editor, |host, _| !host.commands.is_empty() && !host.modules.is_empty());
+    assert_eq!(host.commands[0].name, "Shout");
+    assert_eq!(host.commands[0].help, "Upper case the first line");
+    assert_eq!(module_text(&host, "mock").as_deref(), Some("ready"));
+
+    // the command reads the first line and replaces it by two
+    assert!(!host.run_command("Whisper", "", false, None));
+    assert!(host.run_command("Shout", "", false, None));
+    wait_for(&mut host, &mut editor, |_, editor| editor.messages.iter().any(|m| m == "shouted"));
+    assert_eq!(editor.lines, ["HELLO", "WORLD", "there"]);
+
+    // only the events it subscribed to reach it
+    host.emit("BufRead", json!({}));
+    host.emit("TextChanged", json!({}));
+    wait_for(&mut host, &mut editor, |host, _| module_text(host, "mock").as_deref() == Some("1 changes"));
+
+    // an unknown method is answered with an error
+    wait_for(&mut host, &mut editor, |_, editor| editor.messages.iter().any(|m| m == "got an error"));
+
+    host.stop_all();
+    assert!(!host.is_running());
+    assert_eq!(host.list(), [(format!("sh {}", script), false)]);
+}
+
+#[test]
+fn plugin_that_exits_loses_its_commands() {
+    let mut host = PluginHost::new();
+    let mut editor = FakeEditor { lines: vec![String::new()], messages: Vec::new() };
+    let script = r#"read -r line; echo '{"jsonrpc":"2.0","id":1,"method":"registerCommand","params":{"name":"Bye"}}'; read -r line"#;
+
+    host.start("sh", &["-c".to_string(), script.to_string()], json!({})).unwrap();
+    wait_for(&mut host, &mut editor, |_, editor| editor.messages.iter().any(|m| m.ends_with("exited")));
+
+    assert!(host.commands.is_empty());
+    assert!(!host.is_running());
+}
+
+#[test]
+fn command_names_are_letters_only() {
+    let mut host = PluginHost::new();
+    let mut editor = FakeEditor { lines: vec![String::new()], messages: Vec::new() };
+    // shows the error it gets back for each name
+    let script = r#"read -r line
+for name in Shout2 shout Shout; do
+    echo '{"jsonrpc":"2.0","id":1,"method":"registerCommand","params":{"name":"'$name'"}}'
+    read -r reply
+    case "$reply" in
+        *'"error"'*) message=$(echo "$reply" | sed 's/.*"message":"\([^"]*\)".*/\1/') ;;
+        *) message=ok ;;
+    esac
+    echo '{"jsonrpc":"2.0","method":"showMessage","params":{"text":"'"$message"'"}}'
+done
+read -r line"#;
+
+    host.start("sh", &["-c".to_string(), script.to_string()], json!({})).unwrap();
+    wait_for(&mut host, &mut editor, |_, editor| editor.messages.len() == 3);
+
+    assert_eq!(editor.messages, [
+        "E182: Invalid command name: Shout2",
+        "E183: User defined commands must start with an uppercase letter",
+        "ok",
+    ]);
+    assert_eq!(host.commands.len(), 1);
+    assert_eq!(host.commands[0].name, "Shout");
+    host.stop_all();
+}
+
+#[test]
+fn missing_plugin_is_an_error() {
+    let mut host = PluginHost::new();
+    assert!(host.start("/nonexistent/plugin", &[], json!({})).is_err());
+    assert!(host.list().is_empty());
+}